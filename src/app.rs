@@ -1,42 +1,75 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use adw::prelude::*;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use relm4::prelude::*;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
+use crate::commands::{self, CommandContext};
 use crate::config;
-use crate::models::{Account, Conversation, Message, ProviderId, Role};
+use crate::models::{
+    Account, Assistant, BranchPosition, Conversation, Folder, GenerationParams, Message,
+    MessageSearchHit, PromptPreset, PromptTemplate, ProviderId, Role, Tag,
+};
 use crate::providers::claude::ClaudeProvider;
 use crate::providers::gemini::GeminiProvider;
 use crate::providers::local::LocalProvider;
-use crate::providers::ProviderRouter;
-use crate::services::chat::{self, ChatDispatchParams, StreamResult};
+use crate::providers::openai::OpenAiProvider;
+use crate::providers::{ProviderRouter, RetryNotice, ToolDefinition};
+use crate::services::chat::{self, ChatDispatchParams, FallbackRoute, StreamResult};
+use crate::services::palette::{PaletteAction, PaletteItem, PaletteTarget};
+use crate::services::prompt_library::{self, LibraryFormat};
 use crate::services::settings::AppSettings;
+use crate::services::titles;
+use crate::services::tools::ToolRegistry;
 use crate::services::{AccountService, Database, KeyringService, SettingsService};
-use crate::ui::account_selector::{AccountSelector, AccountSelectorMsg, AccountSelectorOutput};
+use crate::ui::account_selector::{
+    known_models, AccountSelector, AccountSelectorMsg, AccountSelectorOutput,
+};
 use crate::ui::chat_view::{ChatView, ChatViewMsg, ChatViewOutput};
 use crate::ui::dialogs::account_setup::AccountSetupDialog;
-use crate::ui::dialogs::system_prompt::{SystemPromptDialog, SystemPromptInit, SystemPromptOutput};
+use crate::ui::dialogs::assistants::{
+    AssistantsDialog, AssistantsInit, AssistantsMsg, AssistantsOutput,
+};
+use crate::ui::dialogs::command_palette::{
+    CommandPalette, CommandPaletteMsg, CommandPaletteOutput,
+};
+use crate::ui::dialogs::fork::{ForkDialog, ForkInit, ForkOutput};
+use crate::ui::dialogs::prompt_library::{
+    PromptLibraryDialog, PromptLibraryMsg, PromptLibraryOutput,
+};
+use crate::ui::dialogs::system_prompt::{
+    SystemPromptDialog, SystemPromptInit, SystemPromptMsg, SystemPromptOutput,
+};
+use crate::ui::generation_params::{
+    GenerationParamsMsg, GenerationParamsOutput, GenerationParamsPopover,
+};
+use crate::ui::message_widget::set_remote_images_allowed;
 use crate::ui::onboarding::OnboardingWindow;
 use crate::ui::preferences::accounts_page::{AccountsPage, AccountsPageMsg};
 use crate::ui::preferences::appearance_page::{apply_color_scheme, AppearancePage};
-use crate::ui::preferences::chat_page::ChatPage;
-use crate::ui::sidebar::{Sidebar, SidebarMsg, SidebarOutput};
+use crate::ui::preferences::chat_page::{ChatPage, ChatPageMsg};
+use crate::ui::sidebar::{OrganizeAction, Sidebar, SidebarMsg, SidebarOutput};
 
 pub struct App {
     db: Database,
     account_service: Option<AccountService>,
     router: Arc<ProviderRouter>,
+    tools: Arc<ToolRegistry>,
     sidebar: Controller<Sidebar>,
     chat_view: Controller<ChatView>,
     account_selector: Controller<AccountSelector>,
+    generation_params: Controller<GenerationParamsPopover>,
     active_conversation: Option<Conversation>,
     selected_account_id: Option<String>,
     selected_model: Option<String>,
     toast_overlay: adw::ToastOverlay,
     content_stack: gtk::Stack,
+    // Assistants to start a chat from, listed on the empty page
+    assistants: Vec<Assistant>,
+    assistant_picker: gtk::FlowBox,
     initialized: bool,
     preferences_window: Option<adw::PreferencesWindow>,
     account_setup: Option<AsyncController<AccountSetupDialog>>,
@@ -45,9 +78,17 @@ pub struct App {
     appearance_page: Option<Controller<AppearancePage>>,
     onboarding: Option<AsyncController<OnboardingWindow>>,
     system_prompt_dialog: Option<AsyncController<SystemPromptDialog>>,
+    fork_dialog: Option<AsyncController<ForkDialog>>,
+    prompt_library: Option<AsyncController<PromptLibraryDialog>>,
+    assistants_dialog: Option<AsyncController<AssistantsDialog>>,
+    command_palette: Option<AsyncController<CommandPalette>>,
+    // When each palette entry was last chosen, to rank it higher next time
+    palette_history: HashMap<PaletteTarget, DateTime<Utc>>,
     // Streaming state
     stream_cancel_token: Option<CancellationToken>,
     streaming_message_id: Option<String>,
+    // Message to scroll to once its conversation loads: (conversation_id, message_id)
+    pending_scroll_to: Option<(String, String)>,
     // Settings
     settings: AppSettings,
 }
@@ -55,9 +96,26 @@ pub struct App {
 #[derive(Debug)]
 pub enum AppMsg {
     NewChat,
+    NewChatWithAssistant(String), // assistant_id
+    ShowAssistants,
+    SaveAssistant(Box<Assistant>),
+    DeleteAssistant(String),
+    CloseAssistants,
+    SaveTemplate(String, String), // name, content
+    DeleteTemplate(String),       // template_id
+    UseTemplate(String),          // template name
+    RunCommand(String),
+    SelectModel(String),
+    /// Ask before clearing, since it can't be undone.
+    ConfirmClearConversation(String),
+    ClearConversation(String),
     ConversationSelected(String),
     DeleteConversation(String),
-    SendMessage(String, Vec<crate::providers::ImageAttachment>),
+    SendMessage(
+        String,
+        Vec<crate::providers::ImageAttachment>,
+        Vec<crate::providers::DocumentAttachment>,
+    ),
     AccountSelected(String),
     ModelSelected(String),
     InitComplete(Database, KeyringService),
@@ -83,13 +141,39 @@ pub enum AppMsg {
     SettingsChanged(AppSettings),
     ShowSystemPromptDialog,
     SetConversationSystemPrompt(String, Option<String>),
+    ShowPromptLibrary,
+    SavePromptPreset {
+        id: Option<String>,
+        name: String,
+        content: String,
+    },
+    DeletePromptPreset(String),
+    ImportPromptPresets(std::path::PathBuf, LibraryFormat),
+    ExportPromptPresets(std::path::PathBuf, LibraryFormat),
+    ClosePromptLibrary,
+    LoadGenerationParams,
+    SetConversationParams(String, GenerationParams),
     RenameConversation(String, String), // id, new_title
     ExportConversation(String),
     RegenerateMessage(String),   // message_id
     EditMessage(String, String), // message_id, new_content
-    TogglePin(String, bool),     // id, new_pinned_state
+    SwitchBranch(String, usize), // message_id, index of the sibling to show
+    ShowForkDialog(String),      // message_id
+    ForkConversation {
+        message_id: String,
+        account_id: String,
+        model: String,
+    },
+    CloseForkDialog,
+    TogglePin(String, bool), // id, new_pinned_state
+    Organize(OrganizeAction),
     ShowShortcuts,
-    QuickSwitch,
+    ShowCommandPalette,
+    PaletteSearch(String),
+    PaletteChosen(PaletteTarget),
+    ClosePalette,
+    SearchMessages(String),
+    OpenSearchResult(String, String), // conversation_id, message_id
 }
 
 #[derive(Debug)]
@@ -97,15 +181,26 @@ pub enum AppCmd {
     Initialized(Database, KeyringService),
     InitFailed(String),
     ConversationsLoaded(Vec<Conversation>),
+    AssistantsLoaded(Vec<Assistant>),
+    TemplatesLoaded(Vec<PromptTemplate>),
+    FoldersAndTagsLoaded(Vec<Folder>, Vec<Tag>),
+    OrganizeFailed(String),
     AccountsLoaded(Vec<Account>),
     MessagesLoaded(String, Vec<Message>),
+    SearchResults(String, Vec<MessageSearchHit>), // query, hits
+    PaletteSearchResults(String, Vec<MessageSearchHit>), // query, hits
+    TitleGenerated(String, String),               // conversation_id, title
     ChatResponse {
         conversation_id: String,
         content: String,
+        thinking: Option<String>,
         model: String,
         tokens_in: Option<i64>,
         tokens_out: Option<i64>,
+        thinking_tokens: Option<i64>,
         account_id: String,
+        parent_message_id: Option<String>,
+        fallback_error: Option<String>,
     },
     ChatError(String),
     ConversationCreated(Conversation),
@@ -117,22 +212,43 @@ pub enum AppCmd {
     StreamToken {
         _conversation_id: String,
         message_id: String,
-        token: String,
+        token: String, // text since the last token
+    },
+    StreamThinking {
+        _conversation_id: String,
+        message_id: String,
+        thinking: String, // reasoning since the last update
     },
     StreamDone {
         conversation_id: String,
         message_id: String,
         full_content: String,
+        thinking: Option<String>,
         model: String,
         tokens_in: Option<i64>,
         tokens_out: Option<i64>,
+        thinking_tokens: Option<i64>,
         account_id: String,
+        parent_message_id: Option<String>,
     },
     StreamError {
         _conversation_id: String,
         message_id: String,
         error: String,
     },
+    /// The model failed and the request moved on to the fallback `model`.
+    FallingBack {
+        conversation_id: String,
+        message_id: String,
+        model: String,
+        error: String,
+        thinking: String,
+    },
+    /// A failed request will be retried shortly.
+    Retrying {
+        conversation_id: String,
+        notice: RetryNotice,
+    },
     SettingsLoaded(AppSettings),
     LocalModelsDiscovered {
         account_id: String,
@@ -179,17 +295,32 @@ impl AsyncComponent for App {
                 }
                 SidebarOutput::ExportConversation(id) => AppMsg::ExportConversation(id),
                 SidebarOutput::TogglePin(id, pinned) => AppMsg::TogglePin(id, pinned),
+                SidebarOutput::SearchMessages(query) => AppMsg::SearchMessages(query),
+                SidebarOutput::OpenSearchResult(conv_id, msg_id) => {
+                    AppMsg::OpenSearchResult(conv_id, msg_id)
+                }
+                SidebarOutput::Organize(action) => AppMsg::Organize(action),
             });
 
         let chat_view = ChatView::builder()
             .launch(())
             .forward(sender.input_sender(), |output| match output {
-                ChatViewOutput::SendMessage { text, images } => AppMsg::SendMessage(text, images),
+                ChatViewOutput::SendMessage {
+                    text,
+                    images,
+                    documents,
+                } => AppMsg::SendMessage(text, images, documents),
                 ChatViewOutput::StopGeneration => AppMsg::StopGeneration,
                 ChatViewOutput::RegenerateMessage(msg_id) => AppMsg::RegenerateMessage(msg_id),
                 ChatViewOutput::EditMessage(msg_id, content) => {
                     AppMsg::EditMessage(msg_id, content)
                 }
+                ChatViewOutput::SwitchBranch(msg_id, index) => AppMsg::SwitchBranch(msg_id, index),
+                ChatViewOutput::ForkFrom(msg_id) => AppMsg::ShowForkDialog(msg_id),
+                ChatViewOutput::SaveTemplate(name, content) => AppMsg::SaveTemplate(name, content),
+                ChatViewOutput::DeleteTemplate(id) => AppMsg::DeleteTemplate(id),
+                ChatViewOutput::RunCommand(text) => AppMsg::RunCommand(text),
+                ChatViewOutput::AttachFailed(reason) => AppMsg::ShowToast(reason),
             });
 
         let account_selector =
@@ -203,8 +334,10 @@ impl AsyncComponent for App {
         let mut router = ProviderRouter::new();
         router.register(Arc::new(GeminiProvider::new()));
         router.register(Arc::new(ClaudeProvider::new()));
+        router.register(Arc::new(OpenAiProvider::new()));
         router.register(Arc::new(LocalProvider::new()));
         let router = Arc::new(router);
+        let tools = Arc::new(ToolRegistry::with_builtins());
 
         let toast_overlay = adw::ToastOverlay::new();
         toast_overlay.set_hexpand(true);
@@ -230,7 +363,31 @@ impl AsyncComponent for App {
         new_chat_btn.connect_clicked(move |_| {
             sender_btn.send(AppMsg::NewChat).unwrap();
         });
-        empty_page.set_child(Some(&new_chat_btn));
+
+        // Assistant picker, filled in once assistants load
+        let assistant_picker = gtk::FlowBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .halign(gtk::Align::Center)
+            .max_children_per_line(4)
+            .column_spacing(6)
+            .row_spacing(6)
+            .visible(false)
+            .build();
+        let manage_assistants_btn = gtk::Button::builder()
+            .label("Manage Assistants…")
+            .halign(gtk::Align::Center)
+            .build();
+        manage_assistants_btn.add_css_class("flat");
+        let sender_assistants = sender.input_sender().clone();
+        manage_assistants_btn.connect_clicked(move |_| {
+            sender_assistants.send(AppMsg::ShowAssistants).unwrap();
+        });
+
+        let empty_box = gtk::Box::new(gtk::Orientation::Vertical, 18);
+        empty_box.append(&new_chat_btn);
+        empty_box.append(&assistant_picker);
+        empty_box.append(&manage_assistants_btn);
+        empty_page.set_child(Some(&empty_box));
         content_stack.add_named(&empty_page, Some("empty"));
 
         // Chat page
@@ -281,6 +438,23 @@ impl AsyncComponent for App {
         });
         content_header.pack_start(&system_prompt_btn);
 
+        // Generation parameters popover
+        let generation_params = GenerationParamsPopover::builder().launch(()).forward(
+            sender.input_sender(),
+            |output| match output {
+                GenerationParamsOutput::Opened => AppMsg::LoadGenerationParams,
+                GenerationParamsOutput::Changed(id, params) => {
+                    AppMsg::SetConversationParams(id, params)
+                }
+            },
+        );
+        let params_btn = gtk::MenuButton::builder()
+            .icon_name("preferences-other-symbolic")
+            .tooltip_text("Generation Parameters")
+            .popover(generation_params.widget())
+            .build();
+        content_header.pack_start(&params_btn);
+
         let content_toolbar = adw::ToolbarView::new();
         content_toolbar.add_top_bar(&content_header);
         content_toolbar.set_content(Some(&content_stack));
@@ -300,6 +474,8 @@ impl AsyncComponent for App {
 
         // Add hamburger menu to sidebar header
         let menu = gio::Menu::new();
+        menu.append(Some("Assistants"), Some("app.assistants"));
+        menu.append(Some("Prompt Library"), Some("app.prompt-library"));
         menu.append(Some("Preferences"), Some("app.preferences"));
         menu.append(Some("About Echo"), Some("app.about"));
 
@@ -338,14 +514,18 @@ impl AsyncComponent for App {
             db: Database::new_in_memory().expect("placeholder db"),
             account_service: None,
             router,
+            tools,
             sidebar,
             chat_view,
             account_selector,
+            generation_params,
             active_conversation: None,
             selected_account_id: None,
             selected_model: None,
             toast_overlay: toast_overlay.clone(),
             content_stack,
+            assistants: Vec::new(),
+            assistant_picker,
             initialized: false,
             preferences_window: None,
             account_setup: None,
@@ -354,8 +534,14 @@ impl AsyncComponent for App {
             appearance_page: None,
             onboarding: None,
             system_prompt_dialog: None,
+            fork_dialog: None,
+            prompt_library: None,
+            assistants_dialog: None,
+            command_palette: None,
+            palette_history: HashMap::new(),
             stream_cancel_token: None,
             streaming_message_id: None,
+            pending_scroll_to: None,
             settings: AppSettings::default(),
         };
 
@@ -370,6 +556,20 @@ impl AsyncComponent for App {
         });
         app.add_action(&prefs_action);
 
+        let sender_assistants = sender.input_sender().clone();
+        let assistants_action = gio::SimpleAction::new("assistants", None);
+        assistants_action.connect_activate(move |_, _| {
+            sender_assistants.send(AppMsg::ShowAssistants).unwrap();
+        });
+        app.add_action(&assistants_action);
+
+        let sender_library = sender.input_sender().clone();
+        let library_action = gio::SimpleAction::new("prompt-library", None);
+        library_action.connect_activate(move |_, _| {
+            sender_library.send(AppMsg::ShowPromptLibrary).unwrap();
+        });
+        app.add_action(&library_action);
+
         let sender_about = sender.input_sender().clone();
         let about_action = gio::SimpleAction::new("about", None);
         about_action.connect_activate(move |_, _| {
@@ -415,14 +615,14 @@ impl AsyncComponent for App {
         app.add_action(&find_action);
         app.set_accels_for_action("app.find-in-conversation", &["<Control>f"]);
 
-        // Ctrl+K - Quick account/model switch
-        let sender_quick = sender.input_sender().clone();
-        let quick_action = gio::SimpleAction::new("quick-switch", None);
-        quick_action.connect_activate(move |_, _| {
-            sender_quick.send(AppMsg::QuickSwitch).unwrap();
+        // Ctrl+K - Command palette
+        let sender_palette = sender.input_sender().clone();
+        let palette_action = gio::SimpleAction::new("command-palette", None);
+        palette_action.connect_activate(move |_, _| {
+            sender_palette.send(AppMsg::ShowCommandPalette).unwrap();
         });
-        app.add_action(&quick_action);
-        app.set_accels_for_action("app.quick-switch", &["<Control>k"]);
+        app.add_action(&palette_action);
+        app.set_accels_for_action("app.command-palette", &["<Control>k"]);
 
         // Async initialization
         sender.command(|out, _| {
@@ -445,63 +645,145 @@ impl AsyncComponent for App {
     ) {
         match msg {
             AppMsg::NewChat => {
-                if self.selected_account_id.is_none() {
-                    self.show_toast("Please add an account first (use Preferences)");
+                if let Some(conversation) = self.new_conversation(None).await {
+                    self.create_conversation(conversation, &sender);
+                }
+            }
+            AppMsg::NewChatWithAssistant(assistant_id) => {
+                let Some(assistant) = self
+                    .assistants
+                    .iter()
+                    .find(|a| a.id == assistant_id)
+                    .cloned()
+                else {
+                    self.show_toast("Assistant not found");
                     return;
+                };
+                if let Some(conversation) = self.new_conversation(Some(&assistant)).await {
+                    self.create_conversation(conversation, &sender);
                 }
-
-                let account_id = self.selected_account_id.clone().unwrap();
-                let model = self
-                    .selected_model
-                    .clone()
-                    .unwrap_or_else(|| "gemini-2.5-flash".to_string());
-
-                let now = Utc::now();
-                let conversation = Conversation {
-                    id: Uuid::new_v4().to_string(),
-                    account_id,
-                    title: "New Chat".to_string(),
-                    model,
-                    system_prompt: None,
-                    pinned: false,
-                    last_message_preview: None,
-                    created_at: now,
-                    updated_at: now,
+            }
+            AppMsg::ShowAssistants => {
+                let (assistants, accounts) = match (
+                    self.db.list_assistants().await,
+                    self.db.list_accounts().await,
+                ) {
+                    (Ok(assistants), Ok(accounts)) => (assistants, accounts),
+                    (Err(e), _) | (_, Err(e)) => {
+                        self.show_toast(&format!("Failed to load assistants: {}", e));
+                        return;
+                    }
                 };
-
-                let db = self.db.clone();
-                let conv = conversation.clone();
-                sender.command(move |out, _| {
-                    Box::pin(async move {
-                        if let Err(e) = db.insert_conversation(&conv).await {
-                            out.send(AppCmd::ChatError(format!(
-                                "Failed to create conversation: {}",
-                                e
-                            )))
-                            .unwrap();
-                        } else {
-                            out.send(AppCmd::ConversationCreated(conv)).unwrap();
-                        }
+                let dialog = AssistantsDialog::builder()
+                    .launch(AssistantsInit {
+                        assistants,
+                        accounts,
                     })
-                });
+                    .forward(sender.input_sender(), |output| match output {
+                        AssistantsOutput::Save(assistant) => AppMsg::SaveAssistant(assistant),
+                        AssistantsOutput::Delete(id) => AppMsg::DeleteAssistant(id),
+                        AssistantsOutput::StartChat(id) => AppMsg::NewChatWithAssistant(id),
+                        AssistantsOutput::Closed => AppMsg::CloseAssistants,
+                    });
+                dialog.widget().set_transient_for(Some(root));
+                dialog.widget().present();
+                self.assistants_dialog = Some(dialog);
+            }
+            AppMsg::SaveAssistant(assistant) => match self.db.save_assistant(&assistant).await {
+                Ok(()) => self.refresh_assistants(Some(assistant.id), &sender).await,
+                Err(e) => self.show_toast(&format!("Failed to save assistant: {}", e)),
+            },
+            AppMsg::DeleteAssistant(id) => {
+                if let Err(e) = self.db.delete_assistant(&id).await {
+                    self.show_toast(&format!("Failed to delete assistant: {}", e));
+                }
+                self.refresh_assistants(None, &sender).await;
+            }
+            AppMsg::CloseAssistants => {
+                self.assistants_dialog = None;
+            }
+            AppMsg::SaveTemplate(name, content) => {
+                match self.db.save_template(&name, &content).await {
+                    Ok(()) => self.show_toast(&format!("Saved template \"{}\"", name)),
+                    Err(e) => self.show_toast(&format!("Failed to save template: {}", e)),
+                }
+                self.refresh_templates().await;
+            }
+            AppMsg::DeleteTemplate(id) => {
+                if let Err(e) = self.db.delete_template(&id).await {
+                    self.show_toast(&format!("Failed to delete template: {}", e));
+                }
+                self.refresh_templates().await;
+            }
+            AppMsg::UseTemplate(name) => match self.db.list_templates().await {
+                Ok(templates) => {
+                    match templates
+                        .into_iter()
+                        .find(|t| t.name.eq_ignore_ascii_case(&name))
+                    {
+                        Some(template) => {
+                            self.chat_view.emit(ChatViewMsg::InsertTemplate(template))
+                        }
+                        None => self.show_toast(&format!("No template named \"{}\"", name)),
+                    }
+                }
+                Err(e) => self.show_toast(&format!("Failed to load templates: {}", e)),
+            },
+            AppMsg::RunCommand(text) => {
+                let Some((command, args)) = commands::parse(&text) else {
+                    return;
+                };
+                let context = CommandContext {
+                    conversation: self.active_conversation.as_ref(),
+                };
+                match (command.run)(args, &context) {
+                    Ok(msg) => sender.input(msg),
+                    Err(e) => self.show_toast(&e),
+                }
+            }
+            AppMsg::SelectModel(model) => {
+                self.account_selector
+                    .emit(AccountSelectorMsg::SelectModel(model));
+            }
+            AppMsg::ConfirmClearConversation(conv_id) => {
+                let input = sender.input_sender().clone();
+                crate::ui::window::confirm_destructive(
+                    root,
+                    "Clear Conversation?",
+                    "All messages in this conversation will be permanently deleted.",
+                    "Clear",
+                    move || input.emit(AppMsg::ClearConversation(conv_id.clone())),
+                );
+            }
+            AppMsg::ClearConversation(conv_id) => {
+                // The reply being streamed would be saved into the cleared conversation
+                if self.reply_in_progress() {
+                    return;
+                }
+                match self.db.clear_messages(&conv_id).await {
+                    Ok(()) => {
+                        let is_active = self
+                            .active_conversation
+                            .as_ref()
+                            .is_some_and(|c| c.id == conv_id);
+                        if is_active {
+                            self.chat_view.emit(ChatViewMsg::LoadMessages(Vec::new()));
+                        }
+                    }
+                    Err(e) => self.show_toast(&format!("Failed to clear conversation: {}", e)),
+                }
             }
             AppMsg::ConversationSelected(id) => {
                 let db = self.db.clone();
                 let conv_id = id.clone();
                 sender.command(move |out, _| {
                     Box::pin(async move {
-                        match db.list_messages(&conv_id).await {
-                            Ok(mut messages) => {
-                                // Load attachments for user messages
-                                for msg in &mut messages {
-                                    if msg.role == Role::User {
-                                        if let Ok(atts) = db.list_attachments(&msg.id).await {
-                                            if !atts.is_empty() {
-                                                msg.attachments = atts;
-                                            }
-                                        }
-                                    }
-                                }
+                        match crate::services::conversation::load_messages_with_attachments(
+                            &db, &conv_id,
+                        )
+                        .await
+                        {
+                            Ok(messages) => {
                                 out.send(AppCmd::MessagesLoaded(conv_id, messages)).unwrap()
                             }
                             Err(e) => out
@@ -511,6 +793,29 @@ impl AsyncComponent for App {
                     })
                 });
             }
+            AppMsg::SearchMessages(query) => {
+                let db = self.db.clone();
+                sender.command(move |out, _| {
+                    Box::pin(async move {
+                        match db.search_messages(&query).await {
+                            Ok(hits) => out.send(AppCmd::SearchResults(query, hits)).unwrap(),
+                            Err(e) => tracing::error!("Message search failed: {}", e),
+                        }
+                    })
+                });
+            }
+            AppMsg::OpenSearchResult(conv_id, msg_id) => {
+                let is_active = self
+                    .active_conversation
+                    .as_ref()
+                    .is_some_and(|c| c.id == conv_id);
+                if is_active {
+                    self.chat_view.emit(ChatViewMsg::ScrollToMessage(msg_id));
+                } else {
+                    self.pending_scroll_to = Some((conv_id.clone(), msg_id));
+                    sender.input(AppMsg::ConversationSelected(conv_id));
+                }
+            }
             AppMsg::DeleteConversation(id) => match self.db.delete_conversation(&id).await {
                 Ok(()) => {
                     self.sidebar
@@ -527,8 +832,9 @@ impl AsyncComponent for App {
                 }
                 Err(e) => self.show_toast(&format!("Failed to delete: {}", e)),
             },
-            AppMsg::SendMessage(text, images) => {
-                self.handle_send_message(text, images, sender).await;
+            AppMsg::SendMessage(text, images, documents) => {
+                self.handle_send_message(text, images, documents, sender)
+                    .await;
             }
             AppMsg::AccountSelected(id) => {
                 // If there's an active conversation bound to a different account, clear it
@@ -546,6 +852,7 @@ impl AsyncComponent for App {
             }
             AppMsg::ModelSelected(model) => {
                 self.selected_model = Some(model.clone());
+                self.update_vision_support().await;
                 // Update the active conversation's model in memory and DB
                 if let Some(conv) = &mut self.active_conversation {
                     conv.model = model.clone();
@@ -587,6 +894,22 @@ impl AsyncComponent for App {
                             Ok(has) => out.send(AppCmd::NeedsOnboarding(!has)).unwrap(),
                             Err(e) => tracing::error!("Failed to check accounts: {}", e),
                         }
+                        match load_folders_and_tags(&db).await {
+                            Ok((folders, tags)) => out
+                                .send(AppCmd::FoldersAndTagsLoaded(folders, tags))
+                                .unwrap(),
+                            Err(e) => tracing::error!("Failed to load folders and tags: {}", e),
+                        }
+                        match db.list_assistants().await {
+                            Ok(assistants) => {
+                                out.send(AppCmd::AssistantsLoaded(assistants)).unwrap()
+                            }
+                            Err(e) => tracing::error!("Failed to load assistants: {}", e),
+                        }
+                        match db.list_templates().await {
+                            Ok(templates) => out.send(AppCmd::TemplatesLoaded(templates)).unwrap(),
+                            Err(e) => tracing::error!("Failed to load templates: {}", e),
+                        }
                         match db.list_conversations().await {
                             Ok(convos) => out.send(AppCmd::ConversationsLoaded(convos)).unwrap(),
                             Err(e) => tracing::error!("Failed to load conversations: {}", e),
@@ -707,15 +1030,28 @@ impl AsyncComponent for App {
             }
             AppMsg::ShowSystemPromptDialog => {
                 if let Some(conv) = &self.active_conversation {
+                    let presets = self.db.list_prompt_presets().await.unwrap_or_else(|e| {
+                        tracing::error!("Failed to load prompt library: {}", e);
+                        Vec::new()
+                    });
                     let dialog = SystemPromptDialog::builder()
                         .launch(SystemPromptInit {
                             conversation_id: conv.id.clone(),
                             current_prompt: conv.system_prompt.clone(),
+                            presets,
                         })
                         .forward(sender.input_sender(), |output| match output {
                             SystemPromptOutput::Updated(id, prompt) => {
                                 AppMsg::SetConversationSystemPrompt(id, prompt)
                             }
+                            SystemPromptOutput::SaveToLibrary(name, content) => {
+                                AppMsg::SavePromptPreset {
+                                    id: None,
+                                    name,
+                                    content,
+                                }
+                            }
+                            SystemPromptOutput::ManageLibrary => AppMsg::ShowPromptLibrary,
                             SystemPromptOutput::Cancelled => {
                                 AppMsg::ShowToast("".to_string()) // no-op
                             }
@@ -753,13 +1089,134 @@ impl AsyncComponent for App {
                     }
                 }
             }
+            AppMsg::ShowPromptLibrary => {
+                self.system_prompt_dialog = None;
+                let presets = match self.db.list_prompt_presets().await {
+                    Ok(presets) => presets,
+                    Err(e) => {
+                        self.show_toast(&format!("Failed to load prompt library: {}", e));
+                        return;
+                    }
+                };
+                let dialog = PromptLibraryDialog::builder().launch(presets).forward(
+                    sender.input_sender(),
+                    |output| match output {
+                        PromptLibraryOutput::Save { id, name, content } => {
+                            AppMsg::SavePromptPreset { id, name, content }
+                        }
+                        PromptLibraryOutput::Delete(id) => AppMsg::DeletePromptPreset(id),
+                        PromptLibraryOutput::Import(path, format) => {
+                            AppMsg::ImportPromptPresets(path, format)
+                        }
+                        PromptLibraryOutput::Export(path, format) => {
+                            AppMsg::ExportPromptPresets(path, format)
+                        }
+                        PromptLibraryOutput::Closed => AppMsg::ClosePromptLibrary,
+                    },
+                );
+                dialog.widget().set_transient_for(Some(root));
+                dialog.widget().present();
+                self.prompt_library = Some(dialog);
+            }
+            AppMsg::SavePromptPreset { id, name, content } => {
+                let result = match &id {
+                    Some(id) => self
+                        .db
+                        .update_prompt_preset(id, &name, &content)
+                        .await
+                        .map(|()| id.clone()),
+                    None => {
+                        let now = Utc::now();
+                        let preset = PromptPreset {
+                            id: Uuid::new_v4().to_string(),
+                            name,
+                            content,
+                            created_at: now,
+                            updated_at: now,
+                        };
+                        self.db
+                            .insert_prompt_preset(&preset)
+                            .await
+                            .map(|()| preset.id)
+                    }
+                };
+                match result {
+                    Ok(saved_id) => {
+                        self.show_toast("Prompt saved to library");
+                        self.refresh_prompt_presets(Some(saved_id)).await;
+                    }
+                    // Names are unique, so this is usually a clash with another preset
+                    Err(e) => self.show_toast(&format!("Failed to save prompt: {}", e)),
+                }
+            }
+            AppMsg::DeletePromptPreset(id) => {
+                if let Err(e) = self.db.delete_prompt_preset(&id).await {
+                    self.show_toast(&format!("Failed to delete prompt: {}", e));
+                }
+                self.refresh_prompt_presets(None).await;
+            }
+            AppMsg::ImportPromptPresets(path, format) => {
+                let result = match prompt_library::import(&path, format) {
+                    Ok(presets) => self.db.import_prompt_presets(presets).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(count) => {
+                        self.show_toast(&format!("Imported {} prompts", count));
+                        self.refresh_prompt_presets(None).await;
+                    }
+                    Err(e) => self.show_toast(&format!("Import failed: {}", e)),
+                }
+            }
+            AppMsg::ExportPromptPresets(path, format) => {
+                let result = match self.db.list_prompt_presets().await {
+                    Ok(presets) => prompt_library::export(&presets, &path, format),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(count) => self.show_toast(&format!("Exported {} prompts", count)),
+                    Err(e) => self.show_toast(&format!("Export failed: {}", e)),
+                }
+            }
+            AppMsg::ClosePromptLibrary => {
+                self.prompt_library = None;
+            }
+            AppMsg::LoadGenerationParams => {
+                if let Some(conv) = &self.active_conversation {
+                    self.generation_params.emit(GenerationParamsMsg::Load(
+                        conv.id.clone(),
+                        conv.params.clone(),
+                    ));
+                } else {
+                    self.generation_params.widget().popdown();
+                    self.show_toast("No active conversation");
+                }
+            }
+            AppMsg::SetConversationParams(conv_id, params) => {
+                let db = self.db.clone();
+                let params_for_db = params.clone();
+                let cid = conv_id.clone();
+                sender.command(move |_out, _| {
+                    Box::pin(async move {
+                        if let Err(e) = db.update_conversation_params(&cid, &params_for_db).await {
+                            tracing::error!("Failed to update generation parameters: {}", e);
+                        }
+                    })
+                });
+
+                if let Some(conv) = &mut self.active_conversation {
+                    if conv.id == conv_id {
+                        conv.params = params;
+                    }
+                }
+            }
             AppMsg::RenameConversation(id, new_title) => {
                 let db = self.db.clone();
                 let cid = id.clone();
                 let title = new_title.clone();
                 sender.command(move |_out, _| {
                     Box::pin(async move {
-                        if let Err(e) = db.update_conversation_title(&cid, &title).await {
+                        if let Err(e) = db.rename_conversation(&cid, &title).await {
                             tracing::error!("Failed to rename conversation: {}", e);
                         }
                     })
@@ -775,15 +1232,103 @@ impl AsyncComponent for App {
                 self.handle_export_conversation(id, root, sender).await;
             }
             AppMsg::RegenerateMessage(msg_id) => {
+                if self.reply_in_progress() {
+                    return;
+                }
                 self.handle_regenerate(msg_id, sender).await;
             }
             AppMsg::EditMessage(msg_id, new_content) => {
+                if self.reply_in_progress() {
+                    return;
+                }
                 self.handle_edit_message(msg_id, new_content, sender).await;
             }
+            AppMsg::ShowForkDialog(message_id) => {
+                let Some(conv) = &self.active_conversation else {
+                    return;
+                };
+                let accounts = match self.db.list_accounts().await {
+                    Ok(accounts) => accounts,
+                    Err(e) => {
+                        self.show_toast(&format!("Failed to load accounts: {}", e));
+                        return;
+                    }
+                };
+
+                let dialog = ForkDialog::builder()
+                    .launch(ForkInit {
+                        message_id,
+                        accounts,
+                        account_id: conv.account_id.clone(),
+                        model: conv.model.clone(),
+                    })
+                    .forward(sender.input_sender(), |output| match output {
+                        ForkOutput::Fork {
+                            message_id,
+                            account_id,
+                            model,
+                        } => AppMsg::ForkConversation {
+                            message_id,
+                            account_id,
+                            model,
+                        },
+                        ForkOutput::Cancelled => AppMsg::CloseForkDialog,
+                    });
+
+                dialog.widget().set_transient_for(Some(root));
+                dialog.widget().present();
+                self.fork_dialog = Some(dialog);
+            }
+            AppMsg::ForkConversation {
+                message_id,
+                account_id,
+                model,
+            } => {
+                self.fork_dialog = None;
+                let Some(conv) = &self.active_conversation else {
+                    return;
+                };
+                match crate::services::conversation::fork_conversation(
+                    &self.db,
+                    conv,
+                    &message_id,
+                    &account_id,
+                    &model,
+                )
+                .await
+                {
+                    Ok(fork) => {
+                        self.sidebar
+                            .emit(SidebarMsg::AddConversation(Box::new(fork.clone())));
+                        sender.input(AppMsg::ConversationSelected(fork.id));
+                    }
+                    Err(e) => self.show_toast(&format!("Failed to fork conversation: {}", e)),
+                }
+            }
+            AppMsg::CloseForkDialog => {
+                self.fork_dialog = None;
+            }
+            AppMsg::SwitchBranch(msg_id, index) => {
+                if self.reply_in_progress() {
+                    return;
+                }
+                let Some(conv) = &self.active_conversation else {
+                    return;
+                };
+                match crate::services::conversation::switch_branch(
+                    &self.db, &conv.id, &msg_id, index,
+                )
+                .await
+                {
+                    Ok(messages) => self.chat_view.emit(ChatViewMsg::LoadMessages(messages)),
+                    Err(e) => self.show_toast(&format!("Failed to switch branch: {}", e)),
+                }
+            }
             AppMsg::SettingsChanged(settings) => {
                 self.settings = settings.clone();
                 // Apply color scheme immediately
                 apply_color_scheme(settings.color_scheme);
+                set_remote_images_allowed(settings.load_remote_images);
                 // Persist settings
                 let db = self.db.clone();
                 sender.command(move |_out, _| {
@@ -795,6 +1340,11 @@ impl AsyncComponent for App {
                 });
             }
             AppMsg::TogglePin(id, pinned) => {
+                if let Some(conv) = &mut self.active_conversation {
+                    if conv.id == id {
+                        conv.pinned = pinned;
+                    }
+                }
                 let db = self.db.clone();
                 let cid = id.clone();
                 sender.command(move |out, _| {
@@ -810,11 +1360,102 @@ impl AsyncComponent for App {
                     })
                 });
             }
+            AppMsg::Organize(action) => {
+                let db = self.db.clone();
+                sender.command(move |out, _| {
+                    Box::pin(async move {
+                        if let Err(e) = organize(&db, action).await {
+                            tracing::error!("Failed to organize conversations: {}", e);
+                            out.send(AppCmd::OrganizeFailed(e.to_string())).unwrap();
+                        }
+                        // Reload so the sidebar shows the new folders, tags and assignments
+                        match load_folders_and_tags(&db).await {
+                            Ok((folders, tags)) => out
+                                .send(AppCmd::FoldersAndTagsLoaded(folders, tags))
+                                .unwrap(),
+                            Err(e) => tracing::error!("Failed to reload folders and tags: {}", e),
+                        }
+                        match db.list_conversations().await {
+                            Ok(convos) => out.send(AppCmd::ConversationsLoaded(convos)).unwrap(),
+                            Err(e) => tracing::error!("Failed to reload conversations: {}", e),
+                        }
+                    })
+                });
+            }
             AppMsg::ShowShortcuts => {
                 crate::ui::window::create_shortcuts_window(root);
             }
-            AppMsg::QuickSwitch => {
-                self.account_selector.emit(AccountSelectorMsg::GrabFocus);
+            AppMsg::ShowCommandPalette => {
+                if let Some(palette) = &self.command_palette {
+                    palette.widget().present();
+                    return;
+                }
+                let items = self.palette_items().await;
+                let palette = CommandPalette::builder().launch(items).forward(
+                    sender.input_sender(),
+                    |output| match output {
+                        CommandPaletteOutput::SearchMessages(query) => AppMsg::PaletteSearch(query),
+                        CommandPaletteOutput::Chosen(target) => AppMsg::PaletteChosen(target),
+                        CommandPaletteOutput::Closed => AppMsg::ClosePalette,
+                    },
+                );
+                palette.widget().set_transient_for(Some(root));
+                palette.widget().present();
+                self.command_palette = Some(palette);
+            }
+            AppMsg::PaletteSearch(query) => {
+                let db = self.db.clone();
+                sender.command(move |out, _| {
+                    Box::pin(async move {
+                        match db.search_messages(&query).await {
+                            Ok(hits) => {
+                                out.send(AppCmd::PaletteSearchResults(query, hits)).unwrap()
+                            }
+                            Err(e) => tracing::error!("Message search failed: {}", e),
+                        }
+                    })
+                });
+            }
+            AppMsg::PaletteChosen(target) => {
+                self.palette_history.insert(target.clone(), Utc::now());
+                match target {
+                    PaletteTarget::Conversation(id) => {
+                        sender.input(AppMsg::ConversationSelected(id));
+                    }
+                    PaletteTarget::Message {
+                        conversation_id,
+                        message_id,
+                    } => {
+                        sender.input(AppMsg::OpenSearchResult(conversation_id, message_id));
+                    }
+                    PaletteTarget::Model { account_id, model } => {
+                        self.account_selector
+                            .emit(AccountSelectorMsg::SelectAccountModel(account_id, model));
+                    }
+                    PaletteTarget::Action(action) => {
+                        let conv = self.active_conversation.as_ref();
+                        let msg = match action {
+                            PaletteAction::NewChat => Some(AppMsg::NewChat),
+                            PaletteAction::Preferences => Some(AppMsg::ShowPreferences),
+                            PaletteAction::ExportConversation => {
+                                conv.map(|c| AppMsg::ExportConversation(c.id.clone()))
+                            }
+                            PaletteAction::TogglePin => {
+                                conv.map(|c| AppMsg::TogglePin(c.id.clone(), !c.pinned))
+                            }
+                            PaletteAction::SystemPrompt => Some(AppMsg::ShowSystemPromptDialog),
+                            PaletteAction::PromptLibrary => Some(AppMsg::ShowPromptLibrary),
+                            PaletteAction::Assistants => Some(AppMsg::ShowAssistants),
+                            PaletteAction::Shortcuts => Some(AppMsg::ShowShortcuts),
+                        };
+                        if let Some(msg) = msg {
+                            sender.input(msg);
+                        }
+                    }
+                }
+            }
+            AppMsg::ClosePalette => {
+                self.command_palette = None;
             }
         }
     }
@@ -836,6 +1477,19 @@ impl AsyncComponent for App {
                 self.sidebar
                     .emit(SidebarMsg::LoadConversations(conversations));
             }
+            AppCmd::AssistantsLoaded(assistants) => {
+                self.set_assistants(assistants, &sender);
+            }
+            AppCmd::TemplatesLoaded(templates) => {
+                self.chat_view.emit(ChatViewMsg::SetTemplates(templates));
+            }
+            AppCmd::FoldersAndTagsLoaded(folders, tags) => {
+                self.sidebar
+                    .emit(SidebarMsg::SetFoldersAndTags(folders, tags));
+            }
+            AppCmd::OrganizeFailed(err) => {
+                self.show_toast(&format!("Error: {}", err));
+            }
             AppCmd::AccountsLoaded(accounts) => {
                 if !accounts.is_empty() {
                     let default = accounts.iter().find(|a| a.is_default).or(accounts.first());
@@ -848,6 +1502,14 @@ impl AsyncComponent for App {
                         .emit(AccountSelectorMsg::SetAccounts(accounts));
                 }
             }
+            AppCmd::SearchResults(query, hits) => {
+                self.sidebar.emit(SidebarMsg::SetSearchResults(query, hits));
+            }
+            AppCmd::PaletteSearchResults(query, hits) => {
+                if let Some(palette) = &self.command_palette {
+                    palette.emit(CommandPaletteMsg::SetMessageHits(query, hits));
+                }
+            }
             AppCmd::MessagesLoaded(conv_id, messages) => {
                 // Load the full conversation from DB to get system_prompt etc.
                 match self.db.get_conversation(&conv_id).await {
@@ -861,6 +1523,7 @@ impl AsyncComponent for App {
                         self.selected_account_id = Some(conv.account_id.clone());
                         self.selected_model = Some(conv.model.clone());
                         self.active_conversation = Some(conv);
+                        self.update_vision_support().await;
                     }
                     _ => {
                         self.active_conversation = Some(Conversation {
@@ -872,7 +1535,11 @@ impl AsyncComponent for App {
                                 .clone()
                                 .unwrap_or_else(|| "gemini-2.5-flash".to_string()),
                             system_prompt: None,
+                            params: GenerationParams::default(),
                             pinned: false,
+                            folder_id: None,
+                            tags: Vec::new(),
+                            assistant_id: None,
                             last_message_preview: None,
                             created_at: Utc::now(),
                             updated_at: Utc::now(),
@@ -881,52 +1548,75 @@ impl AsyncComponent for App {
                 }
                 self.chat_view.emit(ChatViewMsg::LoadMessages(messages));
                 self.content_stack.set_visible_child_name("chat");
+                if let Some((pending_conv, msg_id)) = self.pending_scroll_to.take() {
+                    if pending_conv == conv_id {
+                        self.chat_view.emit(ChatViewMsg::ScrollToMessage(msg_id));
+                    }
+                }
             }
             AppCmd::ChatResponse {
                 conversation_id,
                 content,
+                thinking,
                 model,
                 tokens_in,
                 tokens_out,
+                thinking_tokens,
                 account_id,
+                parent_message_id,
+                fallback_error,
             } => {
+                if let Some(error) = fallback_error {
+                    self.show_fallback_toast(&model, &error);
+                }
+
                 let now = Utc::now();
-                let assistant_msg = Message {
+                let mut assistant_msg = Message {
                     id: Uuid::new_v4().to_string(),
                     conversation_id: conversation_id.clone(),
                     role: Role::Assistant,
                     content,
+                    thinking,
                     model: Some(model),
                     tokens_in,
                     tokens_out,
-                    parent_message_id: None,
+                    thinking_tokens,
+                    parent_message_id,
                     is_active: true,
                     created_at: now,
                     attachments: Vec::new(),
+                    branch: None,
                 };
 
                 if let Err(e) = self.db.insert_message(&assistant_msg).await {
                     tracing::error!("Failed to save assistant message: {}", e);
                 }
+                assistant_msg.branch = self
+                    .branch_position(&conversation_id, &assistant_msg.id)
+                    .await;
 
                 let _ = self
                     .db
                     .update_conversation_timestamp(&conversation_id)
                     .await;
 
+                // Reasoning is billed as output
                 if let (Some(ti), Some(to)) = (tokens_in, tokens_out) {
+                    let to = to + thinking_tokens.unwrap_or(0);
                     let _ = self.db.update_account_usage(&account_id, ti, to).await;
                 }
 
                 self.chat_view.emit(ChatViewMsg::AddMessage(assistant_msg));
                 self.chat_view.emit(ChatViewMsg::SetLoading(false));
+                self.generate_title(&conversation_id, &sender).await;
             }
             AppCmd::ChatError(err) => {
                 self.show_toast(&err);
                 self.chat_view.emit(ChatViewMsg::SetLoading(false));
             }
             AppCmd::ConversationCreated(conv) => {
-                self.sidebar.emit(SidebarMsg::AddConversation(conv.clone()));
+                self.sidebar
+                    .emit(SidebarMsg::AddConversation(Box::new(conv.clone())));
                 // Sync dropdowns to the new conversation's account/model
                 self.account_selector
                     .emit(AccountSelectorMsg::SyncToConversation(
@@ -936,6 +1626,7 @@ impl AsyncComponent for App {
                 self.selected_account_id = Some(conv.account_id.clone());
                 self.selected_model = Some(conv.model.clone());
                 self.active_conversation = Some(conv);
+                self.update_vision_support().await;
                 self.chat_view.emit(ChatViewMsg::Clear);
                 self.content_stack.set_visible_child_name("chat");
             }
@@ -969,6 +1660,9 @@ impl AsyncComponent for App {
                 if let Some(page) = &self.accounts_page {
                     page.emit(AccountsPageMsg::SetAccounts(accounts.clone()));
                 }
+                if let Some(page) = &self.chat_page {
+                    page.emit(ChatPageMsg::SetAccounts(accounts.clone()));
+                }
                 if !accounts.is_empty() {
                     let default = accounts.iter().find(|a| a.is_default).or(accounts.first());
                     if let Some(acc) = default {
@@ -989,16 +1683,60 @@ impl AsyncComponent for App {
                 token,
             } => {
                 self.chat_view
-                    .emit(ChatViewMsg::UpdateStreamingMessage(message_id, token));
+                    .emit(ChatViewMsg::AppendStreamingText(message_id, token));
+            }
+            AppCmd::StreamThinking {
+                _conversation_id: _,
+                message_id,
+                thinking,
+            } => {
+                self.chat_view
+                    .emit(ChatViewMsg::AppendStreamingThinking(message_id, thinking));
+            }
+            AppCmd::FallingBack {
+                conversation_id,
+                message_id,
+                model,
+                error,
+                thinking,
+            } => {
+                let is_active = self
+                    .active_conversation
+                    .as_ref()
+                    .is_some_and(|c| c.id == conversation_id);
+                if is_active {
+                    self.show_fallback_toast(&model, &error);
+                    self.chat_view.emit(ChatViewMsg::SetStreamingThinking(
+                        message_id.clone(),
+                        thinking,
+                    ));
+                    self.chat_view
+                        .emit(ChatViewMsg::SetStreamingModel(message_id, model));
+                }
+            }
+            AppCmd::Retrying {
+                conversation_id,
+                notice,
+            } => {
+                let is_active = self
+                    .active_conversation
+                    .as_ref()
+                    .is_some_and(|c| c.id == conversation_id);
+                if is_active {
+                    self.chat_view.emit(ChatViewMsg::ShowRetry(notice));
+                }
             }
             AppCmd::StreamDone {
                 conversation_id,
                 message_id,
                 full_content,
+                thinking,
                 model,
                 tokens_in,
                 tokens_out,
+                thinking_tokens,
                 account_id,
+                parent_message_id,
             } => {
                 self.stream_cancel_token = None;
                 self.streaming_message_id = None;
@@ -1010,13 +1748,16 @@ impl AsyncComponent for App {
                     conversation_id: conversation_id.clone(),
                     role: Role::Assistant,
                     content: full_content,
+                    thinking,
                     model: Some(model),
                     tokens_in,
                     tokens_out,
-                    parent_message_id: None,
+                    thinking_tokens,
+                    parent_message_id,
                     is_active: true,
                     created_at: now,
                     attachments: Vec::new(),
+                    branch: None,
                 };
 
                 if let Err(e) = self.db.insert_message(&assistant_msg).await {
@@ -1028,16 +1769,35 @@ impl AsyncComponent for App {
                     .update_conversation_timestamp(&conversation_id)
                     .await;
 
+                // Reasoning is billed as output
                 if let (Some(ti), Some(to)) = (tokens_in, tokens_out) {
+                    let to = to + thinking_tokens.unwrap_or(0);
                     let _ = self.db.update_account_usage(&account_id, ti, to).await;
                 }
 
                 self.chat_view
                     .emit(ChatViewMsg::StreamingComplete(message_id.clone()));
+                if let Some(branch) = self.branch_position(&conversation_id, &message_id).await {
+                    self.chat_view
+                        .emit(ChatViewMsg::SetMessageBranch(message_id.clone(), branch));
+                }
                 self.chat_view.emit(ChatViewMsg::SetMessageTokens(
-                    message_id, tokens_in, tokens_out,
+                    message_id,
+                    tokens_in,
+                    tokens_out,
+                    thinking_tokens,
                 ));
                 self.chat_view.emit(ChatViewMsg::SetLoading(false));
+                self.generate_title(&conversation_id, &sender).await;
+            }
+            AppCmd::TitleGenerated(conversation_id, title) => {
+                if let Some(conv) = &mut self.active_conversation {
+                    if conv.id == conversation_id {
+                        conv.title = title.clone();
+                    }
+                }
+                self.sidebar
+                    .emit(SidebarMsg::UpdateConversationTitle(conversation_id, title));
             }
             AppCmd::StreamError {
                 _conversation_id: _,
@@ -1054,6 +1814,7 @@ impl AsyncComponent for App {
             AppCmd::SettingsLoaded(settings) => {
                 self.settings = settings;
                 apply_color_scheme(self.settings.color_scheme);
+                set_remote_images_allowed(self.settings.load_remote_images);
             }
             AppCmd::LocalModelsDiscovered { account_id, models } => {
                 self.account_selector
@@ -1070,6 +1831,311 @@ impl App {
         Ok((db, keyring))
     }
 
+    /// Tell the input area whether the selected model can see image attachments.
+    async fn update_vision_support(&self) {
+        let (Some(account_id), Some(model)) = (&self.selected_account_id, &self.selected_model)
+        else {
+            return;
+        };
+
+        let supported = match self.db.get_account(account_id).await {
+            Ok(Some(account)) => self.router.supports_vision(&account.provider, model),
+            _ => true,
+        };
+        self.chat_view
+            .emit(ChatViewMsg::SetVisionSupported(supported));
+    }
+
+    fn show_fallback_toast(&self, model: &str, error: &str) {
+        self.show_toast(&format!("{}; answered by fallback model {}", error, model));
+    }
+
+    /// After a conversation's first reply, ask the title model for a better title in
+    /// the background. Conversations the user renamed keep their title.
+    async fn generate_title(&self, conversation_id: &str, sender: &AsyncComponentSender<Self>) {
+        if !self.settings.auto_title {
+            return;
+        }
+        let Some(account_service) = &self.account_service else {
+            return;
+        };
+        let Ok(Some(conv)) = self.db.get_conversation(conversation_id).await else {
+            return;
+        };
+        let Ok(messages) = self.db.list_messages(conversation_id).await else {
+            return;
+        };
+        let mut replies = messages.iter().filter(|m| m.role == Role::Assistant);
+        let (Some(reply), None) = (replies.next(), replies.next()) else {
+            return;
+        };
+        let Some(question) = messages.iter().find(|m| m.role == Role::User) else {
+            return;
+        };
+
+        let (account_id, model) = match &self.settings.title_model {
+            Some(target) => (target.account_id.as_str(), target.model.as_str()),
+            None => (conv.account_id.as_str(), conv.model.as_str()),
+        };
+        let (account, api_key) = match account_service.get_account_with_key(account_id).await {
+            Ok(pair) => pair,
+            Err(e) => {
+                tracing::warn!("No account for title generation: {}", e);
+                return;
+            }
+        };
+        let request =
+            titles::title_request(api_key, &account, model, &question.content, &reply.content);
+
+        let router = self.router.clone();
+        let db = self.db.clone();
+        let conversation_id = conversation_id.to_string();
+        sender.command(move |out, _| {
+            Box::pin(async move {
+                let title = match titles::generate_title(router, account.provider, request).await {
+                    Ok(title) => title,
+                    Err(e) => {
+                        tracing::warn!("Title generation failed: {}", e);
+                        return;
+                    }
+                };
+                match db.update_conversation_title(&conversation_id, &title).await {
+                    Ok(true) => {
+                        let _ = out.send(AppCmd::TitleGenerated(conversation_id, title));
+                    }
+                    Ok(false) => {}
+                    Err(e) => tracing::error!("Failed to save generated title: {}", e),
+                }
+            })
+        });
+    }
+
+    /// A new conversation on the selected account and model, or set up from `assistant`.
+    /// `None` when there is no account to use.
+    async fn new_conversation(&self, assistant: Option<&Assistant>) -> Option<Conversation> {
+        // The assistant's own account, while it still exists
+        let assistant_account = match assistant.and_then(|a| a.account_id.as_ref()) {
+            Some(id) => self.db.get_account(id).await.ok().flatten(),
+            None => None,
+        };
+        // The assistant's model only makes sense with its account; a deleted account
+        // leaves `account_id` unset but the model in place
+        let (account_id, model) = match assistant_account {
+            Some(account) => (
+                account.id,
+                assistant
+                    .and_then(|a| a.model.clone())
+                    .unwrap_or(account.default_model),
+            ),
+            None => {
+                let Some(account_id) = self.selected_account_id.clone() else {
+                    self.show_toast("Please add an account first (use Preferences)");
+                    return None;
+                };
+                if assistant.is_some_and(|a| a.model.is_some()) {
+                    self.show_toast(
+                        "The assistant's account is gone, so the selected model is used instead",
+                    );
+                }
+                let model = self
+                    .selected_model
+                    .clone()
+                    .unwrap_or_else(|| "gemini-2.5-flash".to_string());
+                (account_id, model)
+            }
+        };
+
+        let now = Utc::now();
+        Some(Conversation {
+            id: Uuid::new_v4().to_string(),
+            account_id,
+            title: "New Chat".to_string(),
+            model,
+            system_prompt: assistant.and_then(|a| a.system_prompt.clone()),
+            params: GenerationParams {
+                temperature: assistant.and_then(|a| a.temperature),
+                ..GenerationParams::default()
+            },
+            pinned: false,
+            folder_id: None,
+            tags: Vec::new(),
+            assistant_id: assistant.map(|a| a.id.clone()),
+            last_message_preview: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Save a new conversation and switch to it.
+    fn create_conversation(&self, conversation: Conversation, sender: &AsyncComponentSender<Self>) {
+        let db = self.db.clone();
+        sender.command(move |out, _| {
+            Box::pin(async move {
+                if let Err(e) = db.insert_conversation(&conversation).await {
+                    out.send(AppCmd::ChatError(format!(
+                        "Failed to create conversation: {}",
+                        e
+                    )))
+                    .unwrap();
+                } else {
+                    out.send(AppCmd::ConversationCreated(conversation)).unwrap();
+                }
+            })
+        });
+    }
+
+    /// The conversation's system prompt, or the global default, with the reference files
+    /// of the assistant it was started from.
+    async fn system_prompt_for(&self, conv: &Conversation) -> Option<String> {
+        let system_prompt = conv
+            .system_prompt
+            .clone()
+            .or_else(|| self.settings.default_system_prompt.clone())
+            .filter(|s| !s.trim().is_empty());
+
+        let Some(assistant_id) = &conv.assistant_id else {
+            return system_prompt;
+        };
+        let files = self
+            .db
+            .list_assistant_files(assistant_id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to load reference files: {}", e);
+                Vec::new()
+            });
+        chat::with_reference_files(system_prompt, files).await
+    }
+
+    /// Show `assistants` in the empty page's picker.
+    fn set_assistants(&mut self, assistants: Vec<Assistant>, sender: &AsyncComponentSender<Self>) {
+        while let Some(child) = self.assistant_picker.first_child() {
+            self.assistant_picker.remove(&child);
+        }
+        for assistant in &assistants {
+            let button = gtk::Button::builder()
+                .label(format!("{}  {}", assistant.icon, assistant.name))
+                .tooltip_text(format!("New chat with {}", assistant.name))
+                .build();
+            button.add_css_class("pill");
+            let input = sender.input_sender().clone();
+            let id = assistant.id.clone();
+            button.connect_clicked(move |_| {
+                input
+                    .send(AppMsg::NewChatWithAssistant(id.clone()))
+                    .unwrap();
+            });
+            self.assistant_picker.append(&button);
+        }
+        self.assistant_picker.set_visible(!assistants.is_empty());
+        self.assistants = assistants;
+    }
+
+    /// Reload assistants into the picker and the assistants dialog, if open.
+    async fn refresh_assistants(
+        &mut self,
+        select: Option<String>,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        let assistants = match self.db.list_assistants().await {
+            Ok(assistants) => assistants,
+            Err(e) => {
+                tracing::error!("Failed to load assistants: {}", e);
+                return;
+            }
+        };
+        if let Some(dialog) = &self.assistants_dialog {
+            dialog.emit(AssistantsMsg::SetAssistants(assistants.clone(), select));
+        }
+        self.set_assistants(assistants, sender);
+    }
+
+    /// Everything the command palette can jump to: conversations, each account's
+    /// models, and app actions, with when each was last used.
+    async fn palette_items(&self) -> Vec<PaletteItem> {
+        let last_used = |target: &PaletteTarget| self.palette_history.get(target).copied();
+        let mut items = Vec::new();
+
+        let conversations = self.db.list_conversations().await.unwrap_or_else(|e| {
+            tracing::error!("Failed to load conversations: {}", e);
+            Vec::new()
+        });
+        for conv in conversations {
+            let target = PaletteTarget::Conversation(conv.id.clone());
+            let chosen = last_used(&target);
+            items.push(PaletteItem {
+                title: conv.title,
+                subtitle: conv.last_message_preview.unwrap_or(conv.model),
+                last_used: chosen.max(Some(conv.updated_at)),
+                target,
+            });
+        }
+
+        let accounts = self.db.list_accounts().await.unwrap_or_else(|e| {
+            tracing::error!("Failed to load accounts: {}", e);
+            Vec::new()
+        });
+        for account in accounts {
+            let mut models = vec![account.default_model.clone()];
+            for model in known_models(account.provider) {
+                if !models.iter().any(|m| m == model) {
+                    models.push(model.to_string());
+                }
+            }
+            for model in models {
+                let target = PaletteTarget::Model {
+                    account_id: account.id.clone(),
+                    model: model.clone(),
+                };
+                items.push(PaletteItem {
+                    title: model,
+                    subtitle: account.label.clone(),
+                    last_used: last_used(&target),
+                    target,
+                });
+            }
+        }
+
+        for action in PaletteAction::ALL {
+            if action.needs_conversation() && self.active_conversation.is_none() {
+                continue;
+            }
+            let target = PaletteTarget::Action(action);
+            items.push(PaletteItem {
+                title: action.title().to_string(),
+                subtitle: String::new(),
+                last_used: last_used(&target),
+                target,
+            });
+        }
+        items
+    }
+
+    /// Reload the message templates into the input area's picker.
+    async fn refresh_templates(&self) {
+        match self.db.list_templates().await {
+            Ok(templates) => self.chat_view.emit(ChatViewMsg::SetTemplates(templates)),
+            Err(e) => tracing::error!("Failed to load templates: {}", e),
+        }
+    }
+
+    /// Send the current prompt library to any open dialog showing it.
+    async fn refresh_prompt_presets(&self, select: Option<String>) {
+        let presets = match self.db.list_prompt_presets().await {
+            Ok(presets) => presets,
+            Err(e) => {
+                tracing::error!("Failed to load prompt library: {}", e);
+                return;
+            }
+        };
+        if let Some(dialog) = &self.system_prompt_dialog {
+            dialog.emit(SystemPromptMsg::SetPresets(presets.clone()));
+        }
+        if let Some(dialog) = &self.prompt_library {
+            dialog.emit(PromptLibraryMsg::SetPresets(presets, select));
+        }
+    }
+
     fn show_toast(&self, message: &str) {
         let toast = adw::Toast::new(message);
         toast.set_timeout(3);
@@ -1211,6 +2277,7 @@ impl App {
         &mut self,
         text: String,
         images: Vec<crate::providers::ImageAttachment>,
+        documents: Vec<crate::providers::DocumentAttachment>,
         sender: AsyncComponentSender<Self>,
     ) {
         if self.active_conversation.is_none() {
@@ -1234,7 +2301,11 @@ impl App {
                 title,
                 model,
                 system_prompt: None,
+                params: GenerationParams::default(),
                 pinned: false,
+                folder_id: None,
+                tags: Vec::new(),
+                assistant_id: None,
                 last_message_preview: None,
                 created_at: now,
                 updated_at: now,
@@ -1246,7 +2317,7 @@ impl App {
             }
 
             self.sidebar
-                .emit(SidebarMsg::AddConversation(conversation.clone()));
+                .emit(SidebarMsg::AddConversation(Box::new(conversation.clone())));
             self.active_conversation = Some(conversation);
             self.content_stack.set_visible_child_name("chat");
         }
@@ -1256,16 +2327,24 @@ impl App {
 
         let now = Utc::now();
         let user_msg_id = Uuid::new_v4().to_string();
+        let parent_message_id = self.reply_parent_id(&conversation_id).await;
+        let is_first_message = parent_message_id.is_none();
 
         // Build attachments for display in the message bubble
         let msg_attachments: Vec<crate::models::Attachment> = images
             .iter()
-            .map(|img| crate::models::Attachment {
-                id: String::new(),
+            .map(|img| (&img.mime_type, None, &img.data))
+            .chain(
+                documents
+                    .iter()
+                    .map(|doc| (&doc.mime_type, Some(&doc.filename), &doc.data)),
+            )
+            .map(|(mime_type, filename, data)| crate::models::Attachment {
+                id: Uuid::new_v4().to_string(),
                 message_id: user_msg_id.clone(),
-                mime_type: img.mime_type.clone(),
-                filename: None,
-                data: img.data.clone(),
+                mime_type: mime_type.clone(),
+                filename: filename.cloned(),
+                data: data.clone(),
                 created_at: now,
             })
             .collect();
@@ -1275,13 +2354,16 @@ impl App {
             conversation_id: conversation_id.clone(),
             role: Role::User,
             content: text.clone(),
+            thinking: None,
             model: None,
             tokens_in: None,
             tokens_out: None,
-            parent_message_id: None,
+            thinking_tokens: None,
+            parent_message_id,
             is_active: true,
             created_at: now,
             attachments: msg_attachments,
+            branch: None,
         };
 
         if let Err(e) = self.db.insert_message(&user_msg).await {
@@ -1289,27 +2371,37 @@ impl App {
             return;
         }
 
+        // Save attachments to DB
+        for attachment in &user_msg.attachments {
+            if let Err(e) = self.db.insert_attachment(attachment).await {
+                tracing::error!("Failed to save attachment: {}", e);
+            }
+        }
+
         self.chat_view.emit(ChatViewMsg::AddMessage(user_msg));
         self.chat_view.emit(ChatViewMsg::SetLoading(true));
 
-        let is_first_message = match self.db.list_messages(&conversation_id).await {
-            Ok(msgs) => msgs.len() == 1,
-            Err(_) => false,
-        };
-
         if is_first_message {
+            // A title the user already gave an empty conversation stays
             let title = truncate_title(&text);
-            let _ = self
+            if let Ok(true) = self
                 .db
                 .update_conversation_title(&conversation_id, &title)
-                .await;
-            self.sidebar.emit(SidebarMsg::UpdateConversationTitle(
-                conversation_id.clone(),
-                title,
-            ));
+                .await
+            {
+                self.sidebar.emit(SidebarMsg::UpdateConversationTitle(
+                    conversation_id.clone(),
+                    title,
+                ));
+            }
         }
 
-        let all_messages = match self.db.list_messages(&conversation_id).await {
+        let all_messages = match crate::services::conversation::load_messages_with_attachments(
+            &self.db,
+            &conversation_id,
+        )
+        .await
+        {
             Ok(msgs) => msgs,
             Err(e) => {
                 self.show_toast(&format!("Failed to load messages: {}", e));
@@ -1320,28 +2412,6 @@ impl App {
 
         let mut chat_messages = chat::messages_to_chat_messages(&all_messages);
 
-        // Attach images to the last (current user) message
-        if !images.is_empty() {
-            if let Some(last_msg) = chat_messages.last_mut() {
-                last_msg.images = images.clone();
-            }
-
-            // Save attachments to DB
-            for img in &images {
-                let attachment = crate::models::Attachment {
-                    id: Uuid::new_v4().to_string(),
-                    message_id: user_msg_id.clone(),
-                    mime_type: img.mime_type.clone(),
-                    filename: None,
-                    data: img.data.clone(),
-                    created_at: Utc::now(),
-                };
-                if let Err(e) = self.db.insert_attachment(&attachment).await {
-                    tracing::error!("Failed to save attachment: {}", e);
-                }
-            }
-        }
-
         let account_service = match &self.account_service {
             Some(s) => s,
             None => {
@@ -1361,11 +2431,13 @@ impl App {
             }
         };
 
-        let system_prompt = conv
-            .system_prompt
-            .clone()
-            .or_else(|| self.settings.default_system_prompt.clone())
-            .filter(|s| !s.trim().is_empty());
+        // Text files, and PDFs the model can't read natively, go into the prompt text
+        let native_pdf = self.router.supports_pdf(&account.provider, &conv.model);
+        for message in &mut chat_messages {
+            chat::inline_documents(message, native_pdf).await;
+        }
+
+        let system_prompt = self.system_prompt_for(conv).await;
 
         let request = chat::build_request(
             api_key,
@@ -1374,6 +2446,8 @@ impl App {
             &account,
             &self.settings,
             system_prompt,
+            &conv.params,
+            self.tool_definitions(),
         );
 
         let params = ChatDispatchParams {
@@ -1382,6 +2456,9 @@ impl App {
             conversation_id: conversation_id.clone(),
             account_id: conv.account_id.clone(),
             model_name: conv.model.clone(),
+            parent_message_id: Some(user_msg_id),
+            tools: self.settings.enable_tools.then(|| self.tools.clone()),
+            fallbacks: self.fallback_routes(&conv.account_id, &conv.model).await,
         };
 
         self.dispatch_ai_request(params, sender);
@@ -1476,13 +2553,15 @@ impl App {
             }
         };
 
-        let system_prompt = conv
-            .system_prompt
-            .clone()
-            .or_else(|| self.settings.default_system_prompt.clone())
-            .filter(|s| !s.trim().is_empty());
+        let system_prompt = self.system_prompt_for(conv).await;
+
+        // Files attached earlier in the conversation are sent again with their messages
+        let mut chat_messages = chat::messages_to_chat_messages(&messages);
+        let native_pdf = self.router.supports_pdf(&account.provider, &conv.model);
+        for message in &mut chat_messages {
+            chat::inline_documents(message, native_pdf).await;
+        }
 
-        let chat_messages = chat::messages_to_chat_messages(&messages);
         let request = chat::build_request(
             api_key,
             &conv.model,
@@ -1490,6 +2569,8 @@ impl App {
             &account,
             &self.settings,
             system_prompt,
+            &conv.params,
+            self.tool_definitions(),
         );
 
         let params = ChatDispatchParams {
@@ -1498,12 +2579,82 @@ impl App {
             conversation_id: conv.id.clone(),
             account_id: conv.account_id.clone(),
             model_name: conv.model.clone(),
+            parent_message_id: messages.last().map(|m| m.id.clone()),
+            tools: self.settings.enable_tools.then(|| self.tools.clone()),
+            fallbacks: self.fallback_routes(&conv.account_id, &conv.model).await,
         };
 
         self.chat_view.emit(ChatViewMsg::SetLoading(true));
         self.dispatch_ai_request(params, sender);
     }
 
+    /// Whether a reply is still streaming, telling the user to wait if so. Changing the
+    /// conversation's messages meanwhile would leave the reply out of place.
+    fn reply_in_progress(&self) -> bool {
+        let streaming = self.streaming_message_id.is_some();
+        if streaming {
+            self.show_toast("Wait for the reply to finish first");
+        }
+        streaming
+    }
+
+    /// The message a new message in `conversation_id` replies to: the end of the active path.
+    async fn reply_parent_id(&self, conversation_id: &str) -> Option<String> {
+        crate::services::conversation::active_leaf_id(&self.db, conversation_id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to find the active message: {}", e);
+                None
+            })
+    }
+
+    async fn branch_position(
+        &self,
+        conversation_id: &str,
+        message_id: &str,
+    ) -> Option<BranchPosition> {
+        crate::services::conversation::load_branch_position(&self.db, conversation_id, message_id)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Resolve the configured fallback chain, skipping the model already being tried.
+    async fn fallback_routes(&self, account_id: &str, model: &str) -> Vec<FallbackRoute> {
+        let Some(account_service) = &self.account_service else {
+            return Vec::new();
+        };
+
+        let mut routes = Vec::new();
+        for target in &self.settings.fallback_chain {
+            if target.account_id == account_id && target.model == model {
+                continue;
+            }
+            match account_service
+                .get_account_with_key(&target.account_id)
+                .await
+            {
+                Ok((account, api_key)) => routes.push(FallbackRoute {
+                    provider: account.provider,
+                    account_id: account.id,
+                    model: target.model.clone(),
+                    api_key,
+                    base_url: account.api_base_url,
+                }),
+                Err(e) => tracing::warn!("Skipping fallback {}: {}", target.model, e),
+            }
+        }
+        routes
+    }
+
+    fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        if self.settings.enable_tools {
+            self.tools.definitions()
+        } else {
+            Vec::new()
+        }
+    }
+
     fn dispatch_ai_request(
         &mut self,
         params: ChatDispatchParams,
@@ -1520,13 +2671,16 @@ impl App {
                 conversation_id: params.conversation_id.clone(),
                 role: Role::Assistant,
                 content: String::new(),
+                thinking: None,
                 model: Some(params.model_name.clone()),
                 tokens_in: None,
                 tokens_out: None,
-                parent_message_id: None,
+                thinking_tokens: None,
+                parent_message_id: params.parent_message_id.clone(),
                 is_active: true,
                 created_at: Utc::now(),
                 attachments: Vec::new(),
+                branch: None,
             };
             self.chat_view
                 .emit(ChatViewMsg::AddStreamingMessage(placeholder));
@@ -1541,12 +2695,50 @@ impl App {
                             StreamResult::Token {
                                 conversation_id,
                                 message_id,
-                                accumulated,
+                                delta,
                             } => {
                                 out.send(AppCmd::StreamToken {
                                     _conversation_id: conversation_id,
                                     message_id,
-                                    token: accumulated,
+                                    token: delta,
+                                })
+                                .unwrap();
+                            }
+                            StreamResult::Thinking {
+                                conversation_id,
+                                message_id,
+                                delta,
+                            } => {
+                                out.send(AppCmd::StreamThinking {
+                                    _conversation_id: conversation_id,
+                                    message_id,
+                                    thinking: delta,
+                                })
+                                .unwrap();
+                            }
+                            StreamResult::FallingBack {
+                                conversation_id,
+                                message_id,
+                                model,
+                                error,
+                                thinking,
+                            } => {
+                                out.send(AppCmd::FallingBack {
+                                    conversation_id,
+                                    message_id,
+                                    model,
+                                    error,
+                                    thinking,
+                                })
+                                .unwrap();
+                            }
+                            StreamResult::Retrying {
+                                conversation_id,
+                                notice,
+                            } => {
+                                out.send(AppCmd::Retrying {
+                                    conversation_id,
+                                    notice,
                                 })
                                 .unwrap();
                             }
@@ -1554,19 +2746,25 @@ impl App {
                                 conversation_id,
                                 message_id,
                                 full_content,
+                                thinking,
                                 model,
                                 tokens_in,
                                 tokens_out,
+                                thinking_tokens,
                                 account_id,
+                                parent_message_id,
                             } => {
                                 out.send(AppCmd::StreamDone {
                                     conversation_id,
                                     message_id,
                                     full_content,
+                                    thinking,
                                     model,
                                     tokens_in,
                                     tokens_out,
+                                    thinking_tokens,
                                     account_id,
+                                    parent_message_id,
                                 })
                                 .unwrap();
                             }
@@ -1590,15 +2788,29 @@ impl App {
         } else {
             sender.command(move |out, _| {
                 Box::pin(async move {
-                    match chat::send_non_streaming(router, params).await {
+                    let conversation_id = params.conversation_id.clone();
+                    let retry_out = out.clone();
+                    let on_retry = move |notice| {
+                        retry_out
+                            .send(AppCmd::Retrying {
+                                conversation_id: conversation_id.clone(),
+                                notice,
+                            })
+                            .unwrap();
+                    };
+                    match chat::send_non_streaming(router, params, on_retry).await {
                         Ok(result) => {
                             out.send(AppCmd::ChatResponse {
                                 conversation_id: result.conversation_id,
                                 content: result.content,
+                                thinking: result.thinking,
                                 model: result.model,
                                 tokens_in: result.tokens_in,
                                 tokens_out: result.tokens_out,
+                                thinking_tokens: result.thinking_tokens,
                                 account_id: result.account_id,
+                                parent_message_id: result.parent_message_id,
+                                fallback_error: result.fallback_error,
                             })
                             .unwrap();
                         }
@@ -1612,4 +2824,49 @@ impl App {
     }
 }
 
+async fn load_folders_and_tags(db: &Database) -> anyhow::Result<(Vec<Folder>, Vec<Tag>)> {
+    Ok((db.list_folders().await?, db.list_tags().await?))
+}
+
+/// Apply a folder or tag change from the sidebar.
+async fn organize(db: &Database, action: OrganizeAction) -> anyhow::Result<()> {
+    match action {
+        OrganizeAction::CreateFolder(name, conversation_id) => {
+            let folder = Folder {
+                id: Uuid::new_v4().to_string(),
+                name,
+                created_at: Utc::now(),
+            };
+            db.insert_folder(&folder).await?;
+            if let Some(conversation_id) = conversation_id {
+                db.set_conversation_folder(&conversation_id, Some(&folder.id))
+                    .await?;
+            }
+        }
+        OrganizeAction::RenameFolder(id, name) => db.rename_folder(&id, &name).await?,
+        OrganizeAction::DeleteFolder(id) => db.delete_folder(&id).await?,
+        OrganizeAction::MoveToFolder(conversation_id, folder_id) => {
+            db.set_conversation_folder(&conversation_id, folder_id.as_deref())
+                .await?
+        }
+        OrganizeAction::CreateTag(name, conversation_id) => {
+            let tag = Tag {
+                id: Uuid::new_v4().to_string(),
+                name,
+            };
+            db.insert_tag(&tag).await?;
+            if let Some(conversation_id) = conversation_id {
+                db.set_conversation_tag(&conversation_id, &tag.id, true)
+                    .await?;
+            }
+        }
+        OrganizeAction::DeleteTag(id) => db.delete_tag(&id).await?,
+        OrganizeAction::SetTag(conversation_id, tag_id, tagged) => {
+            db.set_conversation_tag(&conversation_id, &tag_id, tagged)
+                .await?
+        }
+    }
+    Ok(())
+}
+
 use crate::services::conversation::truncate_title;
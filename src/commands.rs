@@ -0,0 +1,197 @@
+use crate::app::AppMsg;
+use crate::models::{Conversation, GenerationParams};
+
+/// What a command's handler can see of the app when it runs.
+pub struct CommandContext<'a> {
+    pub conversation: Option<&'a Conversation>,
+}
+
+/// A command typed into the input area as `/name args`.
+pub struct SlashCommand {
+    pub name: &'static str,
+    /// Argument hint shown in completions; empty for commands without arguments.
+    pub args: &'static str,
+    pub description: &'static str,
+    /// Turns the (trimmed) arguments into the message to dispatch, or an error to show.
+    pub run: fn(&str, &CommandContext) -> Result<AppMsg, String>,
+}
+
+/// Every slash command, in the order completions list them.
+pub const COMMANDS: &[SlashCommand] = &[
+    SlashCommand {
+        name: "new",
+        args: "",
+        description: "Start a new conversation",
+        run: |_, _| Ok(AppMsg::NewChat),
+    },
+    SlashCommand {
+        name: "clear",
+        args: "",
+        description: "Delete all messages in this conversation",
+        run: |_, ctx| Ok(AppMsg::ConfirmClearConversation(active(ctx)?.id.clone())),
+    },
+    SlashCommand {
+        name: "model",
+        args: "<name>",
+        description: "Switch the model for this conversation",
+        run: |args, _| Ok(AppMsg::SelectModel(required(args, "/model <name>")?)),
+    },
+    SlashCommand {
+        name: "system",
+        args: "[prompt]",
+        description: "Set the system prompt, or edit it when no prompt is given",
+        run: |args, ctx| {
+            if args.is_empty() {
+                Ok(AppMsg::ShowSystemPromptDialog)
+            } else {
+                Ok(AppMsg::SetConversationSystemPrompt(
+                    active(ctx)?.id.clone(),
+                    Some(args.to_string()),
+                ))
+            }
+        },
+    },
+    SlashCommand {
+        name: "temp",
+        args: "[0–2]",
+        description: "Set the temperature, or reset it when no value is given",
+        run: |args, ctx| {
+            let conv = active(ctx)?;
+            let temperature = if args.is_empty() {
+                None
+            } else {
+                match args.parse::<f32>() {
+                    Ok(t) if (0.0..=2.0).contains(&t) => Some(t),
+                    _ => return Err("Temperature must be a number from 0 to 2".to_string()),
+                }
+            };
+            Ok(AppMsg::SetConversationParams(
+                conv.id.clone(),
+                GenerationParams {
+                    temperature,
+                    ..conv.params.clone()
+                },
+            ))
+        },
+    },
+    SlashCommand {
+        name: "template",
+        args: "<name>",
+        description: "Insert a saved message template",
+        run: |args, _| Ok(AppMsg::UseTemplate(required(args, "/template <name>")?)),
+    },
+    SlashCommand {
+        name: "export",
+        args: "",
+        description: "Export this conversation to Markdown",
+        run: |_, ctx| Ok(AppMsg::ExportConversation(active(ctx)?.id.clone())),
+    },
+];
+
+/// The command `text` invokes and its trimmed arguments. `None` when the text isn't
+/// a known command, so messages like `/etc/hosts is empty?` are sent as typed.
+pub fn parse(text: &str) -> Option<(&'static SlashCommand, &str)> {
+    let rest = text.trim().strip_prefix('/')?;
+    let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    COMMANDS
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(name))
+        .map(|c| (c, args.trim()))
+}
+
+/// Commands whose name starts with the partial `/name` being typed.
+pub fn completions(text: &str) -> Vec<&'static SlashCommand> {
+    let Some(prefix) = text.strip_prefix('/') else {
+        return Vec::new();
+    };
+    if prefix.contains(char::is_whitespace) {
+        return Vec::new();
+    }
+    let prefix = prefix.to_lowercase();
+    COMMANDS
+        .iter()
+        .filter(|c| c.name.starts_with(&prefix))
+        .collect()
+}
+
+fn active<'a>(ctx: &CommandContext<'a>) -> Result<&'a Conversation, String> {
+    ctx.conversation
+        .ok_or_else(|| "No active conversation".to_string())
+}
+
+fn required(args: &str, usage: &str) -> Result<String, String> {
+    if args.is_empty() {
+        Err(format!("Usage: {}", usage))
+    } else {
+        Ok(args.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn conversation() -> Conversation {
+        Conversation {
+            id: "c1".to_string(),
+            account_id: "a1".to_string(),
+            title: "Test".to_string(),
+            model: "model".to_string(),
+            system_prompt: None,
+            params: GenerationParams {
+                max_tokens: Some(100),
+                ..Default::default()
+            },
+            pinned: false,
+            folder_id: None,
+            tags: Vec::new(),
+            assistant_id: None,
+            last_message_preview: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn run(text: &str, conversation: Option<&Conversation>) -> Result<AppMsg, String> {
+        let (command, args) = parse(text).expect("not a command");
+        (command.run)(args, &CommandContext { conversation })
+    }
+
+    #[test]
+    fn test_parse() {
+        let (command, args) = parse("  /System  Be brief.  ").unwrap();
+        assert_eq!((command.name, args), ("system", "Be brief."));
+        assert!(parse("/etc/hosts is empty?").is_none());
+        assert!(parse("hello /new").is_none());
+    }
+
+    #[test]
+    fn test_completions() {
+        let names = |text| completions(text).iter().map(|c| c.name).collect::<Vec<_>>();
+        assert_eq!(names("/te"), vec!["temp", "template"]);
+        assert_eq!(names("/").len(), COMMANDS.len());
+        assert!(names("/temp 1").is_empty());
+        assert!(names("temp").is_empty());
+    }
+
+    #[test]
+    fn test_run() {
+        let conv = conversation();
+        match run("/temp 0.3", Some(&conv)) {
+            Ok(AppMsg::SetConversationParams(id, params)) => {
+                assert_eq!(id, "c1");
+                assert_eq!(params.temperature, Some(0.3));
+                assert_eq!(params.max_tokens, Some(100));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(run("/temp 3", Some(&conv)).is_err());
+        assert!(run("/export", None).is_err());
+        assert!(run("/model", Some(&conv)).is_err());
+        assert!(matches!(
+            run("/template Code review", None),
+            Ok(AppMsg::UseTemplate(name)) if name == "Code review"
+        ));
+    }
+}
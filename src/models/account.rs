@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 pub enum ProviderId {
     Gemini,
     Claude,
+    OpenAi,
     Local,
 }
 
@@ -13,6 +14,7 @@ impl ProviderId {
         match self {
             ProviderId::Gemini => "gemini",
             ProviderId::Claude => "claude",
+            ProviderId::OpenAi => "openai",
             ProviderId::Local => "local",
         }
     }
@@ -21,6 +23,7 @@ impl ProviderId {
         match self {
             ProviderId::Gemini => "Google Gemini",
             ProviderId::Claude => "Anthropic Claude",
+            ProviderId::OpenAi => "OpenAI",
             ProviderId::Local => "Local (OpenAI Compatible)",
         }
     }
@@ -29,6 +32,7 @@ impl ProviderId {
         match s {
             "gemini" => Some(ProviderId::Gemini),
             "claude" => Some(ProviderId::Claude),
+            "openai" => Some(ProviderId::OpenAi),
             "local" => Some(ProviderId::Local),
             _ => None,
         }
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A reusable chat setup. Chats started from an assistant take its system prompt,
+/// account, model and temperature, and send its reference files with every request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assistant {
+    pub id: String,
+    pub name: String,
+    /// An emoji or short text shown next to the name.
+    pub icon: String,
+    pub system_prompt: Option<String>,
+    /// `None` uses whichever account and model are selected.
+    pub account_id: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub files: Vec<AssistantFile>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A text or PDF file an assistant gives the model as reference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssistantFile {
+    pub id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::folder::Tag;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
     pub id: String,
@@ -8,8 +10,25 @@ pub struct Conversation {
     pub title: String,
     pub model: String,
     pub system_prompt: Option<String>,
+    pub params: GenerationParams,
     pub pinned: bool,
+    pub folder_id: Option<String>,
+    pub tags: Vec<Tag>,
+    /// Assistant the chat was started from, whose reference files go with each request.
+    pub assistant_id: Option<String>,
     pub last_message_preview: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// Per-conversation sampling parameters. `None` (or empty) fields use the
+/// global setting or the provider's default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationParams {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub stop_sequences: Vec<String>,
+    pub seed: Option<i64>,
+}
@@ -32,12 +32,45 @@ pub struct Message {
     pub conversation_id: String,
     pub role: Role,
     pub content: String,
+    /// Reasoning the model produced before answering, if any.
+    pub thinking: Option<String>,
     pub model: Option<String>,
     pub tokens_in: Option<i64>,
     pub tokens_out: Option<i64>,
+    /// Reasoning tokens, for providers that count them apart from `tokens_out`.
+    #[serde(default)]
+    pub thinking_tokens: Option<i64>,
     pub parent_message_id: Option<String>,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     #[serde(skip)]
     pub attachments: Vec<Attachment>,
+    /// Set when other edits or regenerations share this message's parent.
+    #[serde(skip)]
+    pub branch: Option<BranchPosition>,
+}
+
+/// Where a message sits among the alternatives sharing its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchPosition {
+    /// Zero-based, oldest alternative first.
+    pub index: usize,
+    pub count: usize,
 }
+
+/// A message matching a full-text search.
+#[derive(Debug, Clone)]
+pub struct MessageSearchHit {
+    pub message_id: String,
+    pub conversation_id: String,
+    pub conversation_title: String,
+    pub role: Role,
+    /// Excerpt around the match, with matched terms wrapped in
+    /// `SNIPPET_MATCH_START` / `SNIPPET_MATCH_END`.
+    pub snippet: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Markers around matched terms in `MessageSearchHit::snippet`.
+pub const SNIPPET_MATCH_START: char = '\u{2}';
+pub const SNIPPET_MATCH_END: char = '\u{3}';
@@ -1,9 +1,19 @@
 pub mod account;
+pub mod assistant;
 pub mod attachment;
 pub mod conversation;
+pub mod folder;
 pub mod message;
+pub mod prompt_preset;
+pub mod prompt_template;
 
 pub use account::{Account, AccountStatus, ProviderId};
+pub use assistant::{Assistant, AssistantFile};
 pub use attachment::Attachment;
-pub use conversation::Conversation;
-pub use message::{Message, Role};
+pub use conversation::{Conversation, GenerationParams};
+pub use folder::{Folder, Tag};
+pub use message::{
+    BranchPosition, Message, MessageSearchHit, Role, SNIPPET_MATCH_END, SNIPPET_MATCH_START,
+};
+pub use prompt_preset::PromptPreset;
+pub use prompt_template::PromptTemplate;
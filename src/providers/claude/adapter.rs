@@ -5,12 +5,15 @@ use tokio::sync::mpsc;
 
 use super::models::*;
 use crate::models::{ProviderId, Role};
+use crate::providers::retry::{retry_after_secs, status_error};
 use crate::providers::traits::AiProvider;
 use crate::providers::types::*;
 
 const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 const DEFAULT_MAX_TOKENS: u32 = 8192;
+/// Smallest thinking budget the API accepts.
+const MIN_THINKING_BUDGET: u32 = 1024;
 
 const FALLBACK_MODELS: &[(&str, &str)] = &[
     ("claude-opus-4-0-20250514", "Claude Opus 4"),
@@ -52,42 +55,172 @@ impl ClaudeProvider {
         messages
             .iter()
             .map(|msg| {
-                if msg.images.is_empty() {
+                if msg.images.is_empty()
+                    && msg.documents.is_empty()
+                    && msg.tool_calls.is_empty()
+                    && msg.tool_results.is_empty()
+                    && msg.thinking.is_none()
+                {
                     // Text-only: use simple string content
-                    ClaudeMessage {
+                    return ClaudeMessage {
                         role: Self::translate_role(&msg.role).to_string(),
                         content: ClaudeContent::Text(msg.content.clone()),
-                    }
-                } else {
-                    // Has images: use content block array
-                    let mut blocks = Vec::new();
-
-                    for img in &msg.images {
-                        let b64 = base64::engine::general_purpose::STANDARD.encode(&img.data);
-                        blocks.push(ClaudeContentBlock::Image {
-                            source: ClaudeImageSource {
-                                source_type: "base64".to_string(),
-                                media_type: img.mime_type.clone(),
-                                data: b64,
-                            },
-                        });
-                    }
+                    };
+                }
 
+                // Attachments or tool traffic: use content block array.
+                // Tool results must come first in a user turn.
+                let mut blocks = Vec::new();
+
+                // Signed thinking must lead an assistant turn that calls tools
+                if let Some(ThinkingBlock {
+                    text,
+                    signature: Some(signature),
+                }) = &msg.thinking
+                {
+                    blocks.push(ClaudeContentBlock::Thinking {
+                        thinking: text.clone(),
+                        signature: signature.clone(),
+                    });
+                }
+
+                for result in &msg.tool_results {
+                    blocks.push(ClaudeContentBlock::ToolResult {
+                        tool_use_id: result.tool_call_id.clone(),
+                        content: result.content.clone(),
+                        is_error: result.is_error,
+                    });
+                }
+
+                for img in &msg.images {
+                    let b64 = base64::engine::general_purpose::STANDARD.encode(&img.data);
+                    blocks.push(ClaudeContentBlock::Image {
+                        source: ClaudeImageSource {
+                            source_type: "base64".to_string(),
+                            media_type: img.mime_type.clone(),
+                            data: b64,
+                        },
+                    });
+                }
+
+                for doc in &msg.documents {
+                    let b64 = base64::engine::general_purpose::STANDARD.encode(&doc.data);
+                    blocks.push(ClaudeContentBlock::Document {
+                        source: ClaudeImageSource {
+                            source_type: "base64".to_string(),
+                            media_type: doc.mime_type.clone(),
+                            data: b64,
+                        },
+                        title: Some(doc.filename.clone()),
+                    });
+                }
+
+                if !msg.content.is_empty() {
                     blocks.push(ClaudeContentBlock::Text {
                         text: msg.content.clone(),
                     });
+                }
+
+                for call in &msg.tool_calls {
+                    blocks.push(ClaudeContentBlock::ToolUse {
+                        id: call.id.clone(),
+                        name: call.name.clone(),
+                        input: call.arguments.clone(),
+                    });
+                }
 
-                    ClaudeMessage {
-                        role: Self::translate_role(&msg.role).to_string(),
-                        content: ClaudeContent::Blocks(blocks),
-                    }
+                ClaudeMessage {
+                    role: Self::translate_role(&msg.role).to_string(),
+                    content: ClaudeContent::Blocks(blocks),
                 }
             })
             .collect()
     }
 
+    fn build_tools(tools: &[ToolDefinition]) -> Vec<ClaudeTool> {
+        tools
+            .iter()
+            .map(|tool| ClaudeTool {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                input_schema: tool.parameters.clone(),
+            })
+            .collect()
+    }
+
+    /// Claude 3 models before 3.7 have no extended thinking.
+    fn supports_thinking(model: &str) -> bool {
+        let legacy = model.starts_with("claude-3-") && !model.starts_with("claude-3-7");
+        !legacy && !model.contains("haiku-3")
+    }
+
+    /// PDF input arrived with Claude 3.5; older Claude 3 models lack it.
+    fn is_pdf_model(model: &str) -> bool {
+        !model.starts_with("claude-3-")
+            || model.starts_with("claude-3-5")
+            || model.starts_with("claude-3-7")
+    }
+
+    fn features(model: &str) -> Vec<Feature> {
+        let mut features = vec![Feature::Chat, Feature::Vision, Feature::FunctionCalling];
+        if Self::supports_thinking(model) {
+            features.push(Feature::ExtendedThinking);
+        }
+        if Self::is_pdf_model(model) {
+            features.push(Feature::PdfInput);
+        }
+        features
+    }
+
+    fn thinking_budget(request: &ChatRequest) -> Option<u32> {
+        request
+            .thinking_budget
+            .filter(|budget| *budget > 0 && Self::supports_thinking(&request.model))
+            .map(|budget| budget.max(MIN_THINKING_BUDGET))
+    }
+
+    /// The budget counts against `max_tokens`, so leave room for the answer on top of it.
     fn max_tokens(request: &ChatRequest) -> u32 {
-        request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)
+        let max_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        match Self::thinking_budget(request) {
+            Some(budget) => max_tokens.max(budget + DEFAULT_MAX_TOKENS),
+            None => max_tokens,
+        }
+    }
+
+    fn build_request(request: &ChatRequest, stream: bool) -> ClaudeRequest {
+        let thinking = Self::thinking_budget(request).map(|budget| ClaudeThinkingConfig {
+            thinking_type: "enabled".to_string(),
+            budget_tokens: budget,
+        });
+
+        ClaudeRequest {
+            model: request.model.clone(),
+            max_tokens: Self::max_tokens(request),
+            messages: Self::build_messages(&request.messages),
+            system: request.system_prompt.clone(),
+            // Thinking is incompatible with a custom temperature or top_k,
+            // and only allows top_p between 0.95 and 1
+            temperature: if thinking.is_some() {
+                None
+            } else {
+                request.temperature
+            },
+            top_p: if thinking.is_some() {
+                request.top_p.map(|p| p.clamp(0.95, 1.0))
+            } else {
+                request.top_p
+            },
+            top_k: if thinking.is_some() {
+                None
+            } else {
+                request.top_k
+            },
+            stop_sequences: request.stop_sequences.clone(),
+            stream: stream.then_some(true),
+            tools: Self::build_tools(&request.tools),
+            thinking,
+        }
     }
 
     fn fallback_models() -> Vec<ModelInfo> {
@@ -96,7 +229,7 @@ impl ClaudeProvider {
             .map(|(id, name)| ModelInfo {
                 id: id.to_string(),
                 name: name.to_string(),
-                features: vec![Feature::Chat, Feature::Vision],
+                features: Self::features(id),
             })
             .collect()
     }
@@ -108,6 +241,10 @@ impl AiProvider for ClaudeProvider {
         ProviderId::Claude
     }
 
+    fn supports_pdf(&self, model: &str) -> bool {
+        Self::is_pdf_model(model)
+    }
+
     async fn validate_credentials(
         &self,
         api_key: &str,
@@ -146,9 +283,9 @@ impl AiProvider for ClaudeProvider {
                         m.display_name
                     };
                     ModelInfo {
+                        features: Self::features(&m.id),
                         id: m.id,
                         name,
-                        features: vec![Feature::Chat, Feature::Vision],
                     }
                 })
                 .collect();
@@ -176,16 +313,7 @@ impl AiProvider for ClaudeProvider {
         let base = Self::base_url(request.base_url.as_deref());
         let url = format!("{}/messages", base);
 
-        let messages = Self::build_messages(&request.messages);
-
-        let claude_request = ClaudeRequest {
-            model: request.model.clone(),
-            max_tokens: Self::max_tokens(&request),
-            messages,
-            system: request.system_prompt.clone(),
-            temperature: request.temperature,
-            stream: None,
-        };
+        let claude_request = Self::build_request(&request, false);
 
         let response = self
             .client
@@ -204,18 +332,15 @@ impl AiProvider for ClaudeProvider {
             return Err(ProviderError::AuthError("Invalid API key".to_string()));
         }
 
-        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            return Err(ProviderError::RateLimited {
-                retry_after_secs: None,
-            });
-        }
-
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = retry_after_secs(status, response.headers());
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::RequestFailed(Self::parse_error_message(
-                status, &body,
-            )));
+            return Err(status_error(
+                status,
+                Self::parse_error_message(status, &body),
+                retry_after,
+            ));
         }
 
         let claude_response: ClaudeResponse = response
@@ -223,16 +348,31 @@ impl AiProvider for ClaudeProvider {
             .await
             .map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
 
-        let content = claude_response
-            .content
-            .into_iter()
-            .map(|block| match block {
-                ClaudeResponseBlock::Text { text } => text,
-            })
-            .collect::<Vec<_>>()
-            .join("");
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        let mut thinking = None;
+        for block in claude_response.content {
+            match block {
+                ClaudeResponseBlock::Text { text } => content.push_str(&text),
+                ClaudeResponseBlock::Thinking {
+                    thinking: text,
+                    signature,
+                } => {
+                    thinking = Some(ThinkingBlock {
+                        text,
+                        signature: Some(signature).filter(|s| !s.is_empty()),
+                    });
+                }
+                ClaudeResponseBlock::ToolUse { id, name, input } => tool_calls.push(ToolCall {
+                    id,
+                    name,
+                    arguments: input,
+                }),
+                ClaudeResponseBlock::Other => {}
+            }
+        }
 
-        if content.is_empty() {
+        if content.is_empty() && tool_calls.is_empty() {
             return Err(ProviderError::InvalidResponse(
                 "No content in response".to_string(),
             ));
@@ -245,9 +385,12 @@ impl AiProvider for ClaudeProvider {
 
         Ok(ChatResponse {
             content,
+            tool_calls,
+            thinking,
             model: request.model,
             tokens_in,
             tokens_out,
+            thinking_tokens: None,
         })
     }
 
@@ -261,16 +404,7 @@ impl AiProvider for ClaudeProvider {
         let base = Self::base_url(request.base_url.as_deref());
         let url = format!("{}/messages", base);
 
-        let messages = Self::build_messages(&request.messages);
-
-        let claude_request = ClaudeRequest {
-            model: request.model.clone(),
-            max_tokens: Self::max_tokens(&request),
-            messages,
-            system: request.system_prompt.clone(),
-            temperature: request.temperature,
-            stream: Some(true),
-        };
+        let claude_request = Self::build_request(&request, true);
 
         let response = self
             .client
@@ -289,18 +423,15 @@ impl AiProvider for ClaudeProvider {
             return Err(ProviderError::AuthError("Invalid API key".to_string()));
         }
 
-        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            return Err(ProviderError::RateLimited {
-                retry_after_secs: None,
-            });
-        }
-
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = retry_after_secs(status, response.headers());
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::RequestFailed(Self::parse_error_message(
-                status, &body,
-            )));
+            return Err(status_error(
+                status,
+                Self::parse_error_message(status, &body),
+                retry_after,
+            ));
         }
 
         parse_sse_stream(response, tx).await;
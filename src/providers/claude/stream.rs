@@ -1,8 +1,16 @@
 use futures::StreamExt;
 use tokio::sync::mpsc;
 
-use super::models::{ClaudeDelta, ClaudeStreamEvent};
-use crate::providers::types::StreamEvent;
+use super::models::{ClaudeDelta, ClaudeResponseBlock, ClaudeStreamEvent};
+use crate::providers::types::{StreamEvent, ToolCall};
+
+/// A `tool_use` block whose input JSON is still arriving.
+struct PendingToolUse {
+    index: u32,
+    id: String,
+    name: String,
+    input_json: String,
+}
 
 pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<StreamEvent>) {
     let mut stream = response.bytes_stream();
@@ -10,6 +18,7 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
     let mut buffer = String::new();
     let mut tokens_in: Option<i64> = None;
     let mut tokens_out: Option<i64> = None;
+    let mut tool_use: Option<PendingToolUse> = None;
 
     while let Some(chunk_result) = stream.next().await {
         let bytes = match chunk_result {
@@ -74,11 +83,54 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
                             tokens_in = usage.input_tokens;
                         }
                     }
-                    ClaudeStreamEvent::ContentBlockDelta {
-                        delta: ClaudeDelta::TextDelta { text },
-                        ..
+                    ClaudeStreamEvent::ContentBlockStart {
+                        index,
+                        content_block: ClaudeResponseBlock::ToolUse { id, name, .. },
                     } => {
-                        if tx.send(StreamEvent::Token(text)).await.is_err() {
+                        tool_use = Some(PendingToolUse {
+                            index,
+                            id,
+                            name,
+                            input_json: String::new(),
+                        });
+                    }
+                    ClaudeStreamEvent::ContentBlockDelta { index, delta } => match delta {
+                        ClaudeDelta::TextDelta { text } => {
+                            if tx.send(StreamEvent::Token(text)).await.is_err() {
+                                return; // receiver dropped
+                            }
+                        }
+                        ClaudeDelta::InputJsonDelta { partial_json } => {
+                            if let Some(pending) = tool_use.as_mut().filter(|p| p.index == index) {
+                                pending.input_json.push_str(&partial_json);
+                            }
+                        }
+                        ClaudeDelta::ThinkingDelta { thinking } => {
+                            if tx.send(StreamEvent::Thinking(thinking)).await.is_err() {
+                                return; // receiver dropped
+                            }
+                        }
+                        ClaudeDelta::SignatureDelta { signature } => {
+                            if tx
+                                .send(StreamEvent::ThinkingSignature(signature))
+                                .await
+                                .is_err()
+                            {
+                                return; // receiver dropped
+                            }
+                        }
+                        ClaudeDelta::Other => {}
+                    },
+                    ClaudeStreamEvent::ContentBlockStop { index }
+                        if tool_use.as_ref().is_some_and(|p| p.index == index) =>
+                    {
+                        let pending = tool_use.take().unwrap();
+                        let call = ToolCall::from_json_arguments(
+                            pending.id,
+                            pending.name,
+                            &pending.input_json,
+                        );
+                        if tx.send(StreamEvent::ToolCall(call)).await.is_err() {
                             return; // receiver dropped
                         }
                     }
@@ -92,6 +144,7 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
                             .send(StreamEvent::Done {
                                 tokens_in,
                                 tokens_out,
+                                thinking_tokens: None,
                             })
                             .await;
                         return;
@@ -100,7 +153,7 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
                         let _ = tx.send(StreamEvent::Error(error.message)).await;
                         return;
                     }
-                    // Ignore: ContentBlockStart, ContentBlockStop, Ping
+                    // Ignore: other ContentBlockStart/Stop, Ping
                     _ => {}
                 },
                 Err(e) => {
@@ -115,6 +168,7 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
         .send(StreamEvent::Done {
             tokens_in,
             tokens_out,
+            thinking_tokens: None,
         })
         .await;
 }
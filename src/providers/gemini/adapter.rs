@@ -5,6 +5,7 @@ use tokio::sync::mpsc;
 
 use super::models::*;
 use crate::models::{ProviderId, Role};
+use crate::providers::retry::{retry_after_secs, status_error};
 use crate::providers::traits::AiProvider;
 use crate::providers::types::*;
 
@@ -36,6 +37,16 @@ impl GeminiProvider {
         format!("HTTP {}: Request failed", status.as_u16())
     }
 
+    /// The `retryDelay` Gemini reports in the `RetryInfo` detail of a rate limit error.
+    fn retry_delay(body: &str) -> Option<u64> {
+        let parsed = serde_json::from_str::<serde_json::Value>(body).ok()?;
+        parsed["error"]["details"]
+            .as_array()?
+            .iter()
+            .find_map(|detail| detail["retryDelay"].as_str())
+            .and_then(crate::providers::retry::parse_duration_secs)
+    }
+
     fn translate_role(role: &Role) -> &'static str {
         match role {
             Role::User => "user",
@@ -49,23 +60,54 @@ impl GeminiProvider {
             .map(|msg| {
                 let mut parts = Vec::new();
 
-                // Add image parts first
-                for img in &msg.images {
-                    let b64 = base64::engine::general_purpose::STANDARD.encode(&img.data);
+                // Function responses answer the previous turn's calls
+                for result in &msg.tool_results {
+                    let key = if result.is_error { "error" } else { "output" };
+                    parts.push(GeminiPart {
+                        function_response: Some(GeminiFunctionResponse {
+                            id: None,
+                            name: result.name.clone(),
+                            response: serde_json::json!({ key: result.content }),
+                        }),
+                        ..Default::default()
+                    });
+                }
+
+                // Add image and document parts first
+                let attachments = msg
+                    .images
+                    .iter()
+                    .map(|img| (&img.mime_type, &img.data))
+                    .chain(msg.documents.iter().map(|doc| (&doc.mime_type, &doc.data)));
+                for (mime_type, data) in attachments {
+                    let b64 = base64::engine::general_purpose::STANDARD.encode(data);
                     parts.push(GeminiPart {
-                        text: None,
                         inline_data: Some(GeminiInlineData {
-                            mime_type: img.mime_type.clone(),
+                            mime_type: mime_type.clone(),
                             data: b64,
                         }),
+                        ..Default::default()
                     });
                 }
 
                 // Add text part
-                parts.push(GeminiPart {
-                    text: Some(msg.content.clone()),
-                    inline_data: None,
-                });
+                if !msg.content.is_empty() || (parts.is_empty() && msg.tool_calls.is_empty()) {
+                    parts.push(GeminiPart {
+                        text: Some(msg.content.clone()),
+                        ..Default::default()
+                    });
+                }
+
+                for call in &msg.tool_calls {
+                    parts.push(GeminiPart {
+                        function_call: Some(GeminiFunctionCall {
+                            id: None,
+                            name: call.name.clone(),
+                            args: call.arguments.clone(),
+                        }),
+                        ..Default::default()
+                    });
+                }
 
                 GeminiContent {
                     role: Self::translate_role(&msg.role).to_string(),
@@ -74,6 +116,123 @@ impl GeminiProvider {
             })
             .collect()
     }
+
+    fn build_tools(tools: &[ToolDefinition]) -> Vec<GeminiTool> {
+        if tools.is_empty() {
+            return Vec::new();
+        }
+
+        vec![GeminiTool {
+            function_declarations: tools
+                .iter()
+                .map(|tool| GeminiFunctionDeclaration {
+                    name: tool.name.clone(),
+                    description: tool.description.clone(),
+                    parameters: tool.parameters.clone(),
+                })
+                .collect(),
+        }]
+    }
+
+    /// Gemini 2.5 and later can think before answering.
+    fn supports_thinking(model: &str) -> bool {
+        model.starts_with("gemini-2.5") || model.starts_with("gemini-3")
+    }
+
+    /// Gemini models read PDFs natively; Gemma models served by the same API don't.
+    fn is_pdf_model(model: &str) -> bool {
+        model.starts_with("gemini-")
+    }
+
+    fn features(model: &str) -> Vec<Feature> {
+        let mut features = vec![Feature::Chat, Feature::FunctionCalling];
+        if Self::supports_thinking(model) {
+            features.push(Feature::ExtendedThinking);
+        }
+        if Self::is_pdf_model(model) {
+            features.push(Feature::PdfInput);
+        }
+        features
+    }
+
+    /// 2.5 Flash and Flash-Lite can skip thinking; other thinking models always think.
+    fn can_disable_thinking(model: &str) -> bool {
+        model.starts_with("gemini-2.5-flash")
+    }
+
+    /// Clamp the budget into the range each model family accepts. A budget of 0 turns
+    /// thinking off where the model allows it and asks for the least otherwise, since
+    /// sending nothing leaves the model thinking as much as it likes.
+    fn thinking_config(request: &ChatRequest) -> Option<GeminiThinkingConfig> {
+        let budget = request
+            .thinking_budget
+            .filter(|_| Self::supports_thinking(&request.model))?;
+        let model = request.model.as_str();
+        if budget == 0 && Self::can_disable_thinking(model) {
+            return Some(GeminiThinkingConfig {
+                thinking_budget: 0,
+                include_thoughts: false,
+            });
+        }
+        let min = if model.contains("flash-lite") {
+            512
+        } else {
+            128
+        };
+        let max = if model.contains("pro") { 32768 } else { 24576 };
+
+        Some(GeminiThinkingConfig {
+            thinking_budget: budget.clamp(min, max),
+            include_thoughts: budget > 0,
+        })
+    }
+
+    fn build_request(request: &ChatRequest) -> GeminiRequest {
+        let generation_config = GeminiGenerationConfig {
+            temperature: request.temperature,
+            max_output_tokens: request.max_tokens,
+            top_p: request.top_p,
+            top_k: request.top_k,
+            // Gemini accepts at most five
+            stop_sequences: request.stop_sequences.iter().take(5).cloned().collect(),
+            seed: request.seed,
+            thinking_config: Self::thinking_config(request),
+        };
+        let generation_config = (generation_config.temperature.is_some()
+            || generation_config.max_output_tokens.is_some()
+            || generation_config.top_p.is_some()
+            || generation_config.top_k.is_some()
+            || !generation_config.stop_sequences.is_empty()
+            || generation_config.seed.is_some()
+            || generation_config.thinking_config.is_some())
+        .then_some(generation_config);
+
+        let system_instruction = request.system_prompt.as_ref().map(|prompt| GeminiContent {
+            role: "user".to_string(),
+            parts: vec![GeminiPart {
+                text: Some(prompt.clone()),
+                ..Default::default()
+            }],
+        });
+
+        GeminiRequest {
+            contents: Self::build_contents(&request.messages),
+            system_instruction,
+            generation_config,
+            tools: Self::build_tools(&request.tools),
+        }
+    }
+}
+
+/// Gemini does not always assign call IDs, so mint one when missing.
+pub(super) fn tool_call_from_gemini(call: GeminiFunctionCall) -> ToolCall {
+    ToolCall {
+        id: call
+            .id
+            .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple())),
+        name: call.name,
+        arguments: call.args,
+    }
 }
 
 #[async_trait]
@@ -82,6 +241,10 @@ impl AiProvider for GeminiProvider {
         ProviderId::Gemini
     }
 
+    fn supports_pdf(&self, model: &str) -> bool {
+        Self::is_pdf_model(model)
+    }
+
     async fn validate_credentials(
         &self,
         api_key: &str,
@@ -129,7 +292,7 @@ impl AiProvider for GeminiProvider {
                 ModelInfo {
                     id: name.to_string(),
                     name: m.display_name.unwrap_or_else(|| name.to_string()),
-                    features: vec![Feature::Chat],
+                    features: Self::features(name),
                 }
             })
             .collect();
@@ -141,25 +304,7 @@ impl AiProvider for GeminiProvider {
         let base = Self::base_url(request.base_url.as_deref());
         let url = format!("{}/models/{}:generateContent", base, request.model);
 
-        let contents = Self::build_contents(&request.messages);
-
-        let generation_config = request.temperature.map(|t| GeminiGenerationConfig {
-            temperature: Some(t),
-        });
-
-        let system_instruction = request.system_prompt.as_ref().map(|prompt| GeminiContent {
-            role: "user".to_string(),
-            parts: vec![GeminiPart {
-                text: Some(prompt.clone()),
-                inline_data: None,
-            }],
-        });
-
-        let gemini_request = GeminiRequest {
-            contents,
-            system_instruction,
-            generation_config,
-        };
+        let gemini_request = Self::build_request(&request);
 
         let response = self
             .client
@@ -176,18 +321,15 @@ impl AiProvider for GeminiProvider {
             return Err(ProviderError::AuthError("Invalid API key".to_string()));
         }
 
-        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            return Err(ProviderError::RateLimited {
-                retry_after_secs: None,
-            });
-        }
-
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = retry_after_secs(status, response.headers());
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::RequestFailed(Self::parse_error_message(
-                status, &body,
-            )));
+            return Err(status_error(
+                status,
+                Self::parse_error_message(status, &body),
+                retry_after.or_else(|| Self::retry_delay(&body)),
+            ));
         }
 
         let gemini_response: GeminiResponse = response
@@ -201,23 +343,57 @@ impl AiProvider for GeminiProvider {
             ));
         }
 
-        let content = gemini_response
+        let parts = gemini_response
             .candidates
             .and_then(|c| c.into_iter().next())
             .and_then(|c| c.content)
-            .and_then(|c| c.parts.into_iter().filter_map(|p| p.text).next())
-            .ok_or_else(|| ProviderError::InvalidResponse("No content in response".to_string()))?;
+            .map(|c| c.parts)
+            .unwrap_or_default();
+
+        let mut content = String::new();
+        let mut thinking = String::new();
+        let mut tool_calls = Vec::new();
+        for part in parts {
+            if let Some(text) = part.text {
+                if part.thought {
+                    thinking.push_str(&text);
+                } else {
+                    content.push_str(&text);
+                }
+            }
+            if let Some(call) = part.function_call {
+                tool_calls.push(tool_call_from_gemini(call));
+            }
+        }
 
-        let (tokens_in, tokens_out) = gemini_response
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(ProviderError::InvalidResponse(
+                "No content in response".to_string(),
+            ));
+        }
+
+        let (tokens_in, tokens_out, thinking_tokens) = gemini_response
             .usage_metadata
-            .map(|u| (u.prompt_token_count, u.candidates_token_count))
-            .unwrap_or((None, None));
+            .map(|u| {
+                (
+                    u.prompt_token_count,
+                    u.candidates_token_count,
+                    u.thoughts_token_count,
+                )
+            })
+            .unwrap_or((None, None, None));
 
         Ok(ChatResponse {
             content,
+            tool_calls,
+            thinking: (!thinking.is_empty()).then_some(ThinkingBlock {
+                text: thinking,
+                signature: None,
+            }),
             model: request.model,
             tokens_in,
             tokens_out,
+            thinking_tokens,
         })
     }
 
@@ -234,25 +410,7 @@ impl AiProvider for GeminiProvider {
             base, request.model
         );
 
-        let contents = Self::build_contents(&request.messages);
-
-        let generation_config = request.temperature.map(|t| GeminiGenerationConfig {
-            temperature: Some(t),
-        });
-
-        let system_instruction = request.system_prompt.as_ref().map(|prompt| GeminiContent {
-            role: "user".to_string(),
-            parts: vec![GeminiPart {
-                text: Some(prompt.clone()),
-                inline_data: None,
-            }],
-        });
-
-        let gemini_request = GeminiRequest {
-            contents,
-            system_instruction,
-            generation_config,
-        };
+        let gemini_request = Self::build_request(&request);
 
         let response = self
             .client
@@ -269,18 +427,15 @@ impl AiProvider for GeminiProvider {
             return Err(ProviderError::AuthError("Invalid API key".to_string()));
         }
 
-        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            return Err(ProviderError::RateLimited {
-                retry_after_secs: None,
-            });
-        }
-
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = retry_after_secs(status, response.headers());
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::RequestFailed(Self::parse_error_message(
-                status, &body,
-            )));
+            return Err(status_error(
+                status,
+                Self::parse_error_message(status, &body),
+                retry_after.or_else(|| Self::retry_delay(&body)),
+            ));
         }
 
         parse_sse_stream(response, tx).await;
@@ -288,3 +443,61 @@ impl AiProvider for GeminiProvider {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str, thinking_budget: Option<u32>) -> ChatRequest {
+        ChatRequest {
+            api_key: String::new(),
+            model: model.to_string(),
+            messages: vec![ChatMessage::new(Role::User, "Hi".to_string())],
+            base_url: None,
+            temperature: None,
+            system_prompt: None,
+            max_tokens: None,
+            top_p: None,
+            top_k: None,
+            stop_sequences: Vec::new(),
+            seed: None,
+            tools: Vec::new(),
+            thinking_budget,
+        }
+    }
+
+    fn generation_config(request: &ChatRequest) -> serde_json::Value {
+        serde_json::to_value(GeminiProvider::build_request(request))
+            .unwrap()
+            .get("generationConfig")
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_thinking_config() {
+        assert_eq!(
+            generation_config(&request("gemini-2.5-flash", Some(0))),
+            serde_json::json!({
+                "thinkingConfig": { "thinkingBudget": 0, "includeThoughts": false }
+            })
+        );
+        // Pro can't turn thinking off, so gets the least it accepts
+        assert_eq!(
+            generation_config(&request("gemini-2.5-pro", Some(0)))["thinkingConfig"],
+            serde_json::json!({ "thinkingBudget": 128, "includeThoughts": false })
+        );
+        assert_eq!(
+            generation_config(&request("gemini-2.5-flash-lite", Some(100)))["thinkingConfig"],
+            serde_json::json!({ "thinkingBudget": 512, "includeThoughts": true })
+        );
+        assert_eq!(
+            generation_config(&request("gemini-2.0-flash", Some(0))),
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            generation_config(&request("gemini-2.5-flash", None)),
+            serde_json::Value::Null
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use futures::StreamExt;
 use tokio::sync::mpsc;
 
+use super::adapter::tool_call_from_gemini;
 use super::models::GeminiResponse;
 use crate::providers::types::StreamEvent;
 
@@ -10,6 +11,7 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
     let mut buffer = String::new();
     let mut last_tokens_in: Option<i64> = None;
     let mut last_tokens_out: Option<i64> = None;
+    let mut last_thinking_tokens: Option<i64> = None;
 
     while let Some(chunk_result) = stream.next().await {
         let bytes = match chunk_result {
@@ -73,13 +75,25 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
             match serde_json::from_str::<GeminiResponse>(&data) {
                 Ok(response) => {
                     // Extract text from response
-                    if let Some(candidates) = &response.candidates {
-                        if let Some(candidate) = candidates.first() {
-                            if let Some(content) = &candidate.content {
-                                for part in &content.parts {
-                                    if let Some(text) = &part.text {
-                                        if tx.send(StreamEvent::Token(text.clone())).await.is_err()
-                                        {
+                    if let Some(candidates) = response.candidates {
+                        if let Some(candidate) = candidates.into_iter().next() {
+                            if let Some(content) = candidate.content {
+                                for part in content.parts {
+                                    if let Some(text) = part.text {
+                                        // Thought summaries stay out of the answer text
+                                        let event = if part.thought {
+                                            StreamEvent::Thinking(text)
+                                        } else {
+                                            StreamEvent::Token(text)
+                                        };
+                                        if tx.send(event).await.is_err() {
+                                            return; // receiver dropped
+                                        }
+                                    }
+                                    // Function calls arrive whole, never split across chunks
+                                    if let Some(call) = part.function_call {
+                                        let call = tool_call_from_gemini(call);
+                                        if tx.send(StreamEvent::ToolCall(call)).await.is_err() {
                                             return; // receiver dropped
                                         }
                                     }
@@ -96,6 +110,9 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
                         if usage.candidates_token_count.is_some() {
                             last_tokens_out = usage.candidates_token_count;
                         }
+                        if usage.thoughts_token_count.is_some() {
+                            last_thinking_tokens = usage.thoughts_token_count;
+                        }
                     }
 
                     // Check for errors in response
@@ -121,6 +138,7 @@ pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<Stre
         .send(StreamEvent::Done {
             tokens_in: last_tokens_in,
             tokens_out: last_tokens_out,
+            thinking_tokens: last_thinking_tokens,
         })
         .await;
 }
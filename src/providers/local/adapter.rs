@@ -2,11 +2,28 @@ use async_trait::async_trait;
 use reqwest::Client;
 use tokio::sync::mpsc;
 
-use super::models::*;
-use crate::models::{ProviderId, Role};
+use crate::models::ProviderId;
+use crate::providers::openai_compat::models::*;
+use crate::providers::openai_compat::{build_messages, build_tools, response_content};
+use crate::providers::retry::{retry_after_secs, status_error};
 use crate::providers::traits::AiProvider;
 use crate::providers::types::*;
 
+/// Name fragments of common vision models served locally (llava, gemma3, pixtral, ...).
+/// Local servers don't report capabilities, so vision support is guessed from the model ID.
+const VISION_MODEL_MARKERS: &[&str] = &[
+    "llava",
+    "vision",
+    "internvl",
+    "gemma3",
+    "pixtral",
+    "minicpm-v",
+    "moondream",
+    "mistral-small3",
+    "mistral-small-3",
+    "llama4",
+];
+
 pub struct LocalProvider {
     client: Client,
 }
@@ -18,33 +35,35 @@ impl LocalProvider {
         }
     }
 
-    fn translate_role(role: &Role) -> &'static str {
-        match role {
-            Role::User => "user",
-            Role::Assistant => "assistant",
-        }
+    fn is_vision_model(model: &str) -> bool {
+        let model = model.to_lowercase();
+        VISION_MODEL_MARKERS.iter().any(|m| model.contains(m))
+            // "VL" is too short to find anywhere in a name, so it has to stand alone
+            // or next to a version, as in qwen2-vl, qwen2.5vl:7b or deepseek-vl2
+            || model
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|token| token.trim_matches(|c: char| c.is_ascii_digit()) == "vl")
     }
 
-    fn build_messages(system_prompt: Option<&str>, messages: &[ChatMessage]) -> Vec<OpenAiMessage> {
-        let mut result = Vec::new();
-
-        if let Some(prompt) = system_prompt {
-            if !prompt.is_empty() {
-                result.push(OpenAiMessage {
-                    role: "system".to_string(),
-                    content: prompt.to_string(),
-                });
-            }
-        }
-
-        for msg in messages {
-            result.push(OpenAiMessage {
-                role: Self::translate_role(&msg.role).to_string(),
-                content: msg.content.clone(),
-            });
+    fn build_request(request: &ChatRequest, stream: bool) -> OpenAiRequest {
+        OpenAiRequest {
+            model: request.model.clone(),
+            messages: build_messages(
+                request.system_prompt.as_deref(),
+                &request.messages,
+                Self::is_vision_model(&request.model),
+            ),
+            stream: Some(stream),
+            stream_options: None,
+            temperature: request.temperature,
+            max_completion_tokens: None,
+            max_tokens: request.max_tokens,
+            top_p: request.top_p,
+            top_k: request.top_k,
+            stop: request.stop_sequences.clone(),
+            seed: request.seed,
+            tools: build_tools(&request.tools),
         }
-
-        result
     }
 
     fn build_auth_header(api_key: &str) -> Option<String> {
@@ -69,6 +88,10 @@ impl AiProvider for LocalProvider {
         ProviderId::Local
     }
 
+    fn supports_vision(&self, model: &str) -> bool {
+        Self::is_vision_model(model)
+    }
+
     async fn validate_credentials(
         &self,
         api_key: &str,
@@ -110,10 +133,16 @@ impl AiProvider for LocalProvider {
         let models = model_list
             .data
             .into_iter()
-            .map(|m| ModelInfo {
-                id: m.id.clone(),
-                name: m.id,
-                features: vec![Feature::Chat, Feature::Streaming],
+            .map(|m| {
+                let mut features = vec![Feature::Chat, Feature::Streaming];
+                if Self::is_vision_model(&m.id) {
+                    features.push(Feature::Vision);
+                }
+                ModelInfo {
+                    id: m.id.clone(),
+                    name: m.id,
+                    features,
+                }
             })
             .collect();
 
@@ -127,15 +156,7 @@ impl AiProvider for LocalProvider {
 
         let url = format!("{}/v1/chat/completions", base.trim_end_matches('/'));
 
-        let messages = Self::build_messages(request.system_prompt.as_deref(), &request.messages);
-
-        let openai_request = OpenAiRequest {
-            model: request.model.clone(),
-            messages,
-            stream: false,
-            temperature: request.temperature,
-            max_tokens: request.max_tokens,
-        };
+        let openai_request = Self::build_request(&request, false);
 
         let mut req = self
             .client
@@ -158,32 +179,25 @@ impl AiProvider for LocalProvider {
             return Err(ProviderError::AuthError("Invalid API key".to_string()));
         }
 
-        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            return Err(ProviderError::RateLimited {
-                retry_after_secs: None,
-            });
-        }
-
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = retry_after_secs(status, response.headers());
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::RequestFailed(Self::parse_error_message(
-                status, &body,
-            )));
+            return Err(status_error(
+                status,
+                Self::parse_error_message(status, &body),
+                retry_after,
+            ));
         }
 
-        let openai_response: OpenAiResponse = response
+        let mut openai_response: OpenAiResponse = response
             .json()
             .await
             .map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
 
-        let content = openai_response
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .unwrap_or_default();
+        let (content, tool_calls) = response_content(&mut openai_response);
 
-        if content.is_empty() {
+        if content.is_empty() && tool_calls.is_empty() {
             return Err(ProviderError::InvalidResponse(
                 "No content in response".to_string(),
             ));
@@ -196,9 +210,12 @@ impl AiProvider for LocalProvider {
 
         Ok(ChatResponse {
             content,
+            tool_calls,
+            thinking: None,
             model: request.model,
             tokens_in,
             tokens_out,
+            thinking_tokens: None,
         })
     }
 
@@ -207,7 +224,7 @@ impl AiProvider for LocalProvider {
         request: ChatRequest,
         tx: mpsc::Sender<StreamEvent>,
     ) -> Result<(), ProviderError> {
-        use super::stream::parse_sse_stream;
+        use crate::providers::openai_compat::stream::parse_sse_stream;
 
         let base = request.base_url.as_deref().ok_or_else(|| {
             ProviderError::RequestFailed("Base URL is required for Local provider".to_string())
@@ -215,15 +232,7 @@ impl AiProvider for LocalProvider {
 
         let url = format!("{}/v1/chat/completions", base.trim_end_matches('/'));
 
-        let messages = Self::build_messages(request.system_prompt.as_deref(), &request.messages);
-
-        let openai_request = OpenAiRequest {
-            model: request.model.clone(),
-            messages,
-            stream: true,
-            temperature: request.temperature,
-            max_tokens: request.max_tokens,
-        };
+        let openai_request = Self::build_request(&request, true);
 
         let mut req = self
             .client
@@ -246,18 +255,15 @@ impl AiProvider for LocalProvider {
             return Err(ProviderError::AuthError("Invalid API key".to_string()));
         }
 
-        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            return Err(ProviderError::RateLimited {
-                retry_after_secs: None,
-            });
-        }
-
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = retry_after_secs(status, response.headers());
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::RequestFailed(Self::parse_error_message(
-                status, &body,
-            )));
+            return Err(status_error(
+                status,
+                Self::parse_error_message(status, &body),
+                retry_after,
+            ));
         }
 
         parse_sse_stream(response, tx).await;
@@ -265,3 +271,33 @@ impl AiProvider for LocalProvider {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_vision_model() {
+        for model in [
+            "llava:13b",
+            "qwen2-vl-7b-instruct",
+            "Qwen2.5-VL-32B",
+            "qwen2.5vl:7b",
+            "deepseek-vl2",
+            "internvl2-8b",
+            "gemma3:27b",
+        ] {
+            assert!(LocalProvider::is_vision_model(model), "{}", model);
+        }
+
+        for model in [
+            "llama3.1:8b",
+            "kevlar-7b",
+            "marvl-chat",
+            "solvl-coder",
+            "qwen2.5-coder:32b",
+        ] {
+            assert!(!LocalProvider::is_vision_model(model), "{}", model);
+        }
+    }
+}
@@ -1,5 +1,3 @@
 mod adapter;
-mod models;
-mod stream;
 
 pub use adapter::LocalProvider;
@@ -1,9 +1,15 @@
 pub mod claude;
 pub mod gemini;
 pub mod local;
+pub mod openai;
+pub mod openai_compat;
+pub mod retry;
 pub mod router;
 pub mod traits;
 pub mod types;
 
 pub use router::ProviderRouter;
-pub use types::{ChatMessage, ChatRequest, ImageAttachment, StreamEvent};
+pub use types::{
+    ChatMessage, ChatRequest, DocumentAttachment, ImageAttachment, ProviderError, RetryNotice,
+    StreamEvent, ThinkingBlock, ToolCall, ToolDefinition, ToolResult,
+};
@@ -0,0 +1,287 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use tokio::sync::mpsc;
+
+use crate::models::ProviderId;
+use crate::providers::openai_compat::models::*;
+use crate::providers::openai_compat::{build_messages, build_tools, response_content};
+use crate::providers::retry::{retry_after_secs, status_error};
+use crate::providers::traits::AiProvider;
+use crate::providers::types::*;
+
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+const FALLBACK_MODELS: &[(&str, &str)] = &[
+    ("gpt-4o", "GPT-4o"),
+    ("gpt-4o-mini", "GPT-4o mini"),
+    ("gpt-4.1", "GPT-4.1"),
+    ("gpt-4.1-mini", "GPT-4.1 mini"),
+    ("o3-mini", "o3-mini"),
+];
+
+/// Substrings of model IDs returned by `/models` that are not chat models.
+const NON_CHAT_MARKERS: &[&str] = &[
+    "instruct",
+    "audio",
+    "realtime",
+    "transcribe",
+    "tts",
+    "search",
+    "image",
+    "embedding",
+    "moderation",
+];
+
+pub struct OpenAiProvider {
+    client: Client,
+}
+
+impl OpenAiProvider {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+
+    fn base_url(custom: Option<&str>) -> &str {
+        custom
+            .map(|url| url.trim_end_matches('/'))
+            .unwrap_or(DEFAULT_BASE_URL)
+    }
+
+    fn parse_error_message(status: reqwest::StatusCode, body: &str) -> String {
+        if let Ok(parsed) = serde_json::from_str::<OpenAiErrorResponse>(body) {
+            return format!("HTTP {}: {}", status.as_u16(), parsed.error.message);
+        }
+        format!("HTTP {}: Request failed", status.as_u16())
+    }
+
+    /// Reasoning models (o-series, gpt-5) reject any non-default temperature.
+    fn is_reasoning_model(model: &str) -> bool {
+        model.starts_with('o') || model.starts_with("gpt-5")
+    }
+
+    fn is_chat_model(id: &str) -> bool {
+        let is_chat_family = id.starts_with("gpt-")
+            || id.starts_with("chatgpt-")
+            || id.starts_with("o1")
+            || id.starts_with("o3")
+            || id.starts_with("o4");
+        is_chat_family && !NON_CHAT_MARKERS.iter().any(|m| id.contains(m))
+    }
+
+    fn is_vision_model(id: &str) -> bool {
+        id.starts_with("gpt-4o")
+            || id.starts_with("gpt-4.1")
+            || id.starts_with("gpt-4-turbo")
+            || id.starts_with("gpt-5")
+            || id.starts_with("chatgpt-4o")
+            || id.starts_with("o4")
+            || ((id.starts_with("o1") || id.starts_with("o3")) && !id.contains("mini"))
+    }
+
+    fn model_info(id: &str) -> ModelInfo {
+        let name = FALLBACK_MODELS
+            .iter()
+            .find(|(known, _)| *known == id)
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_else(|| id.to_string());
+
+        let mut features = vec![Feature::Chat, Feature::Streaming, Feature::FunctionCalling];
+        if Self::is_vision_model(id) {
+            features.push(Feature::Vision);
+        }
+
+        ModelInfo {
+            id: id.to_string(),
+            name,
+            features,
+        }
+    }
+
+    fn fallback_models() -> Vec<ModelInfo> {
+        FALLBACK_MODELS
+            .iter()
+            .map(|(id, _)| Self::model_info(id))
+            .collect()
+    }
+
+    fn build_request(request: &ChatRequest, stream: bool) -> OpenAiRequest {
+        // Reasoning models reject sampling parameters and stop sequences
+        let reasoning = Self::is_reasoning_model(&request.model);
+        let temperature = if reasoning { None } else { request.temperature };
+
+        OpenAiRequest {
+            model: request.model.clone(),
+            messages: build_messages(request.system_prompt.as_deref(), &request.messages, true),
+            stream: stream.then_some(true),
+            stream_options: stream.then_some(OpenAiStreamOptions {
+                include_usage: true,
+            }),
+            temperature,
+            max_completion_tokens: request.max_tokens,
+            max_tokens: None,
+            top_p: request.top_p.filter(|_| !reasoning),
+            top_k: None,
+            // OpenAI accepts at most four
+            stop: if reasoning {
+                Vec::new()
+            } else {
+                request.stop_sequences.iter().take(4).cloned().collect()
+            },
+            seed: request.seed,
+            tools: build_tools(&request.tools),
+        }
+    }
+
+    async fn post_chat_completion(
+        &self,
+        request: &ChatRequest,
+        body: &OpenAiRequest,
+    ) -> Result<reqwest::Response, ProviderError> {
+        let url = format!(
+            "{}/chat/completions",
+            Self::base_url(request.base_url.as_deref())
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&request.api_key)
+            .header("content-type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ProviderError::AuthError(Self::parse_error_message(
+                status, &body,
+            )));
+        }
+
+        if !status.is_success() {
+            let retry_after = retry_after_secs(status, response.headers());
+            let body = response.text().await.unwrap_or_default();
+            return Err(status_error(
+                status,
+                Self::parse_error_message(status, &body),
+                retry_after,
+            ));
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl AiProvider for OpenAiProvider {
+    fn provider_id(&self) -> ProviderId {
+        ProviderId::OpenAi
+    }
+
+    fn supports_vision(&self, model: &str) -> bool {
+        Self::is_vision_model(model)
+    }
+
+    async fn validate_credentials(
+        &self,
+        api_key: &str,
+        base_url: Option<&str>,
+    ) -> Result<Vec<ModelInfo>, ProviderError> {
+        let url = format!("{}/models", Self::base_url(base_url));
+
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ProviderError::AuthError(Self::parse_error_message(
+                status, &body,
+            )));
+        }
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ProviderError::RequestFailed(Self::parse_error_message(
+                status, &body,
+            )));
+        }
+
+        let model_list: OpenAiModelList = response.json().await.map_err(|e| {
+            ProviderError::InvalidResponse(format!("Failed to parse model list: {}", e))
+        })?;
+
+        let mut ids: Vec<String> = model_list
+            .data
+            .into_iter()
+            .map(|m| m.id)
+            .filter(|id| Self::is_chat_model(id))
+            .collect();
+        ids.sort();
+
+        if ids.is_empty() {
+            return Ok(Self::fallback_models());
+        }
+
+        Ok(ids.iter().map(|id| Self::model_info(id)).collect())
+    }
+
+    async fn send_message(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+        let openai_request = Self::build_request(&request, false);
+        let response = self.post_chat_completion(&request, &openai_request).await?;
+
+        let mut openai_response: OpenAiResponse = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
+
+        let (content, tool_calls) = response_content(&mut openai_response);
+
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(ProviderError::InvalidResponse(
+                "No content in response".to_string(),
+            ));
+        }
+
+        let (tokens_in, tokens_out) = openai_response
+            .usage
+            .map(|u| (u.prompt_tokens, u.completion_tokens))
+            .unwrap_or((None, None));
+
+        Ok(ChatResponse {
+            content,
+            tool_calls,
+            thinking: None,
+            model: openai_response.model,
+            tokens_in,
+            tokens_out,
+            thinking_tokens: None,
+        })
+    }
+
+    async fn stream_message(
+        &self,
+        request: ChatRequest,
+        tx: mpsc::Sender<StreamEvent>,
+    ) -> Result<(), ProviderError> {
+        use crate::providers::openai_compat::stream::parse_sse_stream;
+
+        let openai_request = Self::build_request(&request, true);
+        let response = self.post_chat_completion(&request, &openai_request).await?;
+
+        parse_sse_stream(response, tx).await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod adapter;
+
+pub use adapter::OpenAiProvider;
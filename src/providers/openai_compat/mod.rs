@@ -0,0 +1,136 @@
+//! Wire format of the OpenAI chat completions API, shared by the OpenAI provider
+//! and the local servers (Ollama, LM Studio, llama.cpp, vLLM) that mimic it.
+
+pub mod models;
+pub mod stream;
+
+use base64::Engine;
+
+use self::models::*;
+use crate::models::Role;
+use crate::providers::types::{ChatMessage, ToolCall, ToolDefinition};
+
+fn translate_role(role: &Role) -> &'static str {
+    match role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+fn translate_tool_call(call: &ToolCall) -> OpenAiToolCall {
+    OpenAiToolCall {
+        id: call.id.clone(),
+        call_type: "function".to_string(),
+        function: OpenAiFunctionCall {
+            name: call.name.clone(),
+            arguments: call.arguments.to_string(),
+        },
+    }
+}
+
+/// Translate the conversation, leaving images out when `include_images` is false.
+pub fn build_messages(
+    system_prompt: Option<&str>,
+    messages: &[ChatMessage],
+    include_images: bool,
+) -> Vec<OpenAiMessage> {
+    let mut result = Vec::new();
+
+    if let Some(prompt) = system_prompt {
+        if !prompt.is_empty() {
+            result.push(OpenAiMessage {
+                role: "system".to_string(),
+                content: Some(OpenAiContent::Text(prompt.to_string())),
+                tool_calls: Vec::new(),
+                tool_call_id: None,
+            });
+        }
+    }
+
+    for msg in messages {
+        let has_images = include_images && !msg.images.is_empty();
+
+        // Tool results each travel in their own "tool" message
+        for tool_result in &msg.tool_results {
+            result.push(OpenAiMessage {
+                role: "tool".to_string(),
+                content: Some(OpenAiContent::Text(tool_result.content.clone())),
+                tool_calls: Vec::new(),
+                tool_call_id: Some(tool_result.tool_call_id.clone()),
+            });
+        }
+        if !msg.tool_results.is_empty() && msg.content.is_empty() && !has_images {
+            continue;
+        }
+
+        let content = if has_images {
+            // Has images: use content part array with data URIs
+            let mut parts = vec![OpenAiContentPart::Text {
+                text: msg.content.clone(),
+            }];
+            for img in &msg.images {
+                let b64 = base64::engine::general_purpose::STANDARD.encode(&img.data);
+                parts.push(OpenAiContentPart::ImageUrl {
+                    image_url: OpenAiImageUrl {
+                        url: format!("data:{};base64,{}", img.mime_type, b64),
+                    },
+                });
+            }
+            Some(OpenAiContent::Parts(parts))
+        } else {
+            (!msg.content.is_empty() || msg.tool_calls.is_empty())
+                .then(|| OpenAiContent::Text(msg.content.clone()))
+        };
+
+        result.push(OpenAiMessage {
+            role: translate_role(&msg.role).to_string(),
+            content,
+            tool_calls: msg.tool_calls.iter().map(translate_tool_call).collect(),
+            tool_call_id: None,
+        });
+    }
+
+    result
+}
+
+pub fn build_tools(tools: &[ToolDefinition]) -> Vec<OpenAiTool> {
+    tools
+        .iter()
+        .map(|tool| OpenAiTool {
+            tool_type: "function".to_string(),
+            function: OpenAiFunctionDefinition {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters: tool.parameters.clone(),
+            },
+        })
+        .collect()
+}
+
+/// Split a non-streaming response into its text and tool calls. A refusal
+/// stands in for the text when the model declined to answer.
+pub fn response_content(response: &mut OpenAiResponse) -> (String, Vec<ToolCall>) {
+    let Some(choice) = response.choices.drain(..).next() else {
+        return Default::default();
+    };
+    let content = choice
+        .message
+        .content
+        .or(choice.message.refusal)
+        .unwrap_or_default();
+    let tool_calls = choice
+        .message
+        .tool_calls
+        .into_iter()
+        .enumerate()
+        .map(|(i, call)| {
+            let id = if call.id.is_empty() {
+                format!("call_{}", i)
+            } else {
+                call.id
+            };
+            ToolCall::from_json_arguments(id, call.function.name, &call.function.arguments)
+        })
+        .collect();
+    (content, tool_calls)
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+
+// --- Request types ---
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiRequest {
+    pub model: String,
+    pub messages: Vec<OpenAiMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<OpenAiStreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+    /// The older name of `max_completion_tokens`, still the one most local servers read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Not part of the OpenAI API, but understood by vLLM, llama.cpp and LM Studio
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<OpenAiTool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiStreamOptions {
+    pub include_usage: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiMessage {
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<OpenAiContent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<OpenAiToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum OpenAiContent {
+    Text(String),
+    Parts(Vec<OpenAiContentPart>),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum OpenAiContentPart {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: OpenAiImageUrl },
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiImageUrl {
+    pub url: String, // data URI
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiTool {
+    #[serde(rename = "type")]
+    pub tool_type: String, // always "function"
+    pub function: OpenAiFunctionDefinition,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiFunctionDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenAiToolCall {
+    // Some local servers omit both
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "type", default)]
+    pub call_type: String, // always "function"
+    pub function: OpenAiFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenAiFunctionCall {
+    pub name: String,
+    pub arguments: String, // JSON-encoded arguments
+}
+
+// --- Response types (non-streaming) ---
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiResponse {
+    pub choices: Vec<OpenAiChoice>,
+    pub model: String,
+    pub usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiChoice {
+    pub message: OpenAiResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiResponseMessage {
+    pub content: Option<String>,
+    pub refusal: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<OpenAiToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiUsage {
+    pub prompt_tokens: Option<i64>,
+    pub completion_tokens: Option<i64>,
+}
+
+// --- Model list ---
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiModelList {
+    pub data: Vec<OpenAiModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiModel {
+    pub id: String,
+}
+
+// --- Streaming types ---
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiStreamChunk {
+    #[serde(default)]
+    pub choices: Vec<OpenAiStreamChoice>,
+    pub usage: Option<OpenAiUsage>,
+    pub error: Option<OpenAiErrorDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiStreamChoice {
+    pub delta: OpenAiDelta,
+    #[allow(dead_code)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiDelta {
+    pub content: Option<String>,
+    pub refusal: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<OpenAiToolCallDelta>,
+}
+
+/// A fragment of a streamed tool call. The id and name arrive in the first
+/// fragment for each `index`; `arguments` is split across the rest.
+#[derive(Debug, Deserialize)]
+pub struct OpenAiToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub function: Option<OpenAiFunctionCallDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiFunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
+
+// --- Error types ---
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiErrorResponse {
+    pub error: OpenAiErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiErrorDetail {
+    pub message: String,
+}
@@ -0,0 +1,220 @@
+use futures::StreamExt;
+use tokio::sync::mpsc;
+
+use super::models::{OpenAiStreamChunk, OpenAiToolCallDelta};
+use crate::providers::types::{StreamEvent, ToolCall};
+
+/// Most tool calls one reply may make. Deltas address calls by index, so a bound
+/// keeps a bogus index from allocating whatever it names.
+const MAX_TOOL_CALLS: usize = 128;
+
+/// A tool call whose arguments are still arriving.
+#[derive(Default)]
+struct PendingToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+fn merge_tool_call_deltas(pending: &mut Vec<PendingToolCall>, deltas: &[OpenAiToolCallDelta]) {
+    for delta in deltas {
+        if delta.index >= MAX_TOOL_CALLS {
+            tracing::warn!("Ignoring tool call delta with index {}", delta.index);
+            continue;
+        }
+        if pending.len() <= delta.index {
+            pending.resize_with(delta.index + 1, PendingToolCall::default);
+        }
+        let call = &mut pending[delta.index];
+        if let Some(id) = &delta.id {
+            call.id = id.clone();
+        }
+        if let Some(function) = &delta.function {
+            if let Some(name) = &function.name {
+                call.name.push_str(name);
+            }
+            if let Some(arguments) = &function.arguments {
+                call.arguments.push_str(arguments);
+            }
+        }
+    }
+}
+
+/// Emit the accumulated tool calls, returning false if the receiver dropped.
+async fn flush_tool_calls(
+    pending: &mut Vec<PendingToolCall>,
+    tx: &mpsc::Sender<StreamEvent>,
+) -> bool {
+    for (i, call) in pending.drain(..).enumerate() {
+        if call.name.is_empty() {
+            continue;
+        }
+        // Some local servers omit tool call IDs
+        let id = if call.id.is_empty() {
+            format!("call_{}", i)
+        } else {
+            call.id
+        };
+        let call = ToolCall::from_json_arguments(id, call.name, &call.arguments);
+        if tx.send(StreamEvent::ToolCall(call)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+pub async fn parse_sse_stream(response: reqwest::Response, tx: mpsc::Sender<StreamEvent>) {
+    let mut stream = response.bytes_stream();
+    let mut byte_buf: Vec<u8> = Vec::new();
+    let mut buffer = String::new();
+    let mut tokens_in: Option<i64> = None;
+    let mut tokens_out: Option<i64> = None;
+    let mut tool_calls: Vec<PendingToolCall> = Vec::new();
+
+    while let Some(chunk_result) = stream.next().await {
+        let bytes = match chunk_result {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = tx
+                    .send(StreamEvent::Error(format!("Stream error: {}", e)))
+                    .await;
+                return;
+            }
+        };
+
+        byte_buf.extend_from_slice(&bytes);
+
+        // Decode as much valid UTF-8 as possible from the byte buffer
+        let decoded = match std::str::from_utf8(&byte_buf) {
+            Ok(s) => {
+                let decoded = s.to_string();
+                byte_buf.clear();
+                decoded
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                if valid_up_to == 0 {
+                    continue;
+                }
+                let decoded = std::str::from_utf8(&byte_buf[..valid_up_to])
+                    .unwrap()
+                    .to_string();
+                byte_buf.drain(..valid_up_to);
+                decoded
+            }
+        };
+
+        // Normalize CRLF to LF
+        let chunk = decoded.replace("\r\n", "\n");
+        buffer.push_str(&chunk);
+
+        // Process complete SSE events (delimited by double newline)
+        while let Some(event_end) = buffer.find("\n\n") {
+            let event_text = buffer[..event_end].to_string();
+            buffer.drain(..event_end + 2);
+
+            for line in event_text.lines() {
+                let payload = if let Some(p) = line.strip_prefix("data: ") {
+                    p
+                } else if let Some(p) = line.strip_prefix("data:") {
+                    p
+                } else {
+                    continue;
+                };
+
+                // OpenAI signals end of stream with [DONE]
+                if payload.trim() == "[DONE]" {
+                    if !flush_tool_calls(&mut tool_calls, &tx).await {
+                        return;
+                    }
+                    let _ = tx
+                        .send(StreamEvent::Done {
+                            tokens_in,
+                            tokens_out,
+                            thinking_tokens: None,
+                        })
+                        .await;
+                    return;
+                }
+
+                match serde_json::from_str::<OpenAiStreamChunk>(payload) {
+                    Ok(chunk) => {
+                        if let Some(error) = chunk.error {
+                            let _ = tx.send(StreamEvent::Error(error.message)).await;
+                            return;
+                        }
+
+                        // With `include_usage`, the final chunk carries usage and no choices.
+                        // Local servers that report usage send it the same way.
+                        if let Some(usage) = chunk.usage {
+                            tokens_in = usage.prompt_tokens;
+                            tokens_out = usage.completion_tokens;
+                        }
+
+                        if let Some(choice) = chunk.choices.first() {
+                            merge_tool_call_deltas(&mut tool_calls, &choice.delta.tool_calls);
+
+                            let text = choice
+                                .delta
+                                .content
+                                .as_ref()
+                                .or(choice.delta.refusal.as_ref());
+                            if let Some(text) = text {
+                                if !text.is_empty()
+                                    && tx.send(StreamEvent::Token(text.clone())).await.is_err()
+                                {
+                                    return; // receiver dropped
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to parse OpenAI SSE data: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    // If the stream ended without a [DONE] signal, send Done anyway
+    if !flush_tool_calls(&mut tool_calls, &tx).await {
+        return;
+    }
+    let _ = tx
+        .send(StreamEvent::Done {
+            tokens_in,
+            tokens_out,
+            thinking_tokens: None,
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(index: usize, name: Option<&str>, arguments: &str) -> OpenAiToolCallDelta {
+        serde_json::from_value(serde_json::json!({
+            "index": index,
+            "function": { "name": name, "arguments": arguments },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merge_tool_call_deltas() {
+        let mut pending = Vec::new();
+        merge_tool_call_deltas(&mut pending, &[delta(0, Some("search"), "{\"q\":")]);
+        merge_tool_call_deltas(&mut pending, &[delta(0, None, "\"rust\"}")]);
+        merge_tool_call_deltas(&mut pending, &[delta(1, Some("clock"), "{}")]);
+
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].name, "search");
+        assert_eq!(pending[0].arguments, "{\"q\":\"rust\"}");
+        assert_eq!(pending[1].name, "clock");
+
+        merge_tool_call_deltas(&mut pending, &[delta(usize::MAX, Some("x"), "{}")]);
+        merge_tool_call_deltas(&mut pending, &[delta(MAX_TOOL_CALLS, Some("x"), "{}")]);
+        assert_eq!(pending.len(), 2);
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use tokio::sync::mpsc;
+
+use super::router::ProviderRouter;
+use super::types::{ChatRequest, ChatResponse, ProviderError, RetryNotice, StreamEvent};
+use crate::models::ProviderId;
+
+/// How transient provider errors are retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// Longest wait before giving up; a provider asking for more fails immediately.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after `retries_done` retries, or `None` to give up.
+    ///
+    /// The provider's requested delay wins; otherwise the delay doubles each time,
+    /// with jitter so clients that failed together don't retry together.
+    pub fn delay(&self, retries_done: u32, error: &ProviderError) -> Option<Duration> {
+        if retries_done >= self.max_retries || !error.is_transient() {
+            return None;
+        }
+
+        if let Some(secs) = error.retry_after_secs() {
+            let delay = Duration::from_secs(secs.max(1));
+            return (delay <= self.max_delay).then_some(delay);
+        }
+
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retries_done))
+            .min(self.max_delay);
+        let jitter = 0.5 + 0.5 * random_fraction();
+        Some(backoff.mul_f64(jitter))
+    }
+
+    fn notice(&self, retries_done: u32, delay: Duration) -> RetryNotice {
+        RetryNotice {
+            attempt: retries_done + 1,
+            max_retries: self.max_retries,
+            delay_secs: delay.as_secs_f64().ceil() as u64,
+        }
+    }
+}
+
+/// Send a request through the router, retrying transient failures.
+pub async fn send_message<F>(
+    router: &ProviderRouter,
+    provider_id: &ProviderId,
+    request: ChatRequest,
+    policy: &RetryPolicy,
+    mut on_retry: F,
+) -> Result<ChatResponse, ProviderError>
+where
+    F: FnMut(RetryNotice),
+{
+    let mut retries_done = 0;
+    loop {
+        match router.send_message(provider_id, request.clone()).await {
+            Err(e) => match policy.delay(retries_done, &e) {
+                Some(delay) => {
+                    tracing::warn!("Request failed ({}), retrying in {:?}", e, delay);
+                    on_retry(policy.notice(retries_done, delay));
+                    tokio::time::sleep(delay).await;
+                    retries_done += 1;
+                }
+                None => return Err(e),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Stream a request through the router, retrying transient failures.
+///
+/// Only failures before the stream starts are retried, so no tokens are repeated.
+/// Each retry is announced on `tx` as `StreamEvent::Retrying`.
+pub async fn stream_message(
+    router: &ProviderRouter,
+    provider_id: &ProviderId,
+    request: ChatRequest,
+    tx: mpsc::Sender<StreamEvent>,
+    policy: &RetryPolicy,
+) -> Result<(), ProviderError> {
+    let mut retries_done = 0;
+    loop {
+        match router
+            .stream_message(provider_id, request.clone(), tx.clone())
+            .await
+        {
+            Err(e) => match policy.delay(retries_done, &e) {
+                Some(delay) => {
+                    tracing::warn!("Stream failed to start ({}), retrying in {:?}", e, delay);
+                    let _ = tx
+                        .send(StreamEvent::Retrying(policy.notice(retries_done, delay)))
+                        .await;
+                    tokio::time::sleep(delay).await;
+                    retries_done += 1;
+                }
+                None => return Err(e),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Map an unsuccessful HTTP status to the matching `ProviderError`.
+pub fn status_error(
+    status: StatusCode,
+    message: String,
+    retry_after_secs: Option<u64>,
+) -> ProviderError {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        ProviderError::RateLimited { retry_after_secs }
+    } else if status.is_server_error() {
+        // Includes Anthropic's 529 "overloaded"
+        ProviderError::Unavailable {
+            message,
+            retry_after_secs,
+        }
+    } else {
+        ProviderError::RequestFailed(message)
+    }
+}
+
+/// Seconds to wait before retrying, from `retry-after` or the rate limit reset headers
+/// (`anthropic-ratelimit-*-reset`, `x-ratelimit-reset-*`). Providers send reset headers
+/// on every response, so they only count for a 429 or an exhausted bucket.
+pub fn retry_after_secs(status: StatusCode, headers: &HeaderMap) -> Option<u64> {
+    if let Some(ms) = header(headers, "retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Some((ms / 1000.0).ceil() as u64);
+    }
+
+    if let Some(value) = header(headers, "retry-after") {
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(secs);
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            return Some(secs_until(date.with_timezone(&chrono::Utc)));
+        }
+    }
+
+    // Wait for the exhausted bucket if the headers say which one it is, else the soonest reset
+    let mut exhausted: Option<u64> = None;
+    let mut soonest: Option<u64> = None;
+    for (name, value) in headers {
+        let name = name.as_str();
+        let (remaining_name, reset) = if let Some(bucket) = name
+            .strip_prefix("anthropic-ratelimit-")
+            .and_then(|n| n.strip_suffix("-reset"))
+        {
+            let reset = value
+                .to_str()
+                .ok()
+                .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+                .map(|date| secs_until(date.with_timezone(&chrono::Utc)));
+            (format!("anthropic-ratelimit-{}-remaining", bucket), reset)
+        } else if let Some(bucket) = name.strip_prefix("x-ratelimit-reset-") {
+            let reset = value.to_str().ok().and_then(parse_duration_secs);
+            (format!("x-ratelimit-remaining-{}", bucket), reset)
+        } else {
+            continue;
+        };
+
+        let Some(reset) = reset else {
+            continue;
+        };
+        if header(headers, &remaining_name) == Some("0") {
+            exhausted = Some(exhausted.map_or(reset, |e| e.max(reset)));
+        }
+        soonest = Some(soonest.map_or(reset, |s| s.min(reset)));
+    }
+
+    exhausted.or(soonest.filter(|_| status == StatusCode::TOO_MANY_REQUESTS))
+}
+
+/// Parse durations such as `1s`, `6m0s`, `1.5s` or `250ms`, rounded up to whole seconds.
+pub fn parse_duration_secs(value: &str) -> Option<u64> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" | "" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        total += number * scale;
+        rest = &rest[unit_len..];
+    }
+
+    Some(total.ceil() as u64)
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(str::trim)
+}
+
+fn secs_until(time: chrono::DateTime<chrono::Utc>) -> u64 {
+    let millis = (time - chrono::Utc::now()).num_milliseconds().max(0);
+    (millis as u64).div_ceil(1000)
+}
+
+/// A value in `[0, 1)` that differs between calls; good enough for jitter.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::UNIX_EPOCH
+            .elapsed()
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("1s"), Some(1));
+        assert_eq!(parse_duration_secs("6m0s"), Some(360));
+        assert_eq!(parse_duration_secs("1.5s"), Some(2));
+        assert_eq!(parse_duration_secs("250ms"), Some(1));
+        assert_eq!(parse_duration_secs("1h2m3s"), Some(3723));
+        assert_eq!(parse_duration_secs("soon"), None);
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("12"));
+        assert_eq!(
+            retry_after_secs(StatusCode::SERVICE_UNAVAILABLE, &headers),
+            Some(12)
+        );
+    }
+
+    #[test]
+    fn test_retry_after_prefers_exhausted_bucket() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset-requests", HeaderValue::from_static("2s"));
+        headers.insert(
+            "x-ratelimit-remaining-requests",
+            HeaderValue::from_static("10"),
+        );
+        headers.insert("x-ratelimit-reset-tokens", HeaderValue::from_static("30s"));
+        headers.insert(
+            "x-ratelimit-remaining-tokens",
+            HeaderValue::from_static("0"),
+        );
+        assert_eq!(
+            retry_after_secs(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(30)
+        );
+    }
+
+    #[test]
+    fn test_unrelated_reset_header_still_retries() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset-tokens", HeaderValue::from_static("120s"));
+        headers.insert(
+            "x-ratelimit-remaining-tokens",
+            HeaderValue::from_static("5000"),
+        );
+        let status = StatusCode::SERVICE_UNAVAILABLE;
+        let retry_after = retry_after_secs(status, &headers);
+        assert_eq!(retry_after, None);
+
+        let error = status_error(status, "HTTP 503".to_string(), retry_after);
+        assert!(RetryPolicy::default().delay(0, &error).is_some());
+
+        // A 429 without an exhausted bucket waits for the soonest reset
+        assert_eq!(
+            retry_after_secs(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(120)
+        );
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::default();
+        let limited = ProviderError::RateLimited {
+            retry_after_secs: Some(5),
+        };
+        assert_eq!(policy.delay(0, &limited), Some(Duration::from_secs(5)));
+        assert_eq!(policy.delay(3, &limited), None);
+
+        let too_long = ProviderError::RateLimited {
+            retry_after_secs: Some(600),
+        };
+        assert_eq!(policy.delay(0, &too_long), None);
+
+        let auth = ProviderError::AuthError("bad key".to_string());
+        assert_eq!(policy.delay(0, &auth), None);
+
+        let overloaded = ProviderError::Unavailable {
+            message: "HTTP 529: Overloaded".to_string(),
+            retry_after_secs: None,
+        };
+        let delay = policy.delay(1, &overloaded).unwrap();
+        assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+    }
+}
@@ -22,6 +22,18 @@ impl ProviderRouter {
         self.providers.insert(provider.provider_id(), provider);
     }
 
+    pub fn supports_vision(&self, provider_id: &ProviderId, model: &str) -> bool {
+        self.providers
+            .get(provider_id)
+            .is_some_and(|provider| provider.supports_vision(model))
+    }
+
+    pub fn supports_pdf(&self, provider_id: &ProviderId, model: &str) -> bool {
+        self.providers
+            .get(provider_id)
+            .is_some_and(|provider| provider.supports_pdf(model))
+    }
+
     pub async fn validate_credentials(
         &self,
         provider_id: &ProviderId,
@@ -8,6 +8,16 @@ use crate::models::ProviderId;
 pub trait AiProvider: Send + Sync {
     fn provider_id(&self) -> ProviderId;
 
+    /// Whether `model` accepts image input. Images sent to other models are dropped.
+    fn supports_vision(&self, _model: &str) -> bool {
+        true
+    }
+
+    /// Whether `model` reads PDF documents natively. Other models get the extracted text.
+    fn supports_pdf(&self, _model: &str) -> bool {
+        false
+    }
+
     async fn validate_credentials(
         &self,
         api_key: &str,
@@ -8,9 +8,16 @@ pub enum ProviderError {
     #[error("Authentication failed: {0}")]
     AuthError(String),
 
-    #[error("Rate limited: retry after {retry_after_secs:?}s")]
+    #[error("Rate limited by the provider")]
     RateLimited { retry_after_secs: Option<u64> },
 
+    /// Server-side failure (5xx or overloaded) that may succeed later.
+    #[error("Service unavailable: {message}")]
+    Unavailable {
+        message: String,
+        retry_after_secs: Option<u64>,
+    },
+
     #[error("Request failed: {0}")]
     RequestFailed(String),
 
@@ -21,18 +28,140 @@ pub enum ProviderError {
     InvalidResponse(String),
 }
 
+impl ProviderError {
+    /// Whether retrying the same request later may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimited { .. } | ProviderError::Unavailable { .. }
+        )
+    }
+
+    /// Whether another provider or model might succeed where this one failed.
+    pub fn should_fall_back(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::NetworkError(_)
+                | ProviderError::RateLimited { .. }
+                | ProviderError::Unavailable { .. }
+        )
+    }
+
+    /// How long the provider asked us to wait, if it said.
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            ProviderError::RateLimited { retry_after_secs }
+            | ProviderError::Unavailable {
+                retry_after_secs, ..
+            } => *retry_after_secs,
+            _ => None,
+        }
+    }
+}
+
+/// Announces that a failed request will be retried after `delay_secs`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryNotice {
+    /// 1-based number of the upcoming retry.
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub delay_secs: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct ImageAttachment {
     pub mime_type: String,
     pub data: Vec<u8>,
 }
 
+/// A non-image file, such as a PDF or source file, sent alongside a message.
+#[derive(Debug, Clone)]
+pub struct DocumentAttachment {
+    pub mime_type: String,
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+/// A function the model may call, described by a JSON Schema for its arguments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+/// A tool invocation requested by the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+impl ToolCall {
+    /// Build a call from JSON-encoded arguments as streamed by most APIs.
+    /// Empty or malformed arguments become an empty object.
+    pub fn from_json_arguments(id: String, name: String, arguments: &str) -> Self {
+        let arguments = serde_json::from_str(arguments)
+            .ok()
+            .filter(serde_json::Value::is_object)
+            .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+        Self {
+            id,
+            name,
+            arguments,
+        }
+    }
+}
+
+/// The outcome of running a `ToolCall`, sent back to the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub tool_call_id: String,
+    pub name: String,
+    pub content: String,
+    pub is_error: bool,
+}
+
+/// Model reasoning that preceded an answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThinkingBlock {
+    pub text: String,
+    /// Opaque signature Claude requires when thinking is sent back during tool use.
+    pub signature: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub role: Role,
     pub content: String,
     #[serde(skip)]
     pub images: Vec<ImageAttachment>,
+    #[serde(skip)]
+    pub documents: Vec<DocumentAttachment>,
+    /// Tool calls made by an assistant message.
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+    /// Tool results carried by a user-side message in reply to `tool_calls`.
+    #[serde(default)]
+    pub tool_results: Vec<ToolResult>,
+    /// Reasoning attached to an assistant message; only replayed within a tool loop.
+    #[serde(default)]
+    pub thinking: Option<ThinkingBlock>,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: String) -> Self {
+        Self {
+            role,
+            content,
+            images: Vec::new(),
+            documents: Vec::new(),
+            tool_calls: Vec::new(),
+            tool_results: Vec::new(),
+            thinking: None,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -44,6 +173,16 @@ pub struct ChatRequest {
     pub temperature: Option<f32>,
     pub system_prompt: Option<String>,
     pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    /// Not supported by OpenAI; dropped for it.
+    pub top_k: Option<u32>,
+    pub stop_sequences: Vec<String>,
+    /// Not supported by Claude; dropped for it.
+    pub seed: Option<i64>,
+    pub tools: Vec<ToolDefinition>,
+    /// Token budget for extended thinking. `Some(0)` turns it off where the model
+    /// allows; `None` leaves the provider's default.
+    pub thinking_budget: Option<u32>,
 }
 
 impl std::fmt::Debug for ChatRequest {
@@ -56,6 +195,12 @@ impl std::fmt::Debug for ChatRequest {
             .field("temperature", &self.temperature)
             .field("system_prompt", &self.system_prompt)
             .field("max_tokens", &self.max_tokens)
+            .field("top_p", &self.top_p)
+            .field("top_k", &self.top_k)
+            .field("stop_sequences", &self.stop_sequences)
+            .field("seed", &self.seed)
+            .field("tools", &self.tools)
+            .field("thinking_budget", &self.thinking_budget)
             .finish()
     }
 }
@@ -63,9 +208,19 @@ impl std::fmt::Debug for ChatRequest {
 #[derive(Debug, Clone)]
 pub enum StreamEvent {
     Token(String),
+    /// A complete tool call; emitted once its arguments have been fully received.
+    ToolCall(ToolCall),
+    /// A fragment of the model's reasoning, streamed separately from the answer.
+    Thinking(String),
+    /// Signature closing the reasoning block.
+    ThinkingSignature(String),
+    /// The request failed before streaming and will be retried.
+    Retrying(RetryNotice),
     Done {
         tokens_in: Option<i64>,
         tokens_out: Option<i64>,
+        /// Reasoning tokens, when the provider counts them apart from `tokens_out`.
+        thinking_tokens: Option<i64>,
     },
     Error(String),
 }
@@ -73,9 +228,13 @@ pub enum StreamEvent {
 #[derive(Debug, Clone)]
 pub struct ChatResponse {
     pub content: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub thinking: Option<ThinkingBlock>,
     pub model: String,
     pub tokens_in: Option<i64>,
     pub tokens_out: Option<i64>,
+    /// Reasoning tokens, when the provider counts them apart from `tokens_out`.
+    pub thinking_tokens: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
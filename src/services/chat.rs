@@ -3,9 +3,25 @@ use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use crate::models::{Account, ProviderId};
-use crate::providers::{ChatMessage, ChatRequest, ProviderRouter, StreamEvent};
+use crate::models::{Account, AssistantFile, GenerationParams, ProviderId, Role};
+use crate::providers::retry::{self, RetryPolicy};
+use crate::providers::{
+    ChatMessage, ChatRequest, DocumentAttachment, ImageAttachment, ProviderError, ProviderRouter,
+    RetryNotice, StreamEvent, ThinkingBlock, ToolCall, ToolDefinition,
+};
 use crate::services::settings::AppSettings;
+use crate::services::tools::ToolRegistry;
+
+/// Upper bound on model -> tool -> model round trips for a single reply.
+const MAX_TOOL_ROUNDS: usize = 8;
+
+/// Why a reply that was still calling tools at `MAX_TOOL_ROUNDS` has nothing to show.
+fn tool_round_limit_error() -> String {
+    format!(
+        "Stopped after {} rounds of tool calls without a reply",
+        MAX_TOOL_ROUNDS
+    )
+}
 
 /// Parameters needed to dispatch a chat request to an AI provider.
 pub struct ChatDispatchParams {
@@ -14,33 +30,99 @@ pub struct ChatDispatchParams {
     pub conversation_id: String,
     pub account_id: String,
     pub model_name: String,
+    /// The message the reply answers, fixed when the request is sent so switching
+    /// branches meanwhile doesn't move the reply.
+    pub parent_message_id: Option<String>,
+    /// Runs the model's tool calls; `None` when tool use is disabled.
+    pub tools: Option<Arc<ToolRegistry>>,
+    /// Tried in order when the request fails with an error another model might not hit.
+    pub fallbacks: Vec<FallbackRoute>,
+}
+
+/// A resolved fallback: the account's provider and credentials plus the model to use.
+pub struct FallbackRoute {
+    pub provider: ProviderId,
+    pub account_id: String,
+    pub model: String,
+    pub api_key: String,
+    pub base_url: Option<String>,
+}
+
+impl FallbackRoute {
+    /// Point `request` at this route's account and model, inlining PDFs the new model
+    /// can't read natively.
+    async fn apply(&self, request: &mut ChatRequest, router: &ProviderRouter) {
+        request.api_key = self.api_key.clone();
+        request.model = self.model.clone();
+        request.base_url = self.base_url.clone();
+
+        let native_pdf = router.supports_pdf(&self.provider, &self.model);
+        for message in &mut request.messages {
+            inline_documents(message, native_pdf).await;
+        }
+    }
+}
+
+/// Why a streaming round ended without completing.
+enum RoundError {
+    Stopped,
+    Ended,
+    Provider(ProviderError),
 }
 
 /// Result from a non-streaming AI call, ready to be turned into an AppCmd.
 pub struct ChatResult {
     pub conversation_id: String,
     pub content: String,
+    pub thinking: Option<String>,
     pub model: String,
     pub tokens_in: Option<i64>,
     pub tokens_out: Option<i64>,
+    pub thinking_tokens: Option<i64>,
     pub account_id: String,
+    pub parent_message_id: Option<String>,
+    /// Error from the requested model when a fallback model answered instead.
+    pub fallback_error: Option<String>,
 }
 
 /// Result from streaming: either a token update, completion, or error.
 pub enum StreamResult {
+    /// Text added to the reply since the last `Token`.
     Token {
         conversation_id: String,
         message_id: String,
-        accumulated: String,
+        delta: String,
+    },
+    /// Reasoning added since the last `Thinking`.
+    Thinking {
+        conversation_id: String,
+        message_id: String,
+        delta: String,
+    },
+    Retrying {
+        conversation_id: String,
+        notice: RetryNotice,
+    },
+    /// The current model failed and the request moved on to `model`.
+    FallingBack {
+        conversation_id: String,
+        message_id: String,
+        model: String,
+        error: String,
+        /// Reasoning from earlier rounds; the failed round's is dropped.
+        thinking: String,
     },
     Done {
         conversation_id: String,
         message_id: String,
         full_content: String,
+        thinking: Option<String>,
         model: String,
         tokens_in: Option<i64>,
         tokens_out: Option<i64>,
+        thinking_tokens: Option<i64>,
         account_id: String,
+        parent_message_id: Option<String>,
     },
     Error {
         conversation_id: String,
@@ -50,6 +132,9 @@ pub enum StreamResult {
 }
 
 /// Build a `ChatRequest` from the resolved parameters.
+///
+/// The conversation's own parameters take precedence over the global settings.
+#[allow(clippy::too_many_arguments)]
 pub fn build_request(
     api_key: String,
     model: &str,
@@ -57,12 +142,12 @@ pub fn build_request(
     account: &Account,
     settings: &AppSettings,
     system_prompt: Option<String>,
+    params: &GenerationParams,
+    tools: Vec<ToolDefinition>,
 ) -> ChatRequest {
-    let temperature = if (settings.temperature - 1.0).abs() < f32::EPSILON {
-        None
-    } else {
-        Some(settings.temperature)
-    };
+    let temperature = params.temperature.or_else(|| {
+        ((settings.temperature - 1.0).abs() >= f32::EPSILON).then_some(settings.temperature)
+    });
 
     ChatRequest {
         api_key,
@@ -71,44 +156,276 @@ pub fn build_request(
         base_url: account.api_base_url.clone(),
         temperature,
         system_prompt,
-        max_tokens: None,
+        max_tokens: params.max_tokens,
+        top_p: params.top_p,
+        top_k: params.top_k,
+        stop_sequences: params.stop_sequences.clone(),
+        seed: params.seed,
+        tools,
+        thinking_budget: settings.thinking_budget,
     }
 }
 
 /// Convert `Message` list to `ChatMessage` list for the provider API.
+///
+/// Every message keeps its stored attachments, so files from earlier turns stay in
+/// the model's context. Documents still need `inline_documents` for the target model.
 pub fn messages_to_chat_messages(messages: &[crate::models::Message]) -> Vec<ChatMessage> {
     messages
         .iter()
-        .map(|m| ChatMessage {
-            role: m.role,
-            content: m.content.clone(),
-            images: Vec::new(),
+        .map(|m| {
+            let mut message = ChatMessage::new(m.role, m.content.clone());
+            for attachment in &m.attachments {
+                if attachment.mime_type.starts_with("image/") {
+                    message.images.push(ImageAttachment {
+                        mime_type: attachment.mime_type.clone(),
+                        data: attachment.data.clone(),
+                    });
+                } else {
+                    message.documents.push(DocumentAttachment {
+                        mime_type: attachment.mime_type.clone(),
+                        filename: attachment.filename.clone().unwrap_or_default(),
+                        data: attachment.data.clone(),
+                    });
+                }
+            }
+            message
         })
         .collect()
 }
 
+/// Inline attached documents into the message text as fenced blocks, ahead of the user's text.
+///
+/// Text files are always inlined. PDFs stay attached when the model reads them natively
+/// (`native_pdf`); otherwise their extracted text is inlined instead.
+pub async fn inline_documents(message: &mut ChatMessage, native_pdf: bool) {
+    let mut blocks = String::new();
+
+    for doc in std::mem::take(&mut message.documents) {
+        if doc.mime_type == "application/pdf" {
+            if native_pdf {
+                message.documents.push(doc);
+                continue;
+            }
+            let data = doc.data;
+            let text =
+                tokio::task::spawn_blocking(move || crate::services::pdf::extract_text(&data))
+                    .await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| {
+                        "(no text could be extracted from this document)".to_string()
+                    });
+            append_file_block(&mut blocks, &doc.filename, "", &text);
+        } else {
+            let language = std::path::Path::new(&doc.filename)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("");
+            let text = String::from_utf8_lossy(&doc.data);
+            append_file_block(&mut blocks, &doc.filename, language, &text);
+        }
+    }
+
+    if !blocks.is_empty() {
+        if !message.content.is_empty() {
+            blocks.push_str("\n\n");
+        }
+        message.content.insert_str(0, &blocks);
+    }
+}
+
+/// Add an assistant's reference files to the system prompt, so they reach the model
+/// with every request rather than only with the message that first carried them.
+pub async fn with_reference_files(
+    system_prompt: Option<String>,
+    files: Vec<AssistantFile>,
+) -> Option<String> {
+    if files.is_empty() {
+        return system_prompt;
+    }
+
+    let mut reference = ChatMessage::new(Role::User, String::new());
+    reference.documents = files
+        .into_iter()
+        .map(|f| DocumentAttachment {
+            mime_type: f.mime_type,
+            filename: f.filename,
+            data: f.data,
+        })
+        .collect();
+    inline_documents(&mut reference, false).await;
+
+    let mut prompt = system_prompt.unwrap_or_default();
+    if !prompt.is_empty() {
+        prompt.push_str("\n\n");
+    }
+    prompt.push_str("Reference files:\n\n");
+    prompt.push_str(&reference.content);
+    Some(prompt)
+}
+
+/// Append `File: <name>` followed by the contents in a fenced block.
+fn append_file_block(out: &mut String, filename: &str, language: &str, text: &str) {
+    // The fence must be longer than any backtick run inside the file
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str(&format!(
+        "File: {}\n{}{}\n{}\n{}",
+        filename,
+        fence,
+        language,
+        text.trim_end(),
+        fence
+    ));
+}
+
+/// Run the requested tools and append the exchange to the conversation sent to the model.
+///
+/// The exchange only lives in the request; the stored conversation keeps the final reply.
+async fn append_tool_exchange(
+    request: &mut ChatRequest,
+    tools: &ToolRegistry,
+    content: String,
+    thinking: Option<ThinkingBlock>,
+    tool_calls: Vec<ToolCall>,
+) {
+    let mut tool_results = Vec::with_capacity(tool_calls.len());
+    for call in &tool_calls {
+        tool_results.push(tools.execute(call).await);
+    }
+
+    request.messages.push(ChatMessage {
+        tool_calls,
+        thinking,
+        ..ChatMessage::new(Role::Assistant, content)
+    });
+    request.messages.push(ChatMessage {
+        tool_results,
+        ..ChatMessage::new(Role::User, String::new())
+    });
+}
+
+/// Join text produced across tool rounds, separating rounds with a blank line.
+fn append_round(total: &mut String, round: &str) {
+    if round.is_empty() {
+        return;
+    }
+    if !total.is_empty() {
+        total.push_str("\n\n");
+    }
+    total.push_str(round);
+}
+
+fn add_tokens(total: Option<i64>, round: Option<i64>) -> Option<i64> {
+    match (total, round) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
 /// Run a non-streaming AI request. Returns a `ChatResult` on success.
-pub async fn send_non_streaming(
+///
+/// Tool calls are executed and answered until the model replies with text only.
+/// Transient failures are retried, announcing each retry through `on_retry`.
+pub async fn send_non_streaming<F>(
     router: Arc<ProviderRouter>,
     params: ChatDispatchParams,
-) -> Result<ChatResult, String> {
-    match router.send_message(&params.provider, params.request).await {
-        Ok(response) => Ok(ChatResult {
-            conversation_id: params.conversation_id,
-            content: response.content,
-            model: response.model,
-            tokens_in: response.tokens_in,
-            tokens_out: response.tokens_out,
-            account_id: params.account_id,
-        }),
-        Err(e) => Err(format!("AI error: {}", e)),
+    mut on_retry: F,
+) -> Result<ChatResult, String>
+where
+    F: FnMut(RetryNotice) + Send,
+{
+    let policy = RetryPolicy::default();
+    let mut request = params.request;
+    let mut provider = params.provider;
+    let mut account_id = params.account_id;
+    let mut fallbacks = params.fallbacks.into_iter();
+    let mut fallback_error = None;
+    let mut content = String::new();
+    let mut thinking = String::new();
+    let mut tokens_in = None;
+    let mut tokens_out = None;
+    let mut thinking_tokens = None;
+    let mut round = 0;
+
+    loop {
+        let response =
+            match retry::send_message(&router, &provider, request.clone(), &policy, &mut on_retry)
+                .await
+            {
+                Ok(response) => response,
+                Err(e) => match fallbacks.next().filter(|_| e.should_fall_back()) {
+                    Some(route) => {
+                        tracing::warn!(
+                            "{} failed ({}), falling back to {}",
+                            request.model,
+                            e,
+                            route.model
+                        );
+                        route.apply(&mut request, &router).await;
+                        provider = route.provider;
+                        account_id = route.account_id;
+                        fallback_error = Some(e.to_string());
+                        continue;
+                    }
+                    None => return Err(format!("AI error: {}", e)),
+                },
+            };
+
+        tokens_in = add_tokens(tokens_in, response.tokens_in);
+        tokens_out = add_tokens(tokens_out, response.tokens_out);
+        thinking_tokens = add_tokens(thinking_tokens, response.thinking_tokens);
+        append_round(&mut content, &response.content);
+        if let Some(block) = &response.thinking {
+            append_round(&mut thinking, &block.text);
+        }
+
+        match &params.tools {
+            Some(tools) if !response.tool_calls.is_empty() && round < MAX_TOOL_ROUNDS => {
+                append_tool_exchange(
+                    &mut request,
+                    tools,
+                    response.content,
+                    response.thinking,
+                    response.tool_calls,
+                )
+                .await;
+                round += 1;
+            }
+            _ => {
+                if content.is_empty() {
+                    return Err(if response.tool_calls.is_empty() {
+                        "AI error: No content in response".to_string()
+                    } else {
+                        format!("AI error: {}", tool_round_limit_error())
+                    });
+                }
+                return Ok(ChatResult {
+                    conversation_id: params.conversation_id,
+                    content,
+                    thinking: Some(thinking).filter(|t| !t.is_empty()),
+                    model: response.model,
+                    tokens_in,
+                    tokens_out,
+                    thinking_tokens,
+                    account_id,
+                    parent_message_id: params.parent_message_id,
+                    fallback_error,
+                });
+            }
+        }
     }
 }
 
 /// Run a streaming AI request, sending `StreamResult` events through a callback.
 ///
-/// `on_event` is called for each streaming event. It returns `false` to stop processing
-/// (not used currently but available for future use).
+/// `on_event` is called for each streaming event. When the model requests tools, they are
+/// executed and a follow-up request is streamed into the same message.
 pub async fn run_streaming<F>(
     router: Arc<ProviderRouter>,
     params: ChatDispatchParams,
@@ -118,96 +435,185 @@ pub async fn run_streaming<F>(
 ) where
     F: FnMut(StreamResult) + Send,
 {
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<StreamEvent>(64);
-
-    let provider = params.provider;
-    let request = params.request;
+    let mut provider = params.provider;
+    let mut request = params.request;
     let conv_id = params.conversation_id;
-    let acc_id = params.account_id;
-    let model = params.model_name;
-
-    let _stream_handle = tokio::spawn(async move {
-        if let Err(e) = router.stream_message(&provider, request, tx.clone()).await {
-            let _ = tx.send(StreamEvent::Error(e.to_string())).await;
-        }
-    });
+    let mut acc_id = params.account_id;
+    let mut model = params.model_name;
+    let tools = params.tools;
+    let mut fallbacks = params.fallbacks.into_iter();
 
     let mut accumulated = String::new();
+    let mut accumulated_thinking = String::new();
+    let mut tokens_in = None;
+    let mut tokens_out = None;
+    let mut thinking_tokens = None;
+    let mut round = 0;
 
     loop {
-        tokio::select! {
-            _ = cancel_token.cancelled() => {
-                if !accumulated.is_empty() {
-                    on_event(StreamResult::Done {
-                        conversation_id: conv_id,
-                        message_id,
-                        full_content: accumulated,
-                        model,
-                        tokens_in: None,
-                        tokens_out: None,
-                        account_id: acc_id,
-                    });
-                } else {
-                    on_event(StreamResult::Error {
-                        conversation_id: conv_id,
-                        message_id,
-                        error: "Generation stopped".to_string(),
-                    });
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<StreamEvent>(64);
+
+        let round_router = router.clone();
+        let round_request = request.clone();
+        let mut stream_handle = tokio::spawn(async move {
+            let policy = RetryPolicy::default();
+            retry::stream_message(&round_router, &provider, round_request, tx, &policy).await
+        });
+
+        let mut round_text = String::new();
+        let mut round_thinking = String::new();
+        let thinking_before = accumulated_thinking.len();
+        let mut thinking_signature = None;
+        let mut tool_calls = Vec::new();
+
+        let outcome: Result<(), RoundError> = loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    // Don't leave a pending retry running in the background
+                    stream_handle.abort();
+                    break Err(RoundError::Stopped);
                 }
-                return;
-            }
-            event = rx.recv() => {
-                match event {
-                    Some(StreamEvent::Token(token)) => {
-                        accumulated.push_str(&token);
-                        on_event(StreamResult::Token {
-                            conversation_id: conv_id.clone(),
-                            message_id: message_id.clone(),
-                            accumulated: accumulated.clone(),
-                        });
-                    }
-                    Some(StreamEvent::Done { tokens_in, tokens_out }) => {
-                        on_event(StreamResult::Done {
-                            conversation_id: conv_id,
-                            message_id,
-                            full_content: accumulated,
-                            model,
-                            tokens_in,
-                            tokens_out,
-                            account_id: acc_id,
-                        });
-                        return;
-                    }
-                    Some(StreamEvent::Error(error)) => {
-                        on_event(StreamResult::Error {
-                            conversation_id: conv_id,
-                            message_id,
-                            error,
-                        });
-                        return;
-                    }
-                    None => {
-                        if !accumulated.is_empty() {
-                            on_event(StreamResult::Done {
-                                conversation_id: conv_id,
-                                message_id,
-                                full_content: accumulated,
-                                model,
-                                tokens_in: None,
-                                tokens_out: None,
-                                account_id: acc_id,
+                event = rx.recv() => {
+                    match event {
+                        Some(StreamEvent::Token(token)) => {
+                            let mut delta = String::new();
+                            if round_text.is_empty() && !accumulated.is_empty() {
+                                delta.push_str("\n\n");
+                            }
+                            delta.push_str(&token);
+                            round_text.push_str(&token);
+                            accumulated.push_str(&delta);
+                            on_event(StreamResult::Token {
+                                conversation_id: conv_id.clone(),
+                                message_id: message_id.clone(),
+                                delta,
                             });
-                        } else {
+                        }
+                        Some(StreamEvent::Thinking(text)) => {
+                            let mut delta = String::new();
+                            if round_thinking.is_empty() && !accumulated_thinking.is_empty() {
+                                delta.push_str("\n\n");
+                            }
+                            delta.push_str(&text);
+                            round_thinking.push_str(&text);
+                            accumulated_thinking.push_str(&delta);
+                            on_event(StreamResult::Thinking {
+                                conversation_id: conv_id.clone(),
+                                message_id: message_id.clone(),
+                                delta,
+                            });
+                        }
+                        Some(StreamEvent::ThinkingSignature(signature)) => {
+                            thinking_signature = Some(signature);
+                        }
+                        Some(StreamEvent::Retrying(notice)) => {
+                            on_event(StreamResult::Retrying {
+                                conversation_id: conv_id.clone(),
+                                notice,
+                            });
+                        }
+                        Some(StreamEvent::ToolCall(call)) => tool_calls.push(call),
+                        Some(StreamEvent::Done {
+                            tokens_in: round_in,
+                            tokens_out: round_out,
+                            thinking_tokens: round_thinking_tokens,
+                        }) => {
+                            tokens_in = add_tokens(tokens_in, round_in);
+                            tokens_out = add_tokens(tokens_out, round_out);
+                            thinking_tokens = add_tokens(thinking_tokens, round_thinking_tokens);
+                            break Ok(());
+                        }
+                        Some(StreamEvent::Error(error)) => {
                             on_event(StreamResult::Error {
                                 conversation_id: conv_id,
                                 message_id,
-                                error: "Stream ended unexpectedly".to_string(),
+                                error,
                             });
+                            return;
+                        }
+                        None => {
+                            // The stream task has finished; see whether it failed to start
+                            match (&mut stream_handle).await {
+                                Ok(Err(e)) => break Err(RoundError::Provider(e)),
+                                _ => break Err(RoundError::Ended),
+                            }
                         }
-                        return;
                     }
                 }
             }
+        };
+
+        match (&tools, outcome) {
+            (Some(tools), Ok(())) if !tool_calls.is_empty() && round < MAX_TOOL_ROUNDS => {
+                let thinking = (!round_thinking.is_empty()).then_some(ThinkingBlock {
+                    text: round_thinking,
+                    signature: thinking_signature,
+                });
+                append_tool_exchange(&mut request, tools, round_text, thinking, tool_calls).await;
+                round += 1;
+            }
+            (_, Err(RoundError::Provider(e))) => {
+                let route = fallbacks
+                    .next()
+                    .filter(|_| e.should_fall_back() && round_text.is_empty());
+                let Some(route) = route else {
+                    on_event(StreamResult::Error {
+                        conversation_id: conv_id,
+                        message_id,
+                        error: e.to_string(),
+                    });
+                    return;
+                };
+
+                tracing::warn!("{} failed ({}), falling back to {}", model, e, route.model);
+                route.apply(&mut request, &router).await;
+                provider = route.provider;
+                acc_id = route.account_id;
+                model = route.model;
+                accumulated_thinking.truncate(thinking_before);
+                on_event(StreamResult::FallingBack {
+                    conversation_id: conv_id.clone(),
+                    message_id: message_id.clone(),
+                    model: model.clone(),
+                    error: e.to_string(),
+                    thinking: accumulated_thinking.clone(),
+                });
+            }
+            (_, Ok(())) if !tool_calls.is_empty() && accumulated.is_empty() => {
+                on_event(StreamResult::Error {
+                    conversation_id: conv_id,
+                    message_id,
+                    error: tool_round_limit_error(),
+                });
+                return;
+            }
+            (_, Err(error)) if accumulated.is_empty() => {
+                let error = match error {
+                    RoundError::Stopped => "Generation stopped",
+                    _ => "Stream ended unexpectedly",
+                };
+                on_event(StreamResult::Error {
+                    conversation_id: conv_id,
+                    message_id,
+                    error: error.to_string(),
+                });
+                return;
+            }
+            _ => {
+                on_event(StreamResult::Done {
+                    conversation_id: conv_id,
+                    message_id,
+                    full_content: accumulated,
+                    thinking: Some(accumulated_thinking).filter(|t| !t.is_empty()),
+                    model,
+                    tokens_in,
+                    tokens_out,
+                    thinking_tokens,
+                    account_id: acc_id,
+                    parent_message_id: params.parent_message_id,
+                });
+                return;
+            }
         }
     }
 }
@@ -216,3 +622,71 @@ pub async fn run_streaming<F>(
 pub fn new_message_id() -> String {
     Uuid::new_v4().to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Attachment, Message};
+    use chrono::Utc;
+
+    fn message(role: Role, content: &str, attachments: Vec<Attachment>) -> Message {
+        Message {
+            id: Uuid::new_v4().to_string(),
+            conversation_id: "conv".to_string(),
+            role,
+            content: content.to_string(),
+            thinking: None,
+            model: None,
+            tokens_in: None,
+            tokens_out: None,
+            thinking_tokens: None,
+            parent_message_id: None,
+            is_active: true,
+            created_at: Utc::now(),
+            attachments,
+            branch: None,
+        }
+    }
+
+    fn attachment(mime_type: &str, filename: Option<&str>, data: &[u8]) -> Attachment {
+        Attachment {
+            id: Uuid::new_v4().to_string(),
+            message_id: String::new(),
+            mime_type: mime_type.to_string(),
+            filename: filename.map(str::to_string),
+            data: data.to_vec(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_keeps_attachments() {
+        let history = [
+            message(
+                Role::User,
+                "What's in these?",
+                vec![
+                    attachment("image/png", None, b"png"),
+                    attachment("text/plain", Some("notes.txt"), b"buy milk"),
+                ],
+            ),
+            message(Role::Assistant, "A picture and a list.", Vec::new()),
+            message(Role::User, "What was on the list?", Vec::new()),
+        ];
+
+        let mut chat_messages = messages_to_chat_messages(&history);
+        for message in &mut chat_messages {
+            inline_documents(message, false).await;
+        }
+
+        assert_eq!(chat_messages.len(), 3);
+        assert_eq!(chat_messages[0].images.len(), 1);
+        assert_eq!(chat_messages[0].images[0].data, b"png");
+        assert!(chat_messages[0].documents.is_empty());
+        assert_eq!(
+            chat_messages[0].content,
+            "File: notes.txt\n```txt\nbuy milk\n```\n\nWhat's in these?"
+        );
+        assert_eq!(chat_messages[2].content, "What was on the list?");
+    }
+}
@@ -1,14 +1,18 @@
 use anyhow::{bail, Result};
+use chrono::Utc;
+use uuid::Uuid;
 
-use crate::models::{Message, Role};
+use crate::models::{Attachment, BranchPosition, Conversation, Message, Role};
 use crate::services::database::Database;
 
-/// Load messages for a conversation with attachments populated for user messages.
+/// Load messages for a conversation with attachments populated for user messages
+/// and branch positions for messages that have alternatives.
 pub async fn load_messages_with_attachments(
     db: &Database,
     conversation_id: &str,
 ) -> Result<Vec<Message>> {
     let mut messages = db.list_messages(conversation_id).await?;
+    let tree = db.list_message_parents(conversation_id).await?;
     for msg in &mut messages {
         if msg.role == Role::User {
             if let Ok(atts) = db.list_attachments(&msg.id).await {
@@ -17,12 +21,29 @@ pub async fn load_messages_with_attachments(
                 }
             }
         }
+        msg.branch = branch_position(&tree, &msg.id);
     }
     Ok(messages)
 }
 
-/// Prepare for message regeneration: deactivate the target assistant message
-/// and everything after it, then return the remaining active messages.
+/// ID of the last message on the active path, which the next message replies to.
+pub async fn active_leaf_id(db: &Database, conversation_id: &str) -> Result<Option<String>> {
+    Ok(db.list_messages(conversation_id).await?.pop().map(|m| m.id))
+}
+
+/// Branch position of a single message, if it has alternatives.
+pub async fn load_branch_position(
+    db: &Database,
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<Option<BranchPosition>> {
+    let tree = db.list_message_parents(conversation_id).await?;
+    Ok(branch_position(&tree, message_id))
+}
+
+/// Prepare for message regeneration: hide the target assistant message and
+/// its replies, keeping them as a branch, then return the remaining active messages.
+/// The new reply becomes a sibling of the target.
 pub async fn prepare_regeneration(
     db: &Database,
     conversation_id: &str,
@@ -30,56 +51,198 @@ pub async fn prepare_regeneration(
 ) -> Result<Vec<Message>> {
     let messages = db.list_messages(conversation_id).await?;
 
-    let assistant_idx = messages
+    let assistant = messages
         .iter()
-        .position(|m| m.id == assistant_msg_id)
+        .find(|m| m.id == assistant_msg_id)
         .ok_or_else(|| anyhow::anyhow!("Message not found"))?;
 
-    let user_msg = messages[..assistant_idx]
-        .iter()
-        .rev()
-        .find(|m| m.role == Role::User);
-
-    let cutoff_ts = match user_msg {
-        Some(m) => m.created_at.to_rfc3339(),
-        None => bail!("No preceding user message found"),
-    };
-
-    if let Err(e) = db
-        .deactivate_messages_after(conversation_id, &cutoff_ts)
-        .await
-    {
-        tracing::error!("Failed to deactivate messages: {}", e);
+    if assistant.parent_message_id.is_none() {
+        bail!("No preceding user message found");
     }
 
+    let tree = db.list_message_parents(conversation_id).await?;
+    db.update_active_messages(&subtree(&tree, assistant_msg_id), &[])
+        .await?;
+
     load_messages_with_attachments(db, conversation_id).await
 }
 
-/// Prepare for message editing: update content, deactivate messages after
-/// the edited one, then return remaining active messages.
+/// Prepare for message editing: add the edited text as a sibling of the original
+/// message and hide the original branch, then return the active messages.
 pub async fn prepare_edit(
     db: &Database,
     conversation_id: &str,
     msg_id: &str,
     new_content: &str,
 ) -> Result<Vec<Message>> {
-    db.update_message_content(msg_id, new_content).await?;
-
     let messages = db.list_messages(conversation_id).await?;
 
-    if let Some(edited) = messages.iter().find(|m| m.id == msg_id) {
-        let after_ts = edited.created_at.to_rfc3339();
-        if let Err(e) = db
-            .deactivate_messages_after(conversation_id, &after_ts)
-            .await
-        {
-            tracing::error!("Failed to deactivate messages: {}", e);
+    let original = messages
+        .iter()
+        .find(|m| m.id == msg_id)
+        .ok_or_else(|| anyhow::anyhow!("Message not found"))?;
+
+    let now = Utc::now();
+    let edited = Message {
+        id: Uuid::new_v4().to_string(),
+        content: new_content.to_string(),
+        created_at: now,
+        is_active: true,
+        attachments: Vec::new(),
+        branch: None,
+        ..original.clone()
+    };
+
+    let tree = db.list_message_parents(conversation_id).await?;
+    db.update_active_messages(&subtree(&tree, msg_id), &[])
+        .await?;
+    db.insert_message(&edited).await?;
+
+    // The edit keeps the original's attachments
+    for attachment in db.list_attachments(msg_id).await? {
+        let copy = Attachment {
+            id: Uuid::new_v4().to_string(),
+            message_id: edited.id.clone(),
+            created_at: now,
+            ..attachment
+        };
+        if let Err(e) = db.insert_attachment(&copy).await {
+            tracing::error!("Failed to copy attachment: {}", e);
         }
     }
 
     load_messages_with_attachments(db, conversation_id).await
 }
 
+/// Copy the active messages of `source` up to and including `message_id`, with their
+/// attachments, into a new conversation on the given account and model.
+pub async fn fork_conversation(
+    db: &Database,
+    source: &Conversation,
+    message_id: &str,
+    account_id: &str,
+    model: &str,
+) -> Result<Conversation> {
+    let messages = db.list_messages(&source.id).await?;
+    let end = messages
+        .iter()
+        .position(|m| m.id == message_id)
+        .ok_or_else(|| anyhow::anyhow!("Message not found"))?;
+
+    let now = Utc::now();
+    let conversation = Conversation {
+        id: Uuid::new_v4().to_string(),
+        account_id: account_id.to_string(),
+        title: format!("{} (fork)", source.title),
+        model: model.to_string(),
+        system_prompt: source.system_prompt.clone(),
+        params: source.params.clone(),
+        pinned: false,
+        folder_id: None,
+        tags: Vec::new(),
+        assistant_id: source.assistant_id.clone(),
+        last_message_preview: None,
+        created_at: now,
+        updated_at: now,
+    };
+    db.insert_conversation(&conversation).await?;
+
+    // Only the active path is copied, so each message replies to the previous one
+    let mut parent_message_id = None;
+    for msg in &messages[..=end] {
+        let copy = Message {
+            id: Uuid::new_v4().to_string(),
+            conversation_id: conversation.id.clone(),
+            parent_message_id: parent_message_id.clone(),
+            is_active: true,
+            attachments: Vec::new(),
+            branch: None,
+            ..msg.clone()
+        };
+        db.insert_message(&copy).await?;
+
+        for attachment in db.list_attachments(&msg.id).await? {
+            let attachment = Attachment {
+                id: Uuid::new_v4().to_string(),
+                message_id: copy.id.clone(),
+                ..attachment
+            };
+            db.insert_attachment(&attachment).await?;
+        }
+        parent_message_id = Some(copy.id);
+    }
+
+    Ok(conversation)
+}
+
+/// Make the `target_index`th alternative of a message active in its place, following
+/// the latest reply at each level below it, then return the active messages.
+pub async fn switch_branch(
+    db: &Database,
+    conversation_id: &str,
+    message_id: &str,
+    target_index: usize,
+) -> Result<Vec<Message>> {
+    let tree = db.list_message_parents(conversation_id).await?;
+
+    let parent = tree
+        .iter()
+        .find(|(id, _)| id == message_id)
+        .map(|(_, parent)| parent.as_deref())
+        .ok_or_else(|| anyhow::anyhow!("Message not found"))?;
+    let target = children(&tree, parent)
+        .nth(target_index)
+        .ok_or_else(|| anyhow::anyhow!("Branch not found"))?;
+
+    if target != message_id {
+        db.update_active_messages(&subtree(&tree, message_id), &latest_path(&tree, target))
+            .await?;
+    }
+
+    load_messages_with_attachments(db, conversation_id).await
+}
+
+/// IDs of the messages replying to `parent` (roots for `None`), oldest first.
+fn children<'a>(
+    tree: &'a [(String, Option<String>)],
+    parent: Option<&'a str>,
+) -> impl Iterator<Item = &'a str> + 'a {
+    tree.iter()
+        .filter(move |(_, p)| p.as_deref() == parent)
+        .map(|(id, _)| id.as_str())
+}
+
+/// `root` and every message below it.
+fn subtree(tree: &[(String, Option<String>)], root: &str) -> Vec<String> {
+    let mut ids = vec![root.to_string()];
+    let mut i = 0;
+    while i < ids.len() {
+        let below: Vec<String> = children(tree, Some(&ids[i])).map(str::to_string).collect();
+        ids.extend(below);
+        i += 1;
+    }
+    ids
+}
+
+/// `from` followed by the latest reply at each level below it.
+fn latest_path(tree: &[(String, Option<String>)], from: &str) -> Vec<String> {
+    let mut path = vec![from.to_string()];
+    while let Some(next) = children(tree, path.last().map(String::as_str)).last() {
+        path.push(next.to_string());
+    }
+    path
+}
+
+fn branch_position(tree: &[(String, Option<String>)], message_id: &str) -> Option<BranchPosition> {
+    let (_, parent) = tree.iter().find(|(id, _)| id == message_id)?;
+    let siblings: Vec<&str> = children(tree, parent.as_deref()).collect();
+    let index = siblings.iter().position(|id| *id == message_id)?;
+    (siblings.len() > 1).then_some(BranchPosition {
+        index,
+        count: siblings.len(),
+    })
+}
+
 /// Truncate text to a short title for conversations.
 pub fn truncate_title(text: &str) -> String {
     let first_line = text.lines().next().unwrap_or(text);
@@ -95,3 +258,37 @@ pub fn truncate_title(text: &str) -> String {
         first_line.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, parent: Option<&str>) -> (String, Option<String>) {
+        (id.to_string(), parent.map(str::to_string))
+    }
+
+    #[test]
+    fn test_branch_tree() {
+        // u1 -> a1 (regenerated as a2) ; a2 -> u2 ; u1 edited as u1b -> a3
+        let tree = vec![
+            node("u1", None),
+            node("a1", Some("u1")),
+            node("a2", Some("u1")),
+            node("u2", Some("a2")),
+            node("u1b", None),
+            node("a3", Some("u1b")),
+        ];
+
+        assert_eq!(subtree(&tree, "u1"), vec!["u1", "a1", "a2", "u2"]);
+        assert_eq!(latest_path(&tree, "u1"), vec!["u1", "a2", "u2"]);
+        assert_eq!(
+            branch_position(&tree, "a1"),
+            Some(BranchPosition { index: 0, count: 2 })
+        );
+        assert_eq!(
+            branch_position(&tree, "u1b"),
+            Some(BranchPosition { index: 1, count: 2 })
+        );
+        assert_eq!(branch_position(&tree, "u2"), None);
+    }
+}
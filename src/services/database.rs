@@ -6,7 +6,14 @@ use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection};
 use tokio::task;
 
-use crate::models::{Account, AccountStatus, Attachment, Conversation, Message, ProviderId, Role};
+use crate::models::{
+    Account, AccountStatus, Assistant, AssistantFile, Attachment, Conversation, Folder,
+    GenerationParams, Message, MessageSearchHit, PromptPreset, PromptTemplate, ProviderId, Role,
+    Tag, SNIPPET_MATCH_END, SNIPPET_MATCH_START,
+};
+
+/// Most hits returned by `search_messages`.
+const SEARCH_RESULT_LIMIT: usize = 50;
 
 #[derive(Debug, Clone)]
 pub struct Database {
@@ -175,6 +182,193 @@ impl Database {
             )?;
         }
 
+        if version < 6 {
+            conn.execute_batch(
+                "ALTER TABLE messages ADD COLUMN thinking TEXT;
+
+                 UPDATE schema_version SET version = 6;",
+            )?;
+        }
+
+        if version < 7 {
+            conn.execute_batch(
+                "ALTER TABLE conversations ADD COLUMN temperature REAL;
+                 ALTER TABLE conversations ADD COLUMN max_tokens INTEGER;
+                 ALTER TABLE conversations ADD COLUMN top_p REAL;
+                 ALTER TABLE conversations ADD COLUMN top_k INTEGER;
+                 ALTER TABLE conversations ADD COLUMN stop_sequences TEXT;
+                 ALTER TABLE conversations ADD COLUMN seed INTEGER;
+
+                 UPDATE schema_version SET version = 7;",
+            )?;
+        }
+
+        if version < 8 {
+            // Full-text index over message content, kept in sync by triggers.
+            // It references messages by rowid, which only VACUUM could renumber;
+            // `INSERT INTO messages_fts(messages_fts) VALUES ('rebuild')` repairs it.
+            conn.execute_batch(
+                "CREATE VIRTUAL TABLE messages_fts USING fts5(
+                    content,
+                    content='messages',
+                    content_rowid='rowid',
+                    tokenize='unicode61 remove_diacritics 2'
+                 );
+
+                 CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages BEGIN
+                    INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+                 END;
+                 CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages BEGIN
+                    INSERT INTO messages_fts(messages_fts, rowid, content)
+                        VALUES ('delete', old.rowid, old.content);
+                 END;
+                 CREATE TRIGGER messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+                    INSERT INTO messages_fts(messages_fts, rowid, content)
+                        VALUES ('delete', old.rowid, old.content);
+                    INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+                 END;
+
+                 INSERT INTO messages_fts(messages_fts) VALUES ('rebuild');
+
+                 UPDATE schema_version SET version = 8;",
+            )?;
+        }
+
+        if version < 9 {
+            // Messages form a tree; earlier versions never recorded parents, so link each
+            // message to the nearest earlier message of the other role. Replies hidden by an
+            // edit or regeneration hang off the active message they followed.
+            conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_messages_parent ON messages(parent_message_id);
+
+                 UPDATE messages SET parent_message_id = (
+                    SELECT p.id FROM messages p
+                    WHERE p.conversation_id = messages.conversation_id
+                      AND p.role != messages.role
+                      AND p.created_at < messages.created_at
+                      AND (messages.is_active = 0 OR p.is_active = 1)
+                    ORDER BY p.created_at DESC
+                    LIMIT 1
+                 )
+                 WHERE parent_message_id IS NULL;
+
+                 UPDATE schema_version SET version = 9;",
+            )?;
+        }
+
+        if version < 10 {
+            // A conversation is in at most one folder, hence the primary key on conversation_id
+            conn.execute_batch(
+                "CREATE TABLE folders (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                 );
+
+                 CREATE TABLE tags (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE COLLATE NOCASE
+                 );
+
+                 CREATE TABLE conversation_folders (
+                    conversation_id TEXT PRIMARY KEY,
+                    folder_id TEXT NOT NULL,
+                    FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE,
+                    FOREIGN KEY (folder_id) REFERENCES folders(id) ON DELETE CASCADE
+                 );
+                 CREATE INDEX idx_conversation_folders_folder ON conversation_folders(folder_id);
+
+                 CREATE TABLE conversation_tags (
+                    conversation_id TEXT NOT NULL,
+                    tag_id TEXT NOT NULL,
+                    PRIMARY KEY (conversation_id, tag_id),
+                    FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE,
+                    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+                 );
+                 CREATE INDEX idx_conversation_tags_tag ON conversation_tags(tag_id);
+
+                 UPDATE schema_version SET version = 10;",
+            )?;
+        }
+
+        if version < 11 {
+            // Names are unique so imported presets replace the ones they share a name with
+            conn.execute_batch(
+                "CREATE TABLE system_prompts (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                    content TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                 );
+
+                 UPDATE schema_version SET version = 11;",
+            )?;
+        }
+
+        if version < 12 {
+            conn.execute_batch(
+                "CREATE TABLE assistants (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    icon TEXT NOT NULL,
+                    system_prompt TEXT,
+                    account_id TEXT,
+                    model TEXT,
+                    temperature REAL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE SET NULL
+                 );
+
+                 CREATE TABLE assistant_files (
+                    id TEXT PRIMARY KEY,
+                    assistant_id TEXT NOT NULL,
+                    filename TEXT NOT NULL,
+                    mime_type TEXT NOT NULL,
+                    data BLOB NOT NULL,
+                    FOREIGN KEY (assistant_id) REFERENCES assistants(id) ON DELETE CASCADE
+                 );
+                 CREATE INDEX idx_assistant_files_assistant ON assistant_files(assistant_id);
+
+                 ALTER TABLE conversations ADD COLUMN assistant_id TEXT
+                    REFERENCES assistants(id) ON DELETE SET NULL;
+
+                 UPDATE schema_version SET version = 12;",
+            )?;
+        }
+
+        if version < 13 {
+            conn.execute_batch(
+                "CREATE TABLE templates (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                    content TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                 );
+
+                 UPDATE schema_version SET version = 13;",
+            )?;
+        }
+
+        if version < 14 {
+            // Set once the user renames a conversation, so automatic titles leave it alone
+            conn.execute_batch(
+                "ALTER TABLE conversations ADD COLUMN title_locked INTEGER NOT NULL DEFAULT 0;
+
+                 UPDATE schema_version SET version = 14;",
+            )?;
+        }
+
+        if version < 15 {
+            conn.execute_batch(
+                "ALTER TABLE messages ADD COLUMN thinking_tokens BIGINT;
+
+                 UPDATE schema_version SET version = 15;",
+            )?;
+        }
+
         Ok(())
     }
 
@@ -311,8 +505,9 @@ impl Database {
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             conn.execute(
-                "INSERT INTO conversations (id, account_id, title, model, system_prompt, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                "INSERT INTO conversations (id, account_id, title, model, system_prompt, created_at, updated_at,
+                                            temperature, max_tokens, top_p, top_k, stop_sequences, seed, assistant_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
                 params![
                     conv.id,
                     conv.account_id,
@@ -321,6 +516,13 @@ impl Database {
                     conv.system_prompt,
                     conv.created_at.to_rfc3339(),
                     conv.updated_at.to_rfc3339(),
+                    conv.params.temperature,
+                    conv.params.max_tokens,
+                    conv.params.top_p,
+                    conv.params.top_k,
+                    Self::stop_sequences_to_sql(&conv.params.stop_sequences)?,
+                    conv.params.seed,
+                    conv.assistant_id,
                 ],
             )?;
             Ok(())
@@ -334,7 +536,13 @@ impl Database {
             let conn = conn.lock().unwrap();
             let mut stmt = conn.prepare(
                 "SELECT c.id, c.account_id, c.title, c.model, c.system_prompt, c.created_at, c.updated_at, c.pinned,
-                        (SELECT SUBSTR(m.content, 1, 100) FROM messages m WHERE m.conversation_id = c.id AND m.is_active = 1 ORDER BY m.created_at DESC LIMIT 1) as last_preview
+                        (SELECT SUBSTR(m.content, 1, 100) FROM messages m WHERE m.conversation_id = c.id AND m.is_active = 1 ORDER BY m.created_at DESC LIMIT 1) as last_preview,
+                        c.temperature, c.max_tokens, c.top_p, c.top_k, c.stop_sequences, c.seed,
+                        (SELECT cf.folder_id FROM conversation_folders cf WHERE cf.conversation_id = c.id) as folder_id,
+                        (SELECT json_group_array(json_object('id', t.id, 'name', t.name))
+                         FROM conversation_tags ct JOIN tags t ON t.id = ct.tag_id
+                         WHERE ct.conversation_id = c.id) as tags,
+                        c.assistant_id
                  FROM conversations c ORDER BY c.pinned DESC, c.updated_at DESC",
             )?;
             let conversations = stmt
@@ -347,14 +555,34 @@ impl Database {
         .await?
     }
 
-    pub async fn update_conversation_title(&self, id: &str, title: &str) -> Result<()> {
+    /// Set an automatic title. Returns false, leaving the title alone, when the user
+    /// has renamed the conversation.
+    pub async fn update_conversation_title(&self, id: &str, title: &str) -> Result<bool> {
+        let conn = self.conn.clone();
+        let id = id.to_string();
+        let title = title.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let updated = conn.execute(
+                "UPDATE conversations SET title = ?1, updated_at = ?2
+                 WHERE id = ?3 AND title_locked = 0",
+                params![title, Utc::now().to_rfc3339(), id],
+            )?;
+            Ok(updated > 0)
+        })
+        .await?
+    }
+
+    /// Set a title chosen by the user, which automatic titles never replace.
+    pub async fn rename_conversation(&self, id: &str, title: &str) -> Result<()> {
         let conn = self.conn.clone();
         let id = id.to_string();
         let title = title.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             conn.execute(
-                "UPDATE conversations SET title = ?1, updated_at = ?2 WHERE id = ?3",
+                "UPDATE conversations SET title = ?1, title_locked = 1, updated_at = ?2
+                 WHERE id = ?3",
                 params![title, Utc::now().to_rfc3339(), id],
             )?;
             Ok(())
@@ -402,6 +630,21 @@ impl Database {
         .await?
     }
 
+    /// Delete every message in a conversation, keeping the conversation itself.
+    pub async fn clear_messages(&self, conversation_id: &str) -> Result<()> {
+        let conn = self.conn.clone();
+        let conversation_id = conversation_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute(
+                "DELETE FROM messages WHERE conversation_id = ?1",
+                params![conversation_id],
+            )?;
+            Ok(())
+        })
+        .await?
+    }
+
     pub async fn toggle_conversation_pin(&self, id: &str, pinned: bool) -> Result<()> {
         let conn = self.conn.clone();
         let id = id.to_string();
@@ -416,125 +659,181 @@ impl Database {
         .await?
     }
 
-    // --- Message CRUD ---
+    // --- Folder and tag CRUD ---
 
-    pub async fn insert_message(&self, message: &Message) -> Result<()> {
+    pub async fn insert_folder(&self, folder: &Folder) -> Result<()> {
         let conn = self.conn.clone();
-        let msg = message.clone();
+        let folder = folder.clone();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             conn.execute(
-                "INSERT INTO messages (id, conversation_id, role, content, model, tokens_in, tokens_out, parent_message_id, is_active, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-                params![
-                    msg.id,
-                    msg.conversation_id,
-                    msg.role.as_str(),
-                    msg.content,
-                    msg.model,
-                    msg.tokens_in,
-                    msg.tokens_out,
-                    msg.parent_message_id,
-                    msg.is_active as i32,
-                    msg.created_at.to_rfc3339(),
-                ],
+                "INSERT INTO folders (id, name, created_at) VALUES (?1, ?2, ?3)",
+                params![folder.id, folder.name, folder.created_at.to_rfc3339()],
             )?;
             Ok(())
         })
         .await?
     }
 
-    pub async fn list_messages(&self, conversation_id: &str) -> Result<Vec<Message>> {
+    pub async fn list_folders(&self) -> Result<Vec<Folder>> {
         let conn = self.conn.clone();
-        let conversation_id = conversation_id.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             let mut stmt = conn.prepare(
-                "SELECT id, conversation_id, role, content, model, tokens_in, tokens_out, parent_message_id, is_active, created_at
-                 FROM messages WHERE conversation_id = ?1 AND is_active = 1 ORDER BY created_at ASC",
+                "SELECT id, name, created_at FROM folders ORDER BY name COLLATE NOCASE ASC",
             )?;
-            let messages = stmt
-                .query_map(params![conversation_id], |row| {
-                    Ok(Self::row_to_message(row))
-                })?
+            let folders = stmt
+                .query_map([], |row| Ok(Self::row_to_folder(row)))?
                 .collect::<Result<Vec<_>, _>>()?
                 .into_iter()
                 .collect::<Result<Vec<_>, _>>()?;
-            Ok(messages)
+            Ok(folders)
         })
         .await?
     }
 
-    // --- New Phase 3 methods ---
-
-    pub async fn update_conversation_system_prompt(
-        &self,
-        id: &str,
-        system_prompt: Option<&str>,
-    ) -> Result<()> {
+    pub async fn rename_folder(&self, id: &str, name: &str) -> Result<()> {
         let conn = self.conn.clone();
         let id = id.to_string();
-        let system_prompt = system_prompt.map(|s| s.to_string());
+        let name = name.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             conn.execute(
-                "UPDATE conversations SET system_prompt = ?1, updated_at = ?2 WHERE id = ?3",
-                params![system_prompt, Utc::now().to_rfc3339(), id],
+                "UPDATE folders SET name = ?1 WHERE id = ?2",
+                params![name, id],
             )?;
             Ok(())
         })
         .await?
     }
 
-    pub async fn update_message_content(&self, id: &str, content: &str) -> Result<()> {
+    /// Delete a folder. Its conversations are kept, outside any folder.
+    pub async fn delete_folder(&self, id: &str) -> Result<()> {
         let conn = self.conn.clone();
         let id = id.to_string();
-        let content = content.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
-            conn.execute(
-                "UPDATE messages SET content = ?1 WHERE id = ?2",
-                params![content, id],
-            )?;
+            conn.execute("DELETE FROM folders WHERE id = ?1", params![id])?;
             Ok(())
         })
         .await?
     }
 
-    pub async fn deactivate_messages_after(
+    /// Move a conversation into a folder, or out of its folder with `None`.
+    pub async fn set_conversation_folder(
         &self,
         conversation_id: &str,
-        after_created_at: &str,
+        folder_id: Option<&str>,
     ) -> Result<()> {
         let conn = self.conn.clone();
         let conversation_id = conversation_id.to_string();
-        let after_created_at = after_created_at.to_string();
+        let folder_id = folder_id.map(str::to_string);
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            match folder_id {
+                Some(folder_id) => conn.execute(
+                    "INSERT INTO conversation_folders (conversation_id, folder_id) VALUES (?1, ?2)
+                     ON CONFLICT(conversation_id) DO UPDATE SET folder_id = excluded.folder_id",
+                    params![conversation_id, folder_id],
+                )?,
+                None => conn.execute(
+                    "DELETE FROM conversation_folders WHERE conversation_id = ?1",
+                    params![conversation_id],
+                )?,
+            };
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn insert_tag(&self, tag: &Tag) -> Result<()> {
+        let conn = self.conn.clone();
+        let tag = tag.clone();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             conn.execute(
-                "UPDATE messages SET is_active = 0 WHERE conversation_id = ?1 AND created_at > ?2",
-                params![conversation_id, after_created_at],
+                "INSERT INTO tags (id, name) VALUES (?1, ?2)",
+                params![tag.id, tag.name],
             )?;
             Ok(())
         })
         .await?
     }
 
-    pub async fn insert_attachment(&self, attachment: &Attachment) -> Result<()> {
+    pub async fn list_tags(&self) -> Result<Vec<Tag>> {
         let conn = self.conn.clone();
-        let att = attachment.clone();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt =
+                conn.prepare("SELECT id, name FROM tags ORDER BY name COLLATE NOCASE ASC")?;
+            let tags = stmt
+                .query_map([], |row| {
+                    Ok(Tag {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(tags)
+        })
+        .await?
+    }
+
+    pub async fn delete_tag(&self, id: &str) -> Result<()> {
+        let conn = self.conn.clone();
+        let id = id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute("DELETE FROM tags WHERE id = ?1", params![id])?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Add or remove a tag on a conversation.
+    pub async fn set_conversation_tag(
+        &self,
+        conversation_id: &str,
+        tag_id: &str,
+        tagged: bool,
+    ) -> Result<()> {
+        let conn = self.conn.clone();
+        let conversation_id = conversation_id.to_string();
+        let tag_id = tag_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            if tagged {
+                conn.execute(
+                    "INSERT OR IGNORE INTO conversation_tags (conversation_id, tag_id) VALUES (?1, ?2)",
+                    params![conversation_id, tag_id],
+                )?;
+            } else {
+                conn.execute(
+                    "DELETE FROM conversation_tags WHERE conversation_id = ?1 AND tag_id = ?2",
+                    params![conversation_id, tag_id],
+                )?;
+            }
+            Ok(())
+        })
+        .await?
+    }
+
+    // --- System prompt library ---
+
+    pub async fn insert_prompt_preset(&self, preset: &PromptPreset) -> Result<()> {
+        let conn = self.conn.clone();
+        let preset = preset.clone();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             conn.execute(
-                "INSERT INTO message_attachments (id, message_id, mime_type, filename, data, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                "INSERT INTO system_prompts (id, name, content, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
                 params![
-                    att.id,
-                    att.message_id,
-                    att.mime_type,
-                    att.filename,
-                    att.data,
-                    att.created_at.to_rfc3339(),
+                    preset.id,
+                    preset.name,
+                    preset.content,
+                    preset.created_at.to_rfc3339(),
+                    preset.updated_at.to_rfc3339(),
                 ],
             )?;
             Ok(())
@@ -542,94 +841,544 @@ impl Database {
         .await?
     }
 
-    pub async fn list_attachments(&self, message_id: &str) -> Result<Vec<Attachment>> {
+    pub async fn list_prompt_presets(&self) -> Result<Vec<PromptPreset>> {
         let conn = self.conn.clone();
-        let message_id = message_id.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
             let mut stmt = conn.prepare(
-                "SELECT id, message_id, mime_type, filename, data, created_at
-                 FROM message_attachments WHERE message_id = ?1",
+                "SELECT id, name, content, created_at, updated_at FROM system_prompts
+                 ORDER BY name COLLATE NOCASE ASC",
             )?;
-            let attachments = stmt
-                .query_map(params![message_id], |row| Ok(Self::row_to_attachment(row)))?
+            let presets = stmt
+                .query_map([], |row| Ok(Self::row_to_prompt_preset(row)))?
                 .collect::<Result<Vec<_>, _>>()?
                 .into_iter()
                 .collect::<Result<Vec<_>, _>>()?;
-            Ok(attachments)
+            Ok(presets)
         })
         .await?
     }
 
-    pub async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>> {
+    pub async fn update_prompt_preset(&self, id: &str, name: &str, content: &str) -> Result<()> {
         let conn = self.conn.clone();
         let id = id.to_string();
+        let name = name.to_string();
+        let content = content.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
-            let mut stmt = conn.prepare(
-                "SELECT c.id, c.account_id, c.title, c.model, c.system_prompt, c.created_at, c.updated_at, c.pinned,
-                        (SELECT SUBSTR(m.content, 1, 100) FROM messages m WHERE m.conversation_id = c.id AND m.is_active = 1 ORDER BY m.created_at DESC LIMIT 1) as last_preview
-                 FROM conversations c WHERE c.id = ?1",
+            let now = Utc::now().to_rfc3339();
+            conn.execute(
+                "UPDATE system_prompts SET name = ?1, content = ?2, updated_at = ?3 WHERE id = ?4",
+                params![name, content, now, id],
             )?;
-            let result = stmt
-                .query_row(params![id], |row| Ok(Self::row_to_conversation(row)))
-                .optional()?;
-            match result {
-                Some(Ok(conv)) => Ok(Some(conv)),
-                Some(Err(e)) => Err(e),
-                None => Ok(None),
-            }
+            Ok(())
         })
         .await?
     }
 
-    // --- Settings ---
-
-    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+    pub async fn delete_prompt_preset(&self, id: &str) -> Result<()> {
         let conn = self.conn.clone();
-        let key = key.to_string();
+        let id = id.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
-            let result: Option<String> = conn
-                .query_row(
-                    "SELECT value FROM settings WHERE key = ?1",
-                    params![key],
-                    |row| row.get(0),
-                )
-                .optional()?;
-            Ok(result)
+            conn.execute("DELETE FROM system_prompts WHERE id = ?1", params![id])?;
+            Ok(())
         })
         .await?
     }
 
-    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+    /// Add `(name, content)` presets to the library, replacing the content of any preset
+    /// with the same name. Returns how many were imported.
+    pub async fn import_prompt_presets(&self, presets: Vec<(String, String)>) -> Result<usize> {
         let conn = self.conn.clone();
-        let key = key.to_string();
-        let value = value.to_string();
+        task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            let now = Utc::now().to_rfc3339();
+            for (name, content) in &presets {
+                tx.execute(
+                    "INSERT INTO system_prompts (id, name, content, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?4)
+                     ON CONFLICT(name) DO UPDATE SET content = excluded.content,
+                        updated_at = excluded.updated_at",
+                    params![uuid::Uuid::new_v4().to_string(), name, content, now],
+                )?;
+            }
+            tx.commit()?;
+            Ok(presets.len())
+        })
+        .await?
+    }
+
+    // --- Message templates ---
+
+    /// Save a template, replacing the content of any template with the same name.
+    pub async fn save_template(&self, name: &str, content: &str) -> Result<()> {
+        let conn = self.conn.clone();
+        let name = name.to_string();
+        let content = content.to_string();
         task::spawn_blocking(move || {
             let conn = conn.lock().unwrap();
+            let now = Utc::now().to_rfc3339();
             conn.execute(
-                "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = ?2",
-                params![key, value],
+                "INSERT INTO templates (id, name, content, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(name) DO UPDATE SET content = excluded.content,
+                    updated_at = excluded.updated_at",
+                params![uuid::Uuid::new_v4().to_string(), name, content, now],
             )?;
             Ok(())
         })
         .await?
     }
 
-    // --- Row helpers ---
+    pub async fn list_templates(&self) -> Result<Vec<PromptTemplate>> {
+        let conn = self.conn.clone();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT id, name, content, created_at, updated_at FROM templates
+                 ORDER BY name COLLATE NOCASE ASC",
+            )?;
+            let templates = stmt
+                .query_map([], |row| Ok(Self::row_to_template(row)))?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(templates)
+        })
+        .await?
+    }
 
-    fn row_to_account(row: &rusqlite::Row) -> Result<Account> {
-        let provider_str: String = row.get(1)?;
-        let status_str: String = row.get(6)?;
-        let created_str: String = row.get(9)?;
-        let updated_str: String = row.get(10)?;
-        let is_default_int: i32 = row.get(5)?;
+    pub async fn delete_template(&self, id: &str) -> Result<()> {
+        let conn = self.conn.clone();
+        let id = id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute("DELETE FROM templates WHERE id = ?1", params![id])?;
+            Ok(())
+        })
+        .await?
+    }
 
-        Ok(Account {
-            id: row.get(0)?,
-            provider: ProviderId::from_str(&provider_str)
-                .ok_or_else(|| anyhow::anyhow!("Unknown provider: {}", provider_str))?,
+    // --- Assistants ---
+
+    /// Insert or update an assistant, replacing its reference files.
+    pub async fn save_assistant(&self, assistant: &Assistant) -> Result<()> {
+        let conn = self.conn.clone();
+        let assistant = assistant.clone();
+        task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT INTO assistants (id, name, icon, system_prompt, account_id, model, temperature, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT(id) DO UPDATE SET name = excluded.name, icon = excluded.icon,
+                    system_prompt = excluded.system_prompt, account_id = excluded.account_id,
+                    model = excluded.model, temperature = excluded.temperature,
+                    updated_at = excluded.updated_at",
+                params![
+                    assistant.id,
+                    assistant.name,
+                    assistant.icon,
+                    assistant.system_prompt,
+                    assistant.account_id,
+                    assistant.model,
+                    assistant.temperature,
+                    assistant.created_at.to_rfc3339(),
+                    assistant.updated_at.to_rfc3339(),
+                ],
+            )?;
+            tx.execute(
+                "DELETE FROM assistant_files WHERE assistant_id = ?1",
+                params![assistant.id],
+            )?;
+            for file in &assistant.files {
+                tx.execute(
+                    "INSERT INTO assistant_files (id, assistant_id, filename, mime_type, data)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        file.id,
+                        assistant.id,
+                        file.filename,
+                        file.mime_type,
+                        file.data
+                    ],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn list_assistants(&self) -> Result<Vec<Assistant>> {
+        let conn = self.conn.clone();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT id, name, icon, system_prompt, account_id, model, temperature, created_at, updated_at
+                 FROM assistants ORDER BY name COLLATE NOCASE ASC",
+            )?;
+            let mut assistants = stmt
+                .query_map([], |row| Ok(Self::row_to_assistant(row)))?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            for assistant in &mut assistants {
+                assistant.files = Self::query_assistant_files(&conn, &assistant.id)?;
+            }
+            Ok(assistants)
+        })
+        .await?
+    }
+
+    pub async fn list_assistant_files(&self, assistant_id: &str) -> Result<Vec<AssistantFile>> {
+        let conn = self.conn.clone();
+        let assistant_id = assistant_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            Self::query_assistant_files(&conn, &assistant_id)
+        })
+        .await?
+    }
+
+    /// Delete an assistant. Chats started from it are kept, without its reference files.
+    pub async fn delete_assistant(&self, id: &str) -> Result<()> {
+        let conn = self.conn.clone();
+        let id = id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute("DELETE FROM assistants WHERE id = ?1", params![id])?;
+            Ok(())
+        })
+        .await?
+    }
+
+    fn query_assistant_files(conn: &Connection, assistant_id: &str) -> Result<Vec<AssistantFile>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, filename, mime_type, data FROM assistant_files
+             WHERE assistant_id = ?1 ORDER BY rowid ASC",
+        )?;
+        let files = stmt
+            .query_map(params![assistant_id], |row| {
+                Ok(AssistantFile {
+                    id: row.get(0)?,
+                    filename: row.get(1)?,
+                    mime_type: row.get(2)?,
+                    data: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    // --- Message CRUD ---
+
+    pub async fn insert_message(&self, message: &Message) -> Result<()> {
+        let conn = self.conn.clone();
+        let msg = message.clone();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO messages (id, conversation_id, role, content, model, tokens_in, tokens_out, parent_message_id, is_active, created_at, thinking, thinking_tokens)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    msg.id,
+                    msg.conversation_id,
+                    msg.role.as_str(),
+                    msg.content,
+                    msg.model,
+                    msg.tokens_in,
+                    msg.tokens_out,
+                    msg.parent_message_id,
+                    msg.is_active as i32,
+                    msg.created_at.to_rfc3339(),
+                    msg.thinking,
+                    msg.thinking_tokens,
+                ],
+            )?;
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn list_messages(&self, conversation_id: &str) -> Result<Vec<Message>> {
+        let conn = self.conn.clone();
+        let conversation_id = conversation_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT id, conversation_id, role, content, model, tokens_in, tokens_out, parent_message_id, is_active, created_at, thinking, thinking_tokens
+                 FROM messages WHERE conversation_id = ?1 AND is_active = 1 ORDER BY created_at ASC",
+            )?;
+            let messages = stmt
+                .query_map(params![conversation_id], |row| {
+                    Ok(Self::row_to_message(row))
+                })?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(messages)
+        })
+        .await?
+    }
+
+    /// Search the active messages of all conversations, best matches first.
+    pub async fn search_messages(&self, query: &str) -> Result<Vec<MessageSearchHit>> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self.conn.clone();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT m.id, m.conversation_id, c.title, m.role,
+                        snippet(messages_fts, 0, ?2, ?3, '…', 16), m.created_at
+                 FROM messages_fts
+                 JOIN messages m ON m.rowid = messages_fts.rowid
+                 JOIN conversations c ON c.id = m.conversation_id
+                 WHERE messages_fts MATCH ?1 AND m.is_active = 1
+                 ORDER BY bm25(messages_fts)
+                 LIMIT ?4",
+            )?;
+            let hits = stmt
+                .query_map(
+                    params![
+                        fts_query,
+                        SNIPPET_MATCH_START.to_string(),
+                        SNIPPET_MATCH_END.to_string(),
+                        SEARCH_RESULT_LIMIT as i64,
+                    ],
+                    |row| Ok(Self::row_to_search_hit(row)),
+                )?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(hits)
+        })
+        .await?
+    }
+
+    // --- New Phase 3 methods ---
+
+    pub async fn update_conversation_system_prompt(
+        &self,
+        id: &str,
+        system_prompt: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn.clone();
+        let id = id.to_string();
+        let system_prompt = system_prompt.map(|s| s.to_string());
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute(
+                "UPDATE conversations SET system_prompt = ?1, updated_at = ?2 WHERE id = ?3",
+                params![system_prompt, Utc::now().to_rfc3339(), id],
+            )?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Leaves `updated_at` alone: changing parameters isn't activity that should move
+    /// the conversation up the sidebar.
+    pub async fn update_conversation_params(
+        &self,
+        id: &str,
+        generation_params: &GenerationParams,
+    ) -> Result<()> {
+        let conn = self.conn.clone();
+        let id = id.to_string();
+        let gp = generation_params.clone();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute(
+                "UPDATE conversations SET temperature = ?1, max_tokens = ?2, top_p = ?3, top_k = ?4,
+                                          stop_sequences = ?5, seed = ?6
+                 WHERE id = ?7",
+                params![
+                    gp.temperature,
+                    gp.max_tokens,
+                    gp.top_p,
+                    gp.top_k,
+                    Self::stop_sequences_to_sql(&gp.stop_sequences)?,
+                    gp.seed,
+                    id
+                ],
+            )?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Every message of a conversation as `(id, parent_message_id)`, oldest first,
+    /// including inactive branches.
+    pub async fn list_message_parents(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        let conn = self.conn.clone();
+        let conversation_id = conversation_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT id, parent_message_id FROM messages
+                 WHERE conversation_id = ?1 ORDER BY created_at ASC, rowid ASC",
+            )?;
+            let parents = stmt
+                .query_map(params![conversation_id], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(parents)
+        })
+        .await?
+    }
+
+    /// Hide `deactivate` and show `activate` in one transaction, moving the active path.
+    pub async fn update_active_messages(
+        &self,
+        deactivate: &[String],
+        activate: &[String],
+    ) -> Result<()> {
+        let conn = self.conn.clone();
+        let deactivate = deactivate.to_vec();
+        let activate = activate.to_vec();
+        task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare("UPDATE messages SET is_active = ?1 WHERE id = ?2")?;
+                for id in &deactivate {
+                    stmt.execute(params![0, id])?;
+                }
+                for id in &activate {
+                    stmt.execute(params![1, id])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn insert_attachment(&self, attachment: &Attachment) -> Result<()> {
+        let conn = self.conn.clone();
+        let att = attachment.clone();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO message_attachments (id, message_id, mime_type, filename, data, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    att.id,
+                    att.message_id,
+                    att.mime_type,
+                    att.filename,
+                    att.data,
+                    att.created_at.to_rfc3339(),
+                ],
+            )?;
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn list_attachments(&self, message_id: &str) -> Result<Vec<Attachment>> {
+        let conn = self.conn.clone();
+        let message_id = message_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT id, message_id, mime_type, filename, data, created_at
+                 FROM message_attachments WHERE message_id = ?1",
+            )?;
+            let attachments = stmt
+                .query_map(params![message_id], |row| Ok(Self::row_to_attachment(row)))?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(attachments)
+        })
+        .await?
+    }
+
+    pub async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>> {
+        let conn = self.conn.clone();
+        let id = id.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT c.id, c.account_id, c.title, c.model, c.system_prompt, c.created_at, c.updated_at, c.pinned,
+                        (SELECT SUBSTR(m.content, 1, 100) FROM messages m WHERE m.conversation_id = c.id AND m.is_active = 1 ORDER BY m.created_at DESC LIMIT 1) as last_preview,
+                        c.temperature, c.max_tokens, c.top_p, c.top_k, c.stop_sequences, c.seed,
+                        (SELECT cf.folder_id FROM conversation_folders cf WHERE cf.conversation_id = c.id) as folder_id,
+                        (SELECT json_group_array(json_object('id', t.id, 'name', t.name))
+                         FROM conversation_tags ct JOIN tags t ON t.id = ct.tag_id
+                         WHERE ct.conversation_id = c.id) as tags,
+                        c.assistant_id
+                 FROM conversations c WHERE c.id = ?1",
+            )?;
+            let result = stmt
+                .query_row(params![id], |row| Ok(Self::row_to_conversation(row)))
+                .optional()?;
+            match result {
+                Some(Ok(conv)) => Ok(Some(conv)),
+                Some(Err(e)) => Err(e),
+                None => Ok(None),
+            }
+        })
+        .await?
+    }
+
+    // --- Settings ---
+
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.clone();
+        let key = key.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            let result: Option<String> = conn
+                .query_row(
+                    "SELECT value FROM settings WHERE key = ?1",
+                    params![key],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            Ok(result)
+        })
+        .await?
+    }
+
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.clone();
+        let key = key.to_string();
+        let value = value.to_string();
+        task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = ?2",
+                params![key, value],
+            )?;
+            Ok(())
+        })
+        .await?
+    }
+
+    // --- Row helpers ---
+
+    fn row_to_account(row: &rusqlite::Row) -> Result<Account> {
+        let provider_str: String = row.get(1)?;
+        let status_str: String = row.get(6)?;
+        let created_str: String = row.get(9)?;
+        let updated_str: String = row.get(10)?;
+        let is_default_int: i32 = row.get(5)?;
+
+        Ok(Account {
+            id: row.get(0)?,
+            provider: ProviderId::from_str(&provider_str)
+                .ok_or_else(|| anyhow::anyhow!("Unknown provider: {}", provider_str))?,
             label: row.get(2)?,
             api_base_url: row.get(3)?,
             default_model: row.get(4)?,
@@ -648,6 +1397,8 @@ impl Database {
         let updated_str: String = row.get(6)?;
         let pinned_int: i32 = row.get(7)?;
         let last_message_preview: Option<String> = row.get(8)?;
+        let stop_sequences: Option<String> = row.get(13)?;
+        let tags: String = row.get(16)?;
 
         Ok(Conversation {
             id: row.get(0)?,
@@ -655,13 +1406,105 @@ impl Database {
             title: row.get(2)?,
             model: row.get(3)?,
             system_prompt: row.get(4)?,
+            params: GenerationParams {
+                temperature: row.get(9)?,
+                max_tokens: row.get(10)?,
+                top_p: row.get(11)?,
+                top_k: row.get(12)?,
+                stop_sequences: match stop_sequences {
+                    Some(json) => serde_json::from_str(&json)?,
+                    None => Vec::new(),
+                },
+                seed: row.get(14)?,
+            },
             pinned: pinned_int != 0,
+            folder_id: row.get(15)?,
+            tags: serde_json::from_str(&tags)?,
+            assistant_id: row.get(17)?,
             last_message_preview,
             created_at: DateTime::parse_from_rfc3339(&created_str)?.with_timezone(&Utc),
             updated_at: DateTime::parse_from_rfc3339(&updated_str)?.with_timezone(&Utc),
         })
     }
 
+    fn row_to_template(row: &rusqlite::Row) -> Result<PromptTemplate> {
+        let created_str: String = row.get(3)?;
+        let updated_str: String = row.get(4)?;
+
+        Ok(PromptTemplate {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            content: row.get(2)?,
+            created_at: DateTime::parse_from_rfc3339(&created_str)?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_str)?.with_timezone(&Utc),
+        })
+    }
+
+    /// An assistant without its files; `list_assistants` fills them in.
+    fn row_to_assistant(row: &rusqlite::Row) -> Result<Assistant> {
+        let created_str: String = row.get(7)?;
+        let updated_str: String = row.get(8)?;
+
+        Ok(Assistant {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            icon: row.get(2)?,
+            system_prompt: row.get(3)?,
+            account_id: row.get(4)?,
+            model: row.get(5)?,
+            temperature: row.get(6)?,
+            files: Vec::new(),
+            created_at: DateTime::parse_from_rfc3339(&created_str)?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_str)?.with_timezone(&Utc),
+        })
+    }
+
+    fn row_to_folder(row: &rusqlite::Row) -> Result<Folder> {
+        let created_str: String = row.get(2)?;
+
+        Ok(Folder {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            created_at: DateTime::parse_from_rfc3339(&created_str)?.with_timezone(&Utc),
+        })
+    }
+
+    fn row_to_prompt_preset(row: &rusqlite::Row) -> Result<PromptPreset> {
+        let created_str: String = row.get(3)?;
+        let updated_str: String = row.get(4)?;
+
+        Ok(PromptPreset {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            content: row.get(2)?,
+            created_at: DateTime::parse_from_rfc3339(&created_str)?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_str)?.with_timezone(&Utc),
+        })
+    }
+
+    fn row_to_search_hit(row: &rusqlite::Row) -> Result<MessageSearchHit> {
+        let role_str: String = row.get(3)?;
+        let created_str: String = row.get(5)?;
+
+        Ok(MessageSearchHit {
+            message_id: row.get(0)?,
+            conversation_id: row.get(1)?,
+            conversation_title: row.get(2)?,
+            role: Role::from_str(&role_str)
+                .ok_or_else(|| anyhow::anyhow!("Unknown role: {}", role_str))?,
+            snippet: row.get(4)?,
+            created_at: DateTime::parse_from_rfc3339(&created_str)?.with_timezone(&Utc),
+        })
+    }
+
+    /// Stop sequences are stored as a JSON array, or NULL when there are none.
+    fn stop_sequences_to_sql(stop_sequences: &[String]) -> Result<Option<String>> {
+        if stop_sequences.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::to_string(stop_sequences)?))
+    }
+
     fn row_to_message(row: &rusqlite::Row) -> Result<Message> {
         let role_str: String = row.get(2)?;
         let is_active_int: i32 = row.get(8)?;
@@ -673,13 +1516,16 @@ impl Database {
             role: Role::from_str(&role_str)
                 .ok_or_else(|| anyhow::anyhow!("Unknown role: {}", role_str))?,
             content: row.get(3)?,
+            thinking: row.get(10)?,
             model: row.get(4)?,
             tokens_in: row.get(5)?,
             tokens_out: row.get(6)?,
+            thinking_tokens: row.get(11)?,
             parent_message_id: row.get(7)?,
             is_active: is_active_int != 0,
             created_at: DateTime::parse_from_rfc3339(&created_str)?.with_timezone(&Utc),
             attachments: Vec::new(),
+            branch: None,
         })
     }
 
@@ -699,10 +1545,62 @@ impl Database {
 
 use rusqlite::OptionalExtension;
 
+/// Turn free text into an FTS5 query matching every word as a prefix,
+/// or `None` if there is nothing to search for.
+///
+/// Each word is quoted so characters like `"`, `*` or `-` and words like `OR`
+/// are searched for literally instead of being parsed as query syntax.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Insert an account and an empty conversation on it.
+    async fn setup_conversation(db: &Database) -> (Account, Conversation) {
+        let now = Utc::now();
+
+        let account = Account {
+            id: uuid::Uuid::new_v4().to_string(),
+            provider: ProviderId::Claude,
+            label: "Test".to_string(),
+            api_base_url: None,
+            default_model: "claude-sonnet-4-5-20250929".to_string(),
+            is_default: true,
+            status: AccountStatus::Active,
+            total_tokens_in: 0,
+            total_tokens_out: 0,
+            created_at: now,
+            updated_at: now,
+        };
+        db.insert_account(&account).await.unwrap();
+
+        let conv = Conversation {
+            id: uuid::Uuid::new_v4().to_string(),
+            account_id: account.id.clone(),
+            title: "Test Chat".to_string(),
+            model: account.default_model.clone(),
+            system_prompt: None,
+            params: GenerationParams::default(),
+            pinned: false,
+            folder_id: None,
+            tags: Vec::new(),
+            assistant_id: None,
+            last_message_preview: None,
+            created_at: now,
+            updated_at: now,
+        };
+        db.insert_conversation(&conv).await.unwrap();
+
+        (account, conv)
+    }
+
     #[tokio::test]
     async fn test_schema_initialization() {
         let db = Database::new_in_memory().unwrap();
@@ -770,7 +1668,11 @@ mod tests {
             title: "Test Chat".to_string(),
             model: "gemini-2.5-flash".to_string(),
             system_prompt: None,
+            params: GenerationParams::default(),
             pinned: false,
+            folder_id: None,
+            tags: Vec::new(),
+            assistant_id: None,
             last_message_preview: None,
             created_at: now,
             updated_at: now,
@@ -782,13 +1684,16 @@ mod tests {
             conversation_id: conv.id.clone(),
             role: Role::User,
             content: "Hello!".to_string(),
+            thinking: None,
             model: None,
             tokens_in: None,
             tokens_out: None,
+            thinking_tokens: None,
             parent_message_id: None,
             is_active: true,
             created_at: now,
             attachments: Vec::new(),
+            branch: None,
         };
         db.insert_message(&msg).await.unwrap();
 
@@ -807,4 +1712,396 @@ mod tests {
         let messages = db.list_messages(&conv.id).await.unwrap();
         assert!(messages.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_clear_messages() {
+        let db = Database::new_in_memory().unwrap();
+        let (_, conv) = setup_conversation(&db).await;
+
+        let msg = Message {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: conv.id.clone(),
+            role: Role::User,
+            content: "Forget this".to_string(),
+            thinking: None,
+            model: None,
+            tokens_in: None,
+            tokens_out: None,
+            thinking_tokens: None,
+            parent_message_id: None,
+            is_active: true,
+            created_at: Utc::now(),
+            attachments: Vec::new(),
+            branch: None,
+        };
+        db.insert_message(&msg).await.unwrap();
+        assert_eq!(db.search_messages("forget").await.unwrap().len(), 1);
+
+        db.clear_messages(&conv.id).await.unwrap();
+
+        assert!(db.list_messages(&conv.id).await.unwrap().is_empty());
+        assert!(db.search_messages("forget").await.unwrap().is_empty());
+        // The conversation itself stays
+        assert!(db.get_conversation(&conv.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_title_lock() {
+        let db = Database::new_in_memory().unwrap();
+        let (_, conv) = setup_conversation(&db).await;
+
+        assert!(db
+            .update_conversation_title(&conv.id, "Auto")
+            .await
+            .unwrap());
+
+        // Automatic titles stop once the user renames the conversation
+        db.rename_conversation(&conv.id, "Mine").await.unwrap();
+        assert!(!db
+            .update_conversation_title(&conv.id, "Auto again")
+            .await
+            .unwrap());
+        let renamed = db.get_conversation(&conv.id).await.unwrap().unwrap();
+        assert_eq!(renamed.title, "Mine");
+    }
+
+    #[tokio::test]
+    async fn test_message_thinking_persisted() {
+        let db = Database::new_in_memory().unwrap();
+        let now = Utc::now();
+        let (account, conv) = setup_conversation(&db).await;
+
+        let msg = Message {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: conv.id.clone(),
+            role: Role::Assistant,
+            content: "42".to_string(),
+            thinking: Some("Six times seven.".to_string()),
+            model: Some(account.default_model.clone()),
+            tokens_in: None,
+            tokens_out: None,
+            thinking_tokens: None,
+            parent_message_id: None,
+            is_active: true,
+            created_at: now,
+            attachments: Vec::new(),
+            branch: None,
+        };
+        db.insert_message(&msg).await.unwrap();
+
+        let messages = db.list_messages(&conv.id).await.unwrap();
+        assert_eq!(messages[0].thinking.as_deref(), Some("Six times seven."));
+    }
+
+    #[tokio::test]
+    async fn test_message_branches() {
+        let db = Database::new_in_memory().unwrap();
+        let now = Utc::now();
+        let (_, conv) = setup_conversation(&db).await;
+
+        // A question, then an answer and its regeneration
+        let mut ids: Vec<String> = Vec::new();
+        let tree = [
+            (Role::User, None),
+            (Role::Assistant, Some(0)),
+            (Role::Assistant, Some(0)),
+        ];
+        for (i, (role, parent)) in tree.into_iter().enumerate() {
+            let msg = Message {
+                id: uuid::Uuid::new_v4().to_string(),
+                conversation_id: conv.id.clone(),
+                role,
+                content: format!("Message {}", i),
+                thinking: None,
+                model: None,
+                tokens_in: None,
+                tokens_out: None,
+                thinking_tokens: None,
+                parent_message_id: parent.map(|p| ids[p].clone()),
+                is_active: i != 1,
+                created_at: now + chrono::Duration::seconds(i as i64),
+                attachments: Vec::new(),
+                branch: None,
+            };
+            db.insert_message(&msg).await.unwrap();
+            ids.push(msg.id);
+        }
+
+        let parents = db.list_message_parents(&conv.id).await.unwrap();
+        assert_eq!(parents.len(), 3);
+        assert_eq!(parents[2], (ids[2].clone(), Some(ids[0].clone())));
+
+        let active = db.list_messages(&conv.id).await.unwrap();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[1].id, ids[2]);
+
+        // Switch back to the first answer
+        db.update_active_messages(&[ids[2].clone()], &[ids[1].clone()])
+            .await
+            .unwrap();
+        let active = db.list_messages(&conv.id).await.unwrap();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[1].id, ids[1]);
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("  "), None);
+        assert_eq!(
+            fts_query("rust gtk"),
+            Some("\"rust\"* \"gtk\"*".to_string())
+        );
+        assert_eq!(
+            fts_query("say \"hi"),
+            Some("\"say\"* \"\"\"hi\"*".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_messages() {
+        let db = Database::new_in_memory().unwrap();
+        let now = Utc::now();
+        let (_, conv) = setup_conversation(&db).await;
+
+        for content in [
+            "How does the borrow checker work?",
+            "It tracks lifetimes of references.",
+        ] {
+            let msg = Message {
+                id: uuid::Uuid::new_v4().to_string(),
+                conversation_id: conv.id.clone(),
+                role: Role::User,
+                content: content.to_string(),
+                thinking: None,
+                model: None,
+                tokens_in: None,
+                tokens_out: None,
+                thinking_tokens: None,
+                parent_message_id: None,
+                is_active: true,
+                created_at: now,
+                attachments: Vec::new(),
+                branch: None,
+            };
+            db.insert_message(&msg).await.unwrap();
+        }
+
+        let hits = db.search_messages("borrow").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_title, conv.title);
+        assert!(hits[0].snippet.contains("\u{2}borrow\u{3}"));
+
+        // Prefix match, and edits are re-indexed
+        let hits = db.search_messages("lifetime").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE messages SET content = 'It checks scopes.' WHERE id = ?1",
+                params![hits[0].message_id],
+            )
+            .unwrap();
+        assert!(db.search_messages("lifetime").await.unwrap().is_empty());
+        assert_eq!(db.search_messages("scopes").await.unwrap().len(), 1);
+
+        // Deleting the conversation removes its messages from the index
+        db.delete_conversation(&conv.id).await.unwrap();
+        assert!(db.search_messages("borrow").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_conversation_params_persisted() {
+        let db = Database::new_in_memory().unwrap();
+        let (_, conv) = setup_conversation(&db).await;
+
+        let loaded = db.get_conversation(&conv.id).await.unwrap().unwrap();
+        assert_eq!(loaded.params, GenerationParams::default());
+
+        let params = GenerationParams {
+            temperature: Some(0.5),
+            max_tokens: Some(1024),
+            top_p: Some(0.9),
+            top_k: Some(40),
+            stop_sequences: vec!["END".to_string(), "\n\n".to_string()],
+            seed: Some(7),
+        };
+        db.update_conversation_params(&conv.id, &params)
+            .await
+            .unwrap();
+
+        let loaded = db.get_conversation(&conv.id).await.unwrap().unwrap();
+        assert_eq!(loaded.params, params);
+        // Not a new message, so the conversation keeps its place in the sidebar
+        assert_eq!(loaded.updated_at, conv.updated_at);
+    }
+
+    #[tokio::test]
+    async fn test_folders_and_tags() {
+        let db = Database::new_in_memory().unwrap();
+        let now = Utc::now();
+        let (_, conv) = setup_conversation(&db).await;
+
+        let folder = Folder {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "Work".to_string(),
+            created_at: now,
+        };
+        db.insert_folder(&folder).await.unwrap();
+        let tag = Tag {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "rust".to_string(),
+        };
+        db.insert_tag(&tag).await.unwrap();
+
+        // Tag names are unique regardless of case
+        let duplicate = Tag {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "Rust".to_string(),
+        };
+        assert!(db.insert_tag(&duplicate).await.is_err());
+
+        db.set_conversation_folder(&conv.id, Some(&folder.id))
+            .await
+            .unwrap();
+        db.set_conversation_tag(&conv.id, &tag.id, true)
+            .await
+            .unwrap();
+
+        let loaded = db.get_conversation(&conv.id).await.unwrap().unwrap();
+        assert_eq!(loaded.folder_id.as_deref(), Some(folder.id.as_str()));
+        assert_eq!(loaded.tags, vec![tag.clone()]);
+
+        // Deleting the folder and tag keeps the conversation
+        db.delete_folder(&folder.id).await.unwrap();
+        db.delete_tag(&tag.id).await.unwrap();
+        let conversations = db.list_conversations().await.unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].folder_id, None);
+        assert!(conversations[0].tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_presets() {
+        let db = Database::new_in_memory().unwrap();
+        let now = Utc::now();
+
+        let preset = PromptPreset {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "Reviewer".to_string(),
+            content: "Review the code.".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        db.insert_prompt_preset(&preset).await.unwrap();
+
+        db.update_prompt_preset(&preset.id, "Code Reviewer", "Review the code carefully.")
+            .await
+            .unwrap();
+
+        // Imports replace presets with the same name, ignoring case, and add the rest
+        let imported = db
+            .import_prompt_presets(vec![
+                ("code reviewer".to_string(), "Be strict.".to_string()),
+                ("Translator".to_string(), "Translate to French.".to_string()),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(imported, 2);
+
+        let presets = db.list_prompt_presets().await.unwrap();
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets[0].id, preset.id);
+        assert_eq!(presets[0].name, "Code Reviewer");
+        assert_eq!(presets[0].content, "Be strict.");
+        assert_eq!(presets[1].name, "Translator");
+
+        db.delete_prompt_preset(&preset.id).await.unwrap();
+        assert_eq!(db.list_prompt_presets().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_templates() {
+        let db = Database::new_in_memory().unwrap();
+
+        db.save_template("Translate", "Translate to {{language}}:")
+            .await
+            .unwrap();
+        db.save_template("Explain", "Explain {{clipboard}}")
+            .await
+            .unwrap();
+        // Saving under an existing name, in any case, replaces it
+        db.save_template("translate", "Translate into {{language}}:")
+            .await
+            .unwrap();
+
+        let templates = db.list_templates().await.unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[1].name, "Translate");
+        assert_eq!(templates[1].content, "Translate into {{language}}:");
+
+        db.delete_template(&templates[0].id).await.unwrap();
+        assert_eq!(db.list_templates().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_assistants() {
+        let db = Database::new_in_memory().unwrap();
+        let now = Utc::now();
+
+        let (account, conv) = setup_conversation(&db).await;
+
+        let mut assistant = Assistant {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "SQL Helper".to_string(),
+            icon: "🗄".to_string(),
+            system_prompt: Some("You write SQLite.".to_string()),
+            account_id: Some(account.id.clone()),
+            model: Some("claude-haiku-4-5".to_string()),
+            temperature: Some(0.2),
+            files: vec![AssistantFile {
+                id: uuid::Uuid::new_v4().to_string(),
+                filename: "schema.sql".to_string(),
+                mime_type: "text/plain".to_string(),
+                data: b"CREATE TABLE t (id INTEGER);".to_vec(),
+            }],
+            created_at: now,
+            updated_at: now,
+        };
+        db.save_assistant(&assistant).await.unwrap();
+
+        let loaded = db.list_assistants().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].temperature, Some(0.2));
+        assert_eq!(loaded[0].files.len(), 1);
+        assert_eq!(loaded[0].files[0].data, assistant.files[0].data);
+
+        // Saving again updates in place and replaces the files
+        assistant.name = "SQLite Helper".to_string();
+        assistant.files.clear();
+        db.save_assistant(&assistant).await.unwrap();
+        let loaded = db.list_assistants().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "SQLite Helper");
+        assert!(db
+            .list_assistant_files(&assistant.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let conv = Conversation {
+            id: uuid::Uuid::new_v4().to_string(),
+            system_prompt: assistant.system_prompt.clone(),
+            assistant_id: Some(assistant.id.clone()),
+            ..conv
+        };
+        db.insert_conversation(&conv).await.unwrap();
+        let loaded = db.get_conversation(&conv.id).await.unwrap().unwrap();
+        assert_eq!(loaded.assistant_id.as_deref(), Some(assistant.id.as_str()));
+
+        // Deleting the assistant keeps its chats
+        db.delete_assistant(&assistant.id).await.unwrap();
+        let loaded = db.get_conversation(&conv.id).await.unwrap().unwrap();
+        assert_eq!(loaded.assistant_id, None);
+    }
 }
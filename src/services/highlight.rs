@@ -0,0 +1,134 @@
+use std::sync::OnceLock;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+/// A run of code drawn in one style. Offsets count characters, as `gtk::TextBuffer` does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightSpan {
+    pub start: usize,
+    pub end: usize,
+    /// Foreground as RGB; `None` keeps the normal text color.
+    pub color: Option<(u8, u8, u8)>,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme(dark: bool) -> &'static Theme {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    let themes = THEMES.get_or_init(ThemeSet::load_defaults);
+    &themes.themes[if dark { DARK_THEME } else { LIGHT_THEME }]
+}
+
+/// The syntax for a code fence's language, by name or file extension.
+fn find_syntax(language: &str) -> Option<&'static SyntaxReference> {
+    let language = language.trim().to_lowercase();
+    // Common fence names the bundled syntaxes know under another name
+    let token = match language.as_str() {
+        "shell" | "sh" | "zsh" | "console" => "bash",
+        "ts" | "tsx" | "typescript" | "jsx" | "javascript" => "js",
+        "c++" => "cpp",
+        "c#" | "csharp" => "cs",
+        "golang" => "go",
+        "yml" => "yaml",
+        "python3" => "py",
+        other => other,
+    };
+    syntaxes()
+        .find_syntax_by_token(token)
+        .filter(|s| s.name != "Plain Text")
+}
+
+/// Style runs for `code` in `language`, themed for a light or dark background.
+/// Empty when the language is unknown, leaving the code plain.
+pub fn highlight(code: &str, language: &str, dark: bool) -> Vec<HighlightSpan> {
+    let Some(syntax) = find_syntax(language) else {
+        return Vec::new();
+    };
+    let theme = theme(dark);
+    let default_color = theme.settings.foreground;
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    let mut spans: Vec<HighlightSpan> = Vec::new();
+    let mut offset = 0;
+    for line in LinesWithEndings::from(code) {
+        let Ok(ranges) = highlighter.highlight_line(line, syntaxes()) else {
+            return Vec::new();
+        };
+        for (style, text) in ranges {
+            let len = text.chars().count();
+            let color = Some(style.foreground)
+                .filter(|c| Some(*c) != default_color)
+                .map(|Color { r, g, b, .. }| (r, g, b));
+            let bold = style.font_style.contains(FontStyle::BOLD);
+            let italic = style.font_style.contains(FontStyle::ITALIC);
+
+            if color.is_some() || bold || italic {
+                match spans.last_mut() {
+                    // Neighbouring runs in the same style share one span
+                    Some(last)
+                        if last.end == offset
+                            && (last.color, last.bold, last.italic) == (color, bold, italic) =>
+                    {
+                        last.end += len
+                    }
+                    _ => spans.push(HighlightSpan {
+                        start: offset,
+                        end: offset + len,
+                        color,
+                        bold,
+                        italic,
+                    }),
+                }
+            }
+            offset += len;
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_syntax() {
+        assert_eq!(find_syntax("rust").unwrap().name, "Rust");
+        assert_eq!(find_syntax("RS").unwrap().name, "Rust");
+        assert_eq!(find_syntax("sh").unwrap().name, "Bourne Again Shell (bash)");
+        assert!(find_syntax("not-a-language").is_none());
+        assert!(find_syntax("text").is_none());
+    }
+
+    #[test]
+    fn test_highlight() {
+        let code = "fn main() {\n    let s = \"héllo\";\n}\n";
+        let spans = highlight(code, "rust", false);
+        assert!(!spans.is_empty());
+
+        // Offsets are in characters and stay inside the code
+        let chars: Vec<char> = code.chars().collect();
+        let keyword = spans.iter().find(|s| s.start == 0).unwrap();
+        assert_eq!(
+            chars[keyword.start..keyword.end].iter().collect::<String>(),
+            "fn"
+        );
+        assert!(spans
+            .iter()
+            .all(|s| s.start < s.end && s.end <= chars.len()));
+
+        // Dark and light themes color differently
+        assert_ne!(spans, highlight(code, "rust", true));
+        assert!(highlight(code, "unknown", false).is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use base64::Engine;
+
+/// Largest remote image downloaded for a message.
+const MAX_REMOTE_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Largest local image file read for a message.
+const MAX_LOCAL_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Where a markdown image's bytes come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// Decoded `data:` URI contents.
+    Data(Vec<u8>),
+    File(PathBuf),
+    /// An `http(s)` URL, only fetched once the user allows it.
+    Remote(String),
+}
+
+/// Resolve an image URL from markdown. `None` for URLs that can't be loaded, like
+/// relative paths or non-base64 data URIs.
+pub fn image_source(url: &str) -> Option<ImageSource> {
+    let url = url.trim();
+    if let Some(data) = url.strip_prefix("data:") {
+        let (meta, payload) = data.split_once(',')?;
+        if !meta.starts_with("image/") || !meta.ends_with(";base64") {
+            return None;
+        }
+        // Models wrap long payloads over several lines
+        let payload: String = payload.split_whitespace().collect();
+        return base64::engine::general_purpose::STANDARD
+            .decode(payload)
+            .ok()
+            .map(ImageSource::Data);
+    }
+
+    let lower = url.to_ascii_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        return Some(ImageSource::Remote(url.to_string()));
+    }
+    if lower.starts_with("file://") {
+        let path = percent_decode(url[7..].trim_start_matches("localhost"));
+        return path
+            .starts_with('/')
+            .then(|| ImageSource::File(path.into()));
+    }
+    if let Some(rest) = url.strip_prefix("~/") {
+        let home = std::env::var_os("HOME")?;
+        return Some(ImageSource::File(PathBuf::from(home).join(rest)));
+    }
+    url.starts_with('/')
+        .then(|| ImageSource::File(PathBuf::from(url)))
+}
+
+/// Download a remote image, refusing anything that isn't an image or is too large.
+pub async fn fetch_remote_image(url: &str) -> Result<Vec<u8>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+
+    let is_image = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|v| v.starts_with("image/"));
+    if !is_image {
+        return Err("Not an image".to_string());
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > MAX_REMOTE_IMAGE_BYTES)
+    {
+        return Err("Image is too large".to_string());
+    }
+
+    // The length header is optional, so enforce the limit while reading too
+    let mut response = response;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if bytes.len() + chunk.len() > MAX_REMOTE_IMAGE_BYTES {
+            return Err("Image is too large".to_string());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Read a local image, refusing anything but a regular file under the size limit.
+/// Paths come from model output, so devices like `/dev/zero` must not be read.
+pub async fn read_local_image(path: PathBuf) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        // Check before opening too, as opening a FIFO blocks until a writer shows up
+        let metadata = std::fs::metadata(&path).map_err(|e| e.to_string())?;
+        if !metadata.is_file() {
+            return Err("Not a regular file".to_string());
+        }
+        let file = std::fs::File::open(&path).map_err(|e| e.to_string())?;
+        let metadata = file.metadata().map_err(|e| e.to_string())?;
+        if !metadata.is_file() {
+            return Err("Not a regular file".to_string());
+        }
+        if metadata.len() > MAX_LOCAL_IMAGE_BYTES {
+            return Err("Image is too large".to_string());
+        }
+        // The file may grow after the check
+        let mut bytes = Vec::new();
+        file.take(MAX_LOCAL_IMAGE_BYTES + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())?;
+        if bytes.len() as u64 > MAX_LOCAL_IMAGE_BYTES {
+            return Err("Image is too large".to_string());
+        }
+        Ok(bytes)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Decode `%XX` escapes, as used in `file://` URLs.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_source() {
+        assert_eq!(
+            image_source("data:image/png;base64,aGVs\nbG8="),
+            Some(ImageSource::Data(b"hello".to_vec()))
+        );
+        assert_eq!(image_source("data:text/plain;base64,aGVsbG8="), None);
+        assert_eq!(
+            image_source("https://example.com/cat.png"),
+            Some(ImageSource::Remote(
+                "https://example.com/cat.png".to_string()
+            ))
+        );
+        assert_eq!(
+            image_source("file:///tmp/My%20Plot.png"),
+            Some(ImageSource::File("/tmp/My Plot.png".into()))
+        );
+        assert_eq!(
+            image_source("/tmp/plot.png"),
+            Some(ImageSource::File("/tmp/plot.png".into()))
+        );
+        assert_eq!(image_source("images/plot.png"), None);
+        assert_eq!(image_source("ftp://example.com/a.png"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_local_image() {
+        assert_eq!(
+            read_local_image("/dev/zero".into()).await,
+            Err("Not a regular file".to_string())
+        );
+        assert!(read_local_image("/nonexistent/plot.png".into())
+            .await
+            .is_err());
+
+        let path = std::env::temp_dir().join(format!("echo-image-{}.png", std::process::id()));
+        std::fs::write(&path, b"png bytes").unwrap();
+        assert_eq!(
+            read_local_image(path.clone()).await,
+            Ok(b"png bytes".to_vec())
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
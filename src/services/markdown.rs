@@ -1,4 +1,6 @@
-use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use crate::services::math::tex_to_markup;
 
 #[derive(Debug, Clone)]
 pub enum MessageBlock {
@@ -12,9 +14,43 @@ pub enum MessageBlock {
         spans: Vec<InlineSpan>,
     },
     BlockQuote(Vec<MessageBlock>),
-    OrderedList(Vec<Vec<MessageBlock>>),
-    UnorderedList(Vec<Vec<MessageBlock>>),
+    OrderedList(Vec<ListItem>),
+    UnorderedList(Vec<ListItem>),
     HorizontalRule,
+    /// A `$$...$$` display equation, as TeX.
+    Math(String),
+    Image {
+        url: String,
+        alt: String,
+    },
+    Table {
+        alignments: Vec<ColumnAlignment>,
+        header: Vec<Vec<InlineSpan>>,
+        rows: Vec<Vec<Vec<InlineSpan>>>,
+    },
+    /// Footnote definitions, gathered at the end of the message in reference order.
+    Footnotes(Vec<Footnote>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ListItem {
+    /// `Some(checked)` for task list items (`- [ ] todo`).
+    pub task: Option<bool>,
+    pub blocks: Vec<MessageBlock>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnAlignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone)]
+pub struct Footnote {
+    pub number: usize,
+    pub blocks: Vec<MessageBlock>,
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +60,9 @@ pub struct InlineSpan {
     pub italic: bool,
     pub strikethrough: bool,
     pub code: bool,
+    pub superscript: bool,
+    /// `$...$` inline math; `text` is the TeX.
+    pub math: bool,
     pub link_url: Option<String>,
 }
 
@@ -35,14 +74,23 @@ impl InlineSpan {
             italic: false,
             strikethrough: false,
             code: false,
+            superscript: false,
+            math: false,
             link_url: None,
         }
     }
 }
 
+fn options() -> Options {
+    Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH
+}
+
 pub fn parse_markdown(input: &str) -> Vec<MessageBlock> {
-    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
-    let parser = Parser::new_ext(input, options);
+    let parser = Parser::new_ext(input, options());
     let events: Vec<Event> = parser.collect();
 
     let mut ctx = ParseContext::new();
@@ -50,6 +98,35 @@ pub fn parse_markdown(input: &str) -> Vec<MessageBlock> {
     ctx.finish()
 }
 
+/// Byte offset where the part of a streaming reply that may still change begins:
+/// its last top-level block. Blocks before it are complete and render the same
+/// whatever follows. A list just before the last block stays open too, since an
+/// item after a blank line (`1. a\n\n2`) can still join it.
+pub fn open_block_start(text: &str) -> usize {
+    let mut depth = 0;
+    // Start of each top-level block, and whether it's a list
+    let mut starts: Vec<(usize, bool)> = Vec::new();
+    for (event, range) in Parser::new_ext(text, options()).into_offset_iter() {
+        match event {
+            Event::Start(tag) => {
+                if depth == 0 {
+                    starts.push((range.start, matches!(tag, Tag::List(_))));
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            // Leaf blocks without a container, like rules
+            _ if depth == 0 => starts.push((range.start, false)),
+            _ => {}
+        }
+    }
+    match starts.as_slice() {
+        [.., (list, true), _] => *list,
+        [.., (last, _)] => *last,
+        [] => text.len(),
+    }
+}
+
 struct ParseContext {
     blocks: Vec<MessageBlock>,
     // Current inline spans being accumulated
@@ -70,12 +147,27 @@ struct ParseContext {
     blockquote_depth: u32,
     blockquote_blocks: Vec<Vec<MessageBlock>>,
     list_stack: Vec<ListState>,
+    table: Option<TableState>,
+    // Footnote labels in the order they're first referenced
+    footnote_labels: Vec<String>,
+    footnote_definitions: Vec<(String, Vec<MessageBlock>)>,
+    in_footnote_definition: bool,
+    // Destination and alt text of the image being read
+    image: Option<(String, String)>,
 }
 
 struct ListState {
     ordered: bool,
-    items: Vec<Vec<MessageBlock>>,
+    items: Vec<ListItem>,
     current_item_blocks: Vec<MessageBlock>,
+    current_item_task: Option<bool>,
+}
+
+struct TableState {
+    alignments: Vec<ColumnAlignment>,
+    header: Vec<Vec<InlineSpan>>,
+    rows: Vec<Vec<Vec<InlineSpan>>>,
+    current_row: Vec<Vec<InlineSpan>>,
 }
 
 impl ParseContext {
@@ -96,6 +188,11 @@ impl ParseContext {
             blockquote_depth: 0,
             blockquote_blocks: Vec::new(),
             list_stack: Vec::new(),
+            table: None,
+            footnote_labels: Vec::new(),
+            footnote_definitions: Vec::new(),
+            in_footnote_definition: false,
+            image: None,
         }
     }
 
@@ -114,6 +211,14 @@ impl ParseContext {
             Event::SoftBreak => self.handle_soft_break(),
             Event::HardBreak => self.handle_hard_break(),
             Event::Rule => self.handle_rule(),
+            Event::TaskListMarker(checked) => {
+                if let Some(list_state) = self.list_stack.last_mut() {
+                    list_state.current_item_task = Some(*checked);
+                }
+            }
+            Event::FootnoteReference(label) => self.handle_footnote_reference(label),
+            Event::InlineMath(tex) => self.handle_math(tex, false),
+            Event::DisplayMath(tex) => self.handle_math(tex, true),
             _ => {}
         }
     }
@@ -158,11 +263,30 @@ impl ParseContext {
                     ordered: start.is_some(),
                     items: Vec::new(),
                     current_item_blocks: Vec::new(),
+                    current_item_task: None,
                 });
             }
             Tag::Item => {
                 // Start collecting blocks for this list item
             }
+            Tag::Table(alignments) => {
+                self.flush_paragraph();
+                self.table = Some(TableState {
+                    alignments: alignments.iter().map(column_alignment).collect(),
+                    header: Vec::new(),
+                    rows: Vec::new(),
+                    current_row: Vec::new(),
+                });
+            }
+            Tag::Image { dest_url, .. } => {
+                self.image = Some((dest_url.to_string(), String::new()));
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.flush_paragraph();
+                self.in_footnote_definition = true;
+                self.footnote_definitions
+                    .push((label.to_string(), Vec::new()));
+            }
             _ => {}
         }
     }
@@ -212,8 +336,11 @@ impl ParseContext {
                 if let Some(mut list_state) = self.list_stack.pop() {
                     // Push any remaining item blocks
                     if !list_state.current_item_blocks.is_empty() {
-                        let item_blocks = std::mem::take(&mut list_state.current_item_blocks);
-                        list_state.items.push(item_blocks);
+                        let blocks = std::mem::take(&mut list_state.current_item_blocks);
+                        list_state.items.push(ListItem {
+                            task: list_state.current_item_task.take(),
+                            blocks,
+                        });
                     }
                     let block = if list_state.ordered {
                         MessageBlock::OrderedList(list_state.items)
@@ -226,10 +353,48 @@ impl ParseContext {
             TagEnd::Item => {
                 self.flush_paragraph_into_list();
                 if let Some(list_state) = self.list_stack.last_mut() {
-                    let item_blocks = std::mem::take(&mut list_state.current_item_blocks);
-                    list_state.items.push(item_blocks);
+                    let blocks = std::mem::take(&mut list_state.current_item_blocks);
+                    list_state.items.push(ListItem {
+                        task: list_state.current_item_task.take(),
+                        blocks,
+                    });
+                }
+            }
+            TagEnd::TableCell => {
+                let spans = std::mem::take(&mut self.current_spans);
+                if let Some(table) = &mut self.table {
+                    table.current_row.push(spans);
                 }
             }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header = std::mem::take(&mut table.current_row);
+                }
+            }
+            TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.current_row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.push_block(MessageBlock::Table {
+                        alignments: table.alignments,
+                        header: table.header,
+                        rows: table.rows,
+                    });
+                }
+            }
+            TagEnd::Image => {
+                if let Some((url, alt)) = self.image.take() {
+                    self.handle_image(url, alt);
+                }
+            }
+            TagEnd::FootnoteDefinition => {
+                self.flush_paragraph();
+                self.in_footnote_definition = false;
+            }
             _ => {}
         }
     }
@@ -239,6 +404,10 @@ impl ParseContext {
             self.code_block_content.push_str(text);
             return;
         }
+        if let Some((_, alt)) = &mut self.image {
+            alt.push_str(text);
+            return;
+        }
 
         let span = InlineSpan {
             text: text.to_string(),
@@ -246,6 +415,8 @@ impl ParseContext {
             italic: self.italic,
             strikethrough: self.strikethrough,
             code: self.code_inline,
+            superscript: false,
+            math: false,
             link_url: self.link_url.clone(),
         };
 
@@ -257,12 +428,18 @@ impl ParseContext {
     }
 
     fn handle_inline_code(&mut self, code: &pulldown_cmark::CowStr) {
+        if let Some((_, alt)) = &mut self.image {
+            alt.push_str(code);
+            return;
+        }
         let span = InlineSpan {
             text: code.to_string(),
             bold: self.bold,
             italic: self.italic,
             strikethrough: self.strikethrough,
             code: true,
+            superscript: false,
+            math: false,
             link_url: self.link_url.clone(),
         };
 
@@ -273,6 +450,70 @@ impl ParseContext {
         }
     }
 
+    fn handle_image(&mut self, url: String, alt: String) {
+        // Headings and table cells only hold text, so the image becomes a link there
+        if self.heading_level.is_some() || self.table.is_some() {
+            let span = InlineSpan {
+                link_url: Some(url.clone()),
+                ..InlineSpan::new(if alt.is_empty() { url } else { alt })
+            };
+            if self.heading_level.is_some() {
+                self.heading_spans.push(span);
+            } else {
+                self.current_spans.push(span);
+            }
+            return;
+        }
+        self.flush_paragraph();
+        self.push_block(MessageBlock::Image { url, alt });
+    }
+
+    fn handle_math(&mut self, tex: &pulldown_cmark::CowStr, display: bool) {
+        // Display math gets a block of its own, except where only inline content fits
+        if display && self.heading_level.is_none() && self.table.is_none() {
+            self.flush_paragraph();
+            self.push_block(MessageBlock::Math(tex.trim().to_string()));
+            return;
+        }
+
+        let span = InlineSpan {
+            bold: self.bold,
+            italic: self.italic,
+            math: true,
+            link_url: self.link_url.clone(),
+            ..InlineSpan::new(tex.trim().to_string())
+        };
+        if self.heading_level.is_some() {
+            self.heading_spans.push(span);
+        } else {
+            self.current_spans.push(span);
+        }
+    }
+
+    fn handle_footnote_reference(&mut self, label: &pulldown_cmark::CowStr) {
+        let number = self.footnote_number(label);
+        let span = InlineSpan {
+            superscript: true,
+            ..InlineSpan::new(format!("[{}]", number))
+        };
+        if self.heading_level.is_some() {
+            self.heading_spans.push(span);
+        } else {
+            self.current_spans.push(span);
+        }
+    }
+
+    /// Footnotes are numbered in the order they're first referenced.
+    fn footnote_number(&mut self, label: &str) -> usize {
+        match self.footnote_labels.iter().position(|l| l == label) {
+            Some(i) => i + 1,
+            None => {
+                self.footnote_labels.push(label.to_string());
+                self.footnote_labels.len()
+            }
+        }
+    }
+
     fn handle_soft_break(&mut self) {
         let span = InlineSpan::new(" ".to_string());
         if self.heading_level.is_some() {
@@ -328,11 +569,31 @@ impl ParseContext {
                 return;
             }
         }
+        if self.in_footnote_definition {
+            if let Some((_, blocks)) = self.footnote_definitions.last_mut() {
+                blocks.push(block);
+                return;
+            }
+        }
         self.blocks.push(block);
     }
 
     fn finish(mut self) -> Vec<MessageBlock> {
         self.flush_paragraph();
+
+        // Definitions nobody references are numbered after the referenced ones
+        let definitions = std::mem::take(&mut self.footnote_definitions);
+        let mut footnotes: Vec<Footnote> = definitions
+            .into_iter()
+            .map(|(label, blocks)| Footnote {
+                number: self.footnote_number(&label),
+                blocks,
+            })
+            .collect();
+        if !footnotes.is_empty() {
+            footnotes.sort_by_key(|f| f.number);
+            self.blocks.push(MessageBlock::Footnotes(footnotes));
+        }
         self.blocks
     }
 }
@@ -348,15 +609,38 @@ fn heading_level_to_u8(level: &HeadingLevel) -> u8 {
     }
 }
 
+fn column_alignment(alignment: &Alignment) -> ColumnAlignment {
+    match alignment {
+        Alignment::None => ColumnAlignment::None,
+        Alignment::Left => ColumnAlignment::Left,
+        Alignment::Center => ColumnAlignment::Center,
+        Alignment::Right => ColumnAlignment::Right,
+    }
+}
+
+/// The text of `spans` with inline math written back as `$...$` TeX.
+pub fn spans_to_tex(spans: &[InlineSpan]) -> String {
+    spans
+        .iter()
+        .map(|span| {
+            if span.math {
+                format!("${}$", span.text)
+            } else {
+                span.text.clone()
+            }
+        })
+        .collect()
+}
+
 /// Convert inline spans to Pango markup string
 pub fn spans_to_pango_markup(spans: &[InlineSpan]) -> String {
     let mut markup = String::new();
     for span in spans {
         // Open tags
         if let Some(url) = &span.link_url {
-            markup.push_str("<a href=\"");
-            markup.push_str(&glib::markup_escape_text(url));
-            markup.push_str("\">");
+            // The title shows the target on hover
+            let url = glib::markup_escape_text(url);
+            markup.push_str(&format!("<a href=\"{}\" title=\"{}\">", url, url));
         }
         if span.strikethrough {
             markup.push_str("<s>");
@@ -370,11 +654,21 @@ pub fn spans_to_pango_markup(spans: &[InlineSpan]) -> String {
         if span.code {
             markup.push_str("<tt>");
         }
+        if span.superscript {
+            markup.push_str("<sup>");
+        }
 
         // Text content
-        markup.push_str(&glib::markup_escape_text(&span.text));
+        if span.math {
+            markup.push_str(&tex_to_markup(&span.text, false));
+        } else {
+            markup.push_str(&glib::markup_escape_text(&span.text));
+        }
 
         // Close tags (reverse order)
+        if span.superscript {
+            markup.push_str("</sup>");
+        }
         if span.code {
             markup.push_str("</tt>");
         }
@@ -483,6 +777,121 @@ mod tests {
             .any(|b| matches!(b, MessageBlock::HorizontalRule)));
     }
 
+    #[test]
+    fn test_table() {
+        let blocks =
+            parse_markdown("| Name | Qty |\n|:-----|----:|\n| **apple** | 3 |\n| pear | 10 |");
+        assert_eq!(blocks.len(), 1);
+        match &blocks[0] {
+            MessageBlock::Table {
+                alignments,
+                header,
+                rows,
+            } => {
+                assert_eq!(
+                    alignments,
+                    &vec![ColumnAlignment::Left, ColumnAlignment::Right]
+                );
+                assert_eq!(header.len(), 2);
+                assert_eq!(header[1][0].text, "Qty");
+                assert_eq!(rows.len(), 2);
+                assert!(rows[0][0][0].bold && rows[0][0][0].text == "apple");
+                assert_eq!(rows[1][1][0].text, "10");
+            }
+            _ => panic!("Expected Table"),
+        }
+    }
+
+    #[test]
+    fn test_task_list() {
+        let blocks = parse_markdown("- [x] done\n- [ ] todo\n- plain");
+        match &blocks[0] {
+            MessageBlock::UnorderedList(items) => {
+                let tasks: Vec<_> = items.iter().map(|item| item.task).collect();
+                assert_eq!(tasks, vec![Some(true), Some(false), None]);
+                assert!(
+                    matches!(&items[1].blocks[0], MessageBlock::RichText(spans) if spans[0].text == "todo")
+                );
+            }
+            _ => panic!("Expected UnorderedList"),
+        }
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let blocks = parse_markdown(
+            "First[^b] and second[^a].\n\n[^a]: Note A\n\n[^b]: Note B\n\n[^c]: Unused",
+        );
+        assert_eq!(blocks.len(), 2);
+        match &blocks[0] {
+            MessageBlock::RichText(spans) => {
+                let refs: Vec<_> = spans
+                    .iter()
+                    .filter(|s| s.superscript)
+                    .map(|s| s.text.as_str())
+                    .collect();
+                assert_eq!(refs, vec!["[1]", "[2]"]);
+            }
+            _ => panic!("Expected RichText"),
+        }
+        match &blocks[1] {
+            MessageBlock::Footnotes(notes) => {
+                let texts: Vec<_> = notes
+                    .iter()
+                    .map(|n| match &n.blocks[0] {
+                        MessageBlock::RichText(spans) => (n.number, spans[0].text.as_str()),
+                        _ => panic!("Expected RichText"),
+                    })
+                    .collect();
+                assert_eq!(texts, vec![(1, "Note B"), (2, "Note A"), (3, "Unused")]);
+            }
+            _ => panic!("Expected Footnotes"),
+        }
+    }
+
+    #[test]
+    fn test_math() {
+        let blocks = parse_markdown("Euler: $e^{i\\pi} + 1 = 0$, so\n\n$$\\int_0^1 x\\,dx$$");
+        assert_eq!(blocks.len(), 2);
+        match &blocks[0] {
+            MessageBlock::RichText(spans) => {
+                let math = spans.iter().find(|s| s.math).unwrap();
+                assert_eq!(math.text, "e^{i\\pi} + 1 = 0");
+                assert_eq!(spans_to_tex(spans), "Euler: $e^{i\\pi} + 1 = 0$, so");
+                assert!(tex_to_markup(&math.text, false).contains("<sup><i>i</i>π</sup>"));
+            }
+            _ => panic!("Expected RichText"),
+        }
+        assert!(matches!(&blocks[1], MessageBlock::Math(tex) if tex == "\\int_0^1 x\\,dx"));
+        // Dollar amounts stay text
+        let blocks = parse_markdown("It costs $5 and $10.");
+        assert!(
+            matches!(&blocks[0], MessageBlock::RichText(spans) if spans.iter().all(|s| !s.math))
+        );
+    }
+
+    #[test]
+    fn test_image() {
+        let blocks = parse_markdown("Before ![a *plot*](/tmp/plot.png) after");
+        assert_eq!(blocks.len(), 3);
+        assert!(
+            matches!(&blocks[1], MessageBlock::Image { url, alt } if url == "/tmp/plot.png" && alt == "a plot")
+        );
+        assert!(matches!(&blocks[2], MessageBlock::RichText(spans) if spans[0].text == " after"));
+    }
+
+    #[test]
+    fn test_open_block_start() {
+        assert_eq!(open_block_start("Para one.\n\nPara two"), 11);
+        assert_eq!(open_block_start("# Title\n\n---\n\nText"), 14);
+        // An unclosed fence runs to the end
+        assert_eq!(open_block_start("Intro\n\n```rust\nfn main() {\n\n"), 7);
+        // The list may still gain items
+        assert_eq!(open_block_start("# Title\n\n1. a\n\n2"), 9);
+        assert_eq!(open_block_start("- a\n\nText\n\nMore"), 11);
+        assert_eq!(open_block_start("  \n"), 3);
+    }
+
     #[test]
     fn test_inline_code() {
         let blocks = parse_markdown("Use `foo()` here");
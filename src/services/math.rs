@@ -0,0 +1,568 @@
+/// Symbols drawn as-is.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ϵ"),
+    ("varepsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("vartheta", "ϑ"),
+    ("iota", "ι"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("pi", "π"),
+    ("varpi", "ϖ"),
+    ("rho", "ρ"),
+    ("varrho", "ϱ"),
+    ("sigma", "σ"),
+    ("varsigma", "ς"),
+    ("tau", "τ"),
+    ("upsilon", "υ"),
+    ("phi", "ϕ"),
+    ("varphi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Xi", "Ξ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Upsilon", "Υ"),
+    ("Phi", "Φ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    ("infty", "∞"),
+    ("partial", "∂"),
+    ("nabla", "∇"),
+    ("sum", "∑"),
+    ("prod", "∏"),
+    ("coprod", "∐"),
+    ("int", "∫"),
+    ("iint", "∬"),
+    ("iiint", "∭"),
+    ("oint", "∮"),
+    ("bigcup", "⋃"),
+    ("bigcap", "⋂"),
+    ("forall", "∀"),
+    ("exists", "∃"),
+    ("nexists", "∄"),
+    ("neg", "¬"),
+    ("lnot", "¬"),
+    ("emptyset", "∅"),
+    ("varnothing", "∅"),
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("cdots", "⋯"),
+    ("vdots", "⋮"),
+    ("ddots", "⋱"),
+    ("angle", "∠"),
+    ("triangle", "△"),
+    ("hbar", "ℏ"),
+    ("ell", "ℓ"),
+    ("Re", "ℜ"),
+    ("Im", "ℑ"),
+    ("aleph", "ℵ"),
+    ("prime", "′"),
+    ("degree", "°"),
+    ("langle", "⟨"),
+    ("rangle", "⟩"),
+    ("lfloor", "⌊"),
+    ("rfloor", "⌋"),
+    ("lceil", "⌈"),
+    ("rceil", "⌉"),
+    ("vert", "|"),
+    ("Vert", "‖"),
+    ("|", "‖"),
+    ("{", "{"),
+    ("}", "}"),
+    ("%", "%"),
+    ("$", "$"),
+    ("#", "#"),
+    ("_", "_"),
+    ("&", "&amp;"),
+    ("quad", "\u{2003}"),
+    ("qquad", "\u{2003}\u{2003}"),
+    (",", "\u{2009}"),
+    (":", "\u{2005}"),
+    (";", "\u{2004}"),
+    (" ", " "),
+    ("!", ""),
+];
+
+/// Binary operators and relations, drawn with a little space around them.
+const OPERATORS: &[(&str, &str)] = &[
+    ("times", "×"),
+    ("cdot", "⋅"),
+    ("div", "÷"),
+    ("pm", "±"),
+    ("mp", "∓"),
+    ("ast", "∗"),
+    ("star", "⋆"),
+    ("circ", "∘"),
+    ("bullet", "∙"),
+    ("oplus", "⊕"),
+    ("otimes", "⊗"),
+    ("cup", "∪"),
+    ("cap", "∩"),
+    ("setminus", "∖"),
+    ("land", "∧"),
+    ("wedge", "∧"),
+    ("lor", "∨"),
+    ("vee", "∨"),
+    ("leq", "≤"),
+    ("le", "≤"),
+    ("geq", "≥"),
+    ("ge", "≥"),
+    ("neq", "≠"),
+    ("ne", "≠"),
+    ("ll", "≪"),
+    ("gg", "≫"),
+    ("approx", "≈"),
+    ("equiv", "≡"),
+    ("cong", "≅"),
+    ("sim", "∼"),
+    ("simeq", "≃"),
+    ("propto", "∝"),
+    ("in", "∈"),
+    ("notin", "∉"),
+    ("ni", "∋"),
+    ("subset", "⊂"),
+    ("subseteq", "⊆"),
+    ("supset", "⊃"),
+    ("supseteq", "⊇"),
+    ("perp", "⊥"),
+    ("parallel", "∥"),
+    ("mid", "∣"),
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("leftarrow", "←"),
+    ("gets", "←"),
+    ("leftrightarrow", "↔"),
+    ("Rightarrow", "⇒"),
+    ("Leftarrow", "⇐"),
+    ("Leftrightarrow", "⇔"),
+    ("implies", "⟹"),
+    ("impliedby", "⟸"),
+    ("iff", "⟺"),
+    ("mapsto", "↦"),
+    ("longrightarrow", "⟶"),
+    ("longmapsto", "⟼"),
+];
+
+/// Named functions, set upright like `\sin`.
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "log", "ln", "lg", "exp", "lim", "liminf", "limsup", "max", "min", "sup", "inf", "det", "dim",
+    "ker", "deg", "arg", "gcd", "lcm", "mod", "Pr",
+];
+
+/// Combining marks for accents like `\hat{x}`.
+const ACCENTS: &[(&str, char)] = &[
+    ("hat", '\u{302}'),
+    ("widehat", '\u{302}'),
+    ("bar", '\u{304}'),
+    ("overline", '\u{305}'),
+    ("tilde", '\u{303}'),
+    ("widetilde", '\u{303}'),
+    ("vec", '\u{20d7}'),
+    ("dot", '\u{307}'),
+    ("ddot", '\u{308}'),
+];
+
+/// Commands that only affect TeX's layout and are dropped.
+const IGNORED: &[&str] = &[
+    "left",
+    "right",
+    "big",
+    "Big",
+    "bigg",
+    "Bigg",
+    "displaystyle",
+    "textstyle",
+    "limits",
+    "nolimits",
+];
+
+/// Render a TeX math expression as Pango markup: symbols become Unicode, scripts
+/// become `<sup>`/`<sub>` and variables are set in italics. `display` math may span
+/// several lines (`\\` rows); inline math stays on one.
+///
+/// Layout TeX can't express in a line of text, like stacked fractions and matrices,
+/// is flattened (`(a+b)/c`, rows separated by `;`). Unknown commands are kept as typed.
+pub fn tex_to_markup(tex: &str, display: bool) -> String {
+    let mut renderer = Renderer {
+        chars: tex.chars().collect(),
+        pos: 0,
+        display,
+    };
+    let mut out = String::new();
+    renderer.sequence(&mut out, Style::default(), false);
+    out.trim().to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Style {
+    upright: bool,
+    bold: bool,
+    blackboard: bool,
+    // Inside `\text{}`: spaces count
+    text: bool,
+    script: bool,
+}
+
+struct Renderer {
+    chars: Vec<char>,
+    pos: usize,
+    display: bool,
+}
+
+impl Renderer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Render atoms until the end of the input, or the `}` closing the current group.
+    fn sequence(&mut self, out: &mut String, style: Style, in_group: bool) {
+        while let Some(c) = self.peek() {
+            if c == '}' {
+                self.pos += 1;
+                if in_group {
+                    return;
+                }
+                out.push('}');
+                continue;
+            }
+            self.atom(out, style);
+        }
+    }
+
+    /// A command or group argument, with whether it's a single letter, number or symbol.
+    fn argument(&mut self, style: Style) -> (String, bool) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        let mut out = String::new();
+        let source: String = if self.peek() == Some('{') {
+            self.pos += 1;
+            self.sequence(&mut out, style, true);
+            // Without the braces, when the group was closed
+            let end = match self.chars.get(self.pos - 1) {
+                Some('}') if self.pos > start + 1 => self.pos - 1,
+                _ => self.pos,
+            };
+            self.chars[start + 1..end].iter().collect()
+        } else {
+            self.atom(&mut out, style);
+            self.chars[start..self.pos].iter().collect()
+        };
+        let source = source.trim();
+        let simple = source.chars().all(char::is_alphanumeric)
+            || source
+                .strip_prefix('\\')
+                .is_some_and(|name| name.chars().all(|c| c.is_ascii_alphabetic()));
+        (out, simple)
+    }
+
+    fn atom(&mut self, out: &mut String, style: Style) {
+        let Some(c) = self.peek() else {
+            return;
+        };
+        self.pos += 1;
+
+        match c {
+            '\\' => self.command(out, style),
+            '{' => self.sequence(out, style, true),
+            '^' | '_' => {
+                let tag = if c == '^' { "sup" } else { "sub" };
+                let (arg, _) = self.argument(Style {
+                    script: true,
+                    ..style
+                });
+                out.push_str(&format!("<{tag}>{arg}</{tag}>"));
+            }
+            '&' => out.push('\u{2003}'),
+            '\'' => out.push('′'),
+            c if c.is_whitespace() => {
+                if style.text {
+                    out.push(' ');
+                }
+            }
+            '=' | '<' | '>' | '+' | '-' if !style.text => {
+                let symbol = match c {
+                    '<' => "&lt;",
+                    '>' => "&gt;",
+                    '-' => "−",
+                    '+' => "+",
+                    _ => "=",
+                };
+                // A leading sign, as in `-x` or `x^{-1}`, isn't spaced
+                if (c == '-' || c == '+') && (out.is_empty() || out.ends_with('(')) {
+                    out.push_str(symbol);
+                } else {
+                    push_operator(out, symbol, style);
+                }
+            }
+            c if c.is_ascii_alphabetic() => push_letter(out, c, style),
+            c => push_escaped(out, c),
+        }
+    }
+
+    fn command(&mut self, out: &mut String, style: Style) {
+        let start = self.pos;
+        let name: String = match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                    self.pos += 1;
+                }
+                self.chars[start..self.pos].iter().collect()
+            }
+            Some(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            None => {
+                out.push('\\');
+                return;
+            }
+        };
+
+        if let Some((_, symbol)) = SYMBOLS.iter().find(|(n, _)| *n == name) {
+            out.push_str(symbol);
+            return;
+        }
+        if let Some((_, symbol)) = OPERATORS.iter().find(|(n, _)| *n == name) {
+            push_operator(out, symbol, style);
+            return;
+        }
+        if FUNCTIONS.contains(&name.as_str()) {
+            out.push_str(&name);
+            out.push('\u{2009}');
+            return;
+        }
+        if let Some((_, mark)) = ACCENTS.iter().find(|(n, _)| *n == name) {
+            let (arg, _) = self.argument(style);
+            out.push_str(&with_mark(&arg, *mark));
+            return;
+        }
+
+        match name.as_str() {
+            "\\" => out.push_str(if self.display { "\n" } else { "; " }),
+            "frac" | "dfrac" | "tfrac" => {
+                let (numerator, simple_numerator) = self.argument(style);
+                let (denominator, simple_denominator) = self.argument(style);
+                out.push_str(&parenthesize(&numerator, simple_numerator));
+                out.push('/');
+                out.push_str(&parenthesize(&denominator, simple_denominator));
+            }
+            "sqrt" => {
+                if self.peek() == Some('[') {
+                    let close = self.chars[self.pos..].iter().position(|&c| c == ']');
+                    if let Some(close) = close {
+                        let degree: String =
+                            self.chars[self.pos + 1..self.pos + close].iter().collect();
+                        self.pos += close + 1;
+                        out.push_str(&format!("<sup>{}</sup>", tex_to_markup(&degree, false)));
+                    }
+                }
+                let (radicand, simple) = self.argument(style);
+                out.push('√');
+                out.push_str(&parenthesize(&radicand, simple));
+            }
+            "text" | "textrm" | "mathrm" | "operatorname" | "mbox" => {
+                let text = name.starts_with("text") || name == "mbox";
+                let (arg, _) = self.argument(Style {
+                    upright: true,
+                    text,
+                    ..style
+                });
+                out.push_str(&arg);
+            }
+            "mathbf" | "textbf" | "boldsymbol" | "bm" => {
+                let (arg, _) = self.argument(Style {
+                    bold: true,
+                    upright: name == "mathbf" || name == "textbf",
+                    ..style
+                });
+                out.push_str(&arg);
+            }
+            "mathit" | "textit" => {
+                let (arg, _) = self.argument(Style {
+                    upright: false,
+                    ..style
+                });
+                out.push_str(&arg);
+            }
+            "mathbb" => {
+                let (arg, _) = self.argument(Style {
+                    blackboard: true,
+                    ..style
+                });
+                out.push_str(&arg);
+            }
+            "mathcal" | "mathscr" | "mathsf" | "mathtt" => {
+                let (arg, _) = self.argument(Style {
+                    upright: true,
+                    ..style
+                });
+                out.push_str(&arg);
+            }
+            "begin" | "end" => {
+                // Environment names aren't drawn; their rows and cells are
+                self.argument(style);
+            }
+            name if IGNORED.contains(&name) => {
+                // `\left.` and `\right.` stand for no delimiter
+                if matches!(name, "left" | "right") && self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+            }
+            _ => {
+                out.push('\\');
+                for c in name.chars() {
+                    push_escaped(out, c);
+                }
+            }
+        }
+    }
+}
+
+fn push_letter(out: &mut String, c: char, style: Style) {
+    let letter = if style.blackboard {
+        double_struck(c)
+    } else {
+        c
+    };
+    let italic = !style.upright && !style.blackboard && !style.text;
+    match (style.bold, italic) {
+        (true, true) => out.push_str(&format!("<b><i>{}</i></b>", letter)),
+        (true, false) => out.push_str(&format!("<b>{}</b>", letter)),
+        (false, true) => out.push_str(&format!("<i>{}</i>", letter)),
+        (false, false) => out.push(letter),
+    }
+}
+
+fn push_operator(out: &mut String, symbol: &str, style: Style) {
+    if style.script {
+        out.push_str(symbol);
+    } else {
+        out.push('\u{2009}');
+        out.push_str(symbol);
+        out.push('\u{2009}');
+    }
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '&' => out.push_str("&amp;"),
+        c => out.push(c),
+    }
+}
+
+fn parenthesize(markup: &str, simple: bool) -> String {
+    if simple {
+        markup.to_string()
+    } else {
+        format!("({})", markup)
+    }
+}
+
+/// Put a combining `mark` after the last character of `markup`, inside any closing tags.
+fn with_mark(markup: &str, mark: char) -> String {
+    let mut end = markup.len();
+    while markup[..end].ends_with('>') {
+        match markup[..end].rfind("</") {
+            Some(tag) => end = tag,
+            None => break,
+        }
+    }
+    format!("{}{}{}", &markup[..end], mark, &markup[end..])
+}
+
+/// The double-struck (`\mathbb`) form of a letter or digit.
+fn double_struck(c: char) -> char {
+    let special = match c {
+        'C' => Some('ℂ'),
+        'H' => Some('ℍ'),
+        'N' => Some('ℕ'),
+        'P' => Some('ℙ'),
+        'Q' => Some('ℚ'),
+        'R' => Some('ℝ'),
+        'Z' => Some('ℤ'),
+        _ => None,
+    };
+    let offset = match c {
+        'A'..='Z' => Some(0x1D538 + (c as u32 - 'A' as u32)),
+        'a'..='z' => Some(0x1D552 + (c as u32 - 'a' as u32)),
+        '0'..='9' => Some(0x1D7D8 + (c as u32 - '0' as u32)),
+        _ => None,
+    };
+    special
+        .or_else(|| offset.and_then(char::from_u32))
+        .unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols_and_scripts() {
+        assert_eq!(
+            tex_to_markup("x^2 + y_{i,j}", false),
+            "<i>x</i><sup>2</sup>\u{2009}+\u{2009}<i>y</i><sub><i>i</i>,<i>j</i></sub>"
+        );
+        assert_eq!(
+            tex_to_markup(r"\alpha \leq \pi", false),
+            "α\u{2009}≤\u{2009}π"
+        );
+        assert_eq!(
+            tex_to_markup("e^{-x}", false),
+            "<i>e</i><sup>−<i>x</i></sup>"
+        );
+        assert_eq!(
+            tex_to_markup(r"\mathbb{R}^n", false),
+            "ℝ<sup><i>n</i></sup>"
+        );
+        assert_eq!(tex_to_markup(r"\sin x", false), "sin\u{2009}<i>x</i>");
+    }
+
+    #[test]
+    fn test_fractions_and_roots() {
+        assert_eq!(tex_to_markup(r"\frac{1}{2}", false), "1/2");
+        assert_eq!(
+            tex_to_markup(r"\frac{a+b}{\pi}", false),
+            "(<i>a</i>\u{2009}+\u{2009}<i>b</i>)/π"
+        );
+        assert_eq!(
+            tex_to_markup(r"\sqrt[3]{x}", false),
+            "<sup>3</sup>√<i>x</i>"
+        );
+    }
+
+    #[test]
+    fn test_text_and_fallbacks() {
+        assert_eq!(
+            tex_to_markup(r"\text{if } x < 0", false),
+            "if <i>x</i>\u{2009}&lt;\u{2009}0"
+        );
+        assert_eq!(tex_to_markup(r"\hat{x}", false), "<i>x\u{302}</i>");
+        assert_eq!(tex_to_markup(r"\unknown{a}", false), r"\unknown<i>a</i>");
+        assert_eq!(tex_to_markup(r"a \\ b", true), "<i>a</i>\n<i>b</i>");
+        assert_eq!(tex_to_markup(r"a \\ b", false), "<i>a</i>; <i>b</i>");
+        // Unbalanced braces don't lose anything
+        assert_eq!(tex_to_markup("{x", false), "<i>x</i>");
+        assert_eq!(tex_to_markup("x}", false), "<i>x</i>}");
+    }
+}
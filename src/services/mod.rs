@@ -3,9 +3,18 @@ pub mod chat;
 pub mod conversation;
 pub mod database;
 pub mod export;
+pub mod highlight;
+pub mod images;
 pub mod keyring;
 pub mod markdown;
+pub mod math;
+pub mod palette;
+pub mod pdf;
+pub mod prompt_library;
 pub mod settings;
+pub mod templates;
+pub mod titles;
+pub mod tools;
 
 pub use accounts::AccountService;
 pub use database::Database;
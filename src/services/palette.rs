@@ -0,0 +1,202 @@
+use chrono::{DateTime, Utc};
+
+/// What choosing a command palette entry does.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PaletteTarget {
+    Conversation(String),
+    Message {
+        conversation_id: String,
+        message_id: String,
+    },
+    Model {
+        account_id: String,
+        model: String,
+    },
+    Action(PaletteAction),
+}
+
+/// App actions offered in the command palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaletteAction {
+    NewChat,
+    Preferences,
+    ExportConversation,
+    TogglePin,
+    SystemPrompt,
+    PromptLibrary,
+    Assistants,
+    Shortcuts,
+}
+
+impl PaletteAction {
+    pub const ALL: [PaletteAction; 8] = [
+        PaletteAction::NewChat,
+        PaletteAction::Preferences,
+        PaletteAction::ExportConversation,
+        PaletteAction::TogglePin,
+        PaletteAction::SystemPrompt,
+        PaletteAction::PromptLibrary,
+        PaletteAction::Assistants,
+        PaletteAction::Shortcuts,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            PaletteAction::NewChat => "New Chat",
+            PaletteAction::Preferences => "Preferences",
+            PaletteAction::ExportConversation => "Export Conversation",
+            PaletteAction::TogglePin => "Pin or Unpin Conversation",
+            PaletteAction::SystemPrompt => "Edit System Prompt",
+            PaletteAction::PromptLibrary => "Prompt Library",
+            PaletteAction::Assistants => "Assistants",
+            PaletteAction::Shortcuts => "Keyboard Shortcuts",
+        }
+    }
+
+    pub fn icon_name(self) -> &'static str {
+        match self {
+            PaletteAction::NewChat => "list-add-symbolic",
+            PaletteAction::Preferences => "preferences-system-symbolic",
+            PaletteAction::ExportConversation => "document-save-symbolic",
+            PaletteAction::TogglePin => "view-pin-symbolic",
+            PaletteAction::SystemPrompt => "document-edit-symbolic",
+            PaletteAction::PromptLibrary => "accessories-text-editor-symbolic",
+            PaletteAction::Assistants => "system-users-symbolic",
+            PaletteAction::Shortcuts => "preferences-desktop-keyboard-shortcuts-symbolic",
+        }
+    }
+
+    /// Whether the action works on the open conversation.
+    pub fn needs_conversation(self) -> bool {
+        matches!(
+            self,
+            PaletteAction::ExportConversation
+                | PaletteAction::TogglePin
+                | PaletteAction::SystemPrompt
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteItem {
+    pub target: PaletteTarget,
+    pub title: String,
+    pub subtitle: String,
+    /// When the item was last used or updated; recent items rank higher.
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// Indices of the items matching `query`, best first. Title matches outrank subtitle
+/// matches, and recently used items get a boost that fades over a few days. Without
+/// a query, items are listed most recent first.
+pub fn rank(items: &[PaletteItem], query: &str, now: DateTime<Utc>) -> Vec<usize> {
+    let query = query.trim();
+    if query.is_empty() {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        // Stable, so items never used keep their order at the end
+        order.sort_by(|&a, &b| items[b].last_used.cmp(&items[a].last_used));
+        return order;
+    }
+
+    let mut scored: Vec<(i32, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let title = fuzzy_score(query, &item.title);
+            let subtitle = fuzzy_score(query, &item.subtitle).map(|s| s / 3);
+            let score = title.max(subtitle)?;
+            Some((score + recency_bonus(item.last_used, now), i))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Score `text` against `query` when every query character appears in it in order,
+/// favouring matches at word starts and runs of consecutive characters.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query = query.to_lowercase();
+    let lowered = text.to_lowercase();
+    let text: Vec<char> = lowered.chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut previous: Option<usize> = None;
+
+    for qc in query.chars().filter(|c| !c.is_whitespace()) {
+        let pos = from + text[from..].iter().position(|&c| c == qc)?;
+        score += 1;
+        if pos == 0 || !text[pos - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match previous {
+            Some(p) if p + 1 == pos => score += 5,
+            Some(p) => score -= (pos - p - 1).min(3) as i32,
+            None => {}
+        }
+        previous = Some(pos);
+        from = pos + 1;
+    }
+
+    // The whole query appearing as typed at the start of a word
+    let whole = query.trim();
+    let at_word_start = lowered.match_indices(whole).any(|(i, _)| {
+        lowered[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric())
+    });
+    if !whole.is_empty() && at_word_start {
+        score += 10;
+    }
+    Some(score)
+}
+
+fn recency_bonus(last_used: Option<DateTime<Utc>>, now: DateTime<Utc>) -> i32 {
+    match last_used {
+        Some(time) => {
+            let days = (now - time).num_days().max(0);
+            (20 / (1 + days)) as i32
+        }
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn item(title: &str, subtitle: &str, last_used: Option<DateTime<Utc>>) -> PaletteItem {
+        PaletteItem {
+            target: PaletteTarget::Conversation(title.to_string()),
+            title: title.to_string(),
+            subtitle: subtitle.to_string(),
+            last_used,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("xyz", "New Chat").is_none());
+        assert!(fuzzy_score("ab", "ba").is_none());
+        // Word starts beat letters buried in words
+        assert!(fuzzy_score("nc", "New Chat") > fuzzy_score("nc", "Cancel"));
+        // Contiguous beats scattered
+        assert!(fuzzy_score("pin", "Pin conversation") > fuzzy_score("pin", "Prompt injection"));
+    }
+
+    #[test]
+    fn test_rank() {
+        let now = Utc::now();
+        let items = vec![
+            item("Rust lifetimes", "gpt-4o", Some(now - Duration::days(30))),
+            item("Trip planning", "rust-belt", Some(now)),
+            item("Rust macros", "claude", Some(now - Duration::hours(1))),
+            item("Groceries", "gemini", None),
+        ];
+        // Title matches first, the most recent of them leading; then the subtitle match
+        assert_eq!(rank(&items, "rust", now), vec![2, 0, 1]);
+        // No query lists everything by recency
+        assert_eq!(rank(&items, "", now), vec![1, 2, 0, 3]);
+    }
+}
@@ -0,0 +1,743 @@
+//! Best-effort PDF text extraction, used to send documents to models that
+//! can't read PDFs natively.
+//!
+//! Text is decoded through its font's ToUnicode map when the font has one, and read
+//! as Latin-1 (or UTF-16 with a byte order mark) otherwise. Composite fonts, such as
+//! Identity-H fonts, draw glyph IDs rather than characters, so a document using one
+//! without a ToUnicode map is refused instead of returning garbage. Scanned pages
+//! yield nothing.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+
+use gio::prelude::*;
+
+/// Stream dictionary keys marking data that never holds page text.
+const NON_CONTENT_MARKERS: &[&[u8]] = &[
+    b"/Image",
+    b"/XRef",
+    b"/ObjStm",
+    b"/Metadata",
+    b"/EmbeddedFile",
+    b"/Length1",
+    b"/Type1C",
+    b"/CIDFontType0C",
+    b"/OpenType",
+];
+
+/// Longest code range a CMap may map in one entry, so a bogus range can't fill memory.
+const MAX_CMAP_RANGE: u32 = 0xFFFF;
+
+/// How the strings shown with a font decode to text.
+enum FontEncoding {
+    /// Single-byte codes read as Latin-1, or UTF-16 with a byte order mark.
+    Simple,
+    /// Codes mapped to text by the font's ToUnicode CMap.
+    ToUnicode(CMap),
+    /// A composite font without a ToUnicode CMap, whose codes are glyph IDs.
+    Unreadable,
+}
+
+/// A ToUnicode CMap: character codes of a fixed byte length and the text they stand for.
+struct CMap {
+    code_len: usize,
+    map: HashMap<u32, String>,
+}
+
+impl CMap {
+    fn decode(&self, bytes: &[u8]) -> String {
+        bytes
+            .chunks(self.code_len)
+            .filter_map(|code| self.map.get(&code_value(code)))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Extract the readable text of a PDF, or `None` if none was found or some of
+/// it is drawn with a font whose characters can't be recovered.
+pub fn extract_text(data: &[u8]) -> Option<String> {
+    let objects = objects(data);
+    let fonts = fonts(&objects);
+    let mut text = String::new();
+
+    for (dict, raw) in streams(data) {
+        if NON_CONTENT_MARKERS.iter().any(|m| contains(dict, m)) {
+            continue;
+        }
+        let Some(decoded) = stream_data(dict, raw) else {
+            continue;
+        };
+
+        let Some(page) = content_text(&decoded, &fonts) else {
+            tracing::info!("PDF uses a font without a ToUnicode map, not extracting text");
+            return None;
+        };
+        let page = page.trim();
+        if !page.is_empty() {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(page);
+        }
+    }
+
+    (!text.is_empty()).then_some(text)
+}
+
+/// Find every `stream ... endstream` body along with the dictionary before it.
+fn streams(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = find(&data[pos..], b"stream") {
+        let keyword = pos + offset;
+        let dict_start = rfind(&data[pos..keyword], b"obj").map_or(pos, |i| pos + i);
+        let dict = &data[dict_start..keyword];
+
+        let mut start = keyword + b"stream".len();
+        if data.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if data.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+
+        let Some(len) = find(&data[start..], b"endstream") else {
+            break;
+        };
+        found.push((dict, &data[start..start + len]));
+        pos = start + len + b"endstream".len();
+    }
+
+    found
+}
+
+/// Decode a stream's data, or `None` for filters that never hold text.
+fn stream_data<'a>(dict: &[u8], raw: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+    if contains(dict, b"/FlateDecode") {
+        inflate(raw).map(Cow::Owned)
+    } else if contains(dict, b"/Filter") {
+        // Other filters are used for images and fonts, not page content
+        None
+    } else {
+        Some(Cow::Borrowed(raw))
+    }
+}
+
+/// Bodies of the indirect objects by number, including those packed in object streams.
+fn objects(data: &[u8]) -> HashMap<u32, Cow<'_, [u8]>> {
+    let mut objects = HashMap::new();
+    let mut pos = 0;
+
+    while let Some(offset) = find(&data[pos..], b"obj") {
+        let keyword = pos + offset;
+        pos = keyword + b"obj".len();
+        // Skip "endobj" and words merely containing "obj"
+        if keyword == 0
+            || !is_whitespace(data[keyword - 1])
+            || data
+                .get(pos)
+                .is_some_and(|&c| !is_whitespace(c) && !is_delimiter(c))
+        {
+            continue;
+        }
+        let Some(number) = object_number(&data[..keyword]) else {
+            continue;
+        };
+        let end = find(&data[pos..], b"endobj").map_or(data.len(), |i| pos + i);
+        objects.insert(number, Cow::Borrowed(&data[pos..end]));
+        pos = end;
+    }
+
+    let packed: Vec<Vec<u8>> = objects
+        .values()
+        .filter(|body| contains(body, b"/ObjStm"))
+        .filter_map(|body| {
+            let (dict, raw) = streams(body).into_iter().next()?;
+            Some(stream_data(dict, raw)?.into_owned())
+        })
+        .collect();
+    for stream in packed {
+        let Some(first) = int_after(&tokens(&stream), b"/First") else {
+            continue;
+        };
+        let Some(header) = stream.get(..first) else {
+            continue;
+        };
+        // The header lists "number offset" pairs, offsets counted from `first`
+        let header: Vec<usize> = tokens(header)
+            .iter()
+            .filter_map(|t| std::str::from_utf8(t).ok()?.parse().ok())
+            .collect();
+        let entries: Vec<&[usize]> = header.chunks_exact(2).collect();
+        for (i, entry) in entries.iter().enumerate() {
+            let start = first.saturating_add(entry[1]);
+            let end = entries
+                .get(i + 1)
+                .map_or(stream.len(), |next| first.saturating_add(next[1]));
+            if let Some(body) = stream.get(start..end) {
+                objects
+                    .entry(entry[0] as u32)
+                    .or_insert_with(|| Cow::Owned(body.to_vec()));
+            }
+        }
+    }
+
+    objects
+}
+
+/// The object number of an `N G obj` header ending at the end of `before`.
+fn object_number(before: &[u8]) -> Option<u32> {
+    let mut fields = before
+        .rsplit(|&c| is_whitespace(c))
+        .filter(|field| !field.is_empty());
+    let generation = fields.next()?;
+    let number = fields.next()?;
+    if !generation.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    std::str::from_utf8(number).ok()?.parse().ok()
+}
+
+/// The encodings of the fonts named in resource dictionaries, by resource name.
+/// Names are assumed to mean the same font on every page.
+fn fonts(objects: &HashMap<u32, Cow<'_, [u8]>>) -> HashMap<Vec<u8>, FontEncoding> {
+    let mut fonts = HashMap::new();
+
+    for body in objects.values() {
+        let mut pos = 0;
+        while let Some(offset) = find(&body[pos..], b"/Font") {
+            pos += offset + b"/Font".len();
+            // Not /FontDescriptor, /FontFile and the like
+            if body.get(pos).is_some_and(|c| c.is_ascii_alphanumeric()) {
+                continue;
+            }
+
+            let rest = &body[pos..];
+            let after = rest
+                .iter()
+                .position(|&c| !is_whitespace(c))
+                .map_or(rest, |i| &rest[i..]);
+            let dict = if after.starts_with(b"<<") {
+                let end = find(after, b">>").unwrap_or(after.len());
+                &after[..end]
+            } else {
+                let Some(number) = reference(&tokens(after), 0) else {
+                    continue;
+                };
+                let Some(body) = objects.get(&number) else {
+                    continue;
+                };
+                body.as_ref()
+            };
+
+            let dict_tokens = tokens(dict);
+            for (i, name) in dict_tokens.iter().enumerate() {
+                if !name.starts_with(b"/") {
+                    continue;
+                }
+                let Some(font) = reference(&dict_tokens, i + 1).and_then(|n| objects.get(&n))
+                else {
+                    continue;
+                };
+                fonts
+                    .entry(name[1..].to_vec())
+                    .or_insert_with(|| font_encoding(font, objects));
+            }
+        }
+    }
+
+    fonts
+}
+
+fn font_encoding(font: &[u8], objects: &HashMap<u32, Cow<'_, [u8]>>) -> FontEncoding {
+    let composite = contains(font, b"/Type0");
+    let font_tokens = tokens(font);
+    let cmap = font_tokens
+        .iter()
+        .position(|t| *t == b"/ToUnicode")
+        .and_then(|i| reference(&font_tokens, i + 1))
+        .and_then(|number| objects.get(&number))
+        .and_then(|body| {
+            let (dict, raw) = streams(body).into_iter().next()?;
+            let data = stream_data(dict, raw)?;
+            parse_cmap(&data, if composite { 2 } else { 1 })
+        });
+
+    match cmap {
+        Some(cmap) => FontEncoding::ToUnicode(cmap),
+        None if composite => FontEncoding::Unreadable,
+        None => FontEncoding::Simple,
+    }
+}
+
+/// Parse the code space and the `bfchar`/`bfrange` mappings of a ToUnicode CMap.
+fn parse_cmap(data: &[u8], default_code_len: usize) -> Option<CMap> {
+    let tokens = tokens(data);
+    let mut code_len = None;
+    let mut map = HashMap::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match tokens[i] {
+            b"begincodespacerange" => {
+                if let Some(low) = tokens.get(i + 1).and_then(|t| hex_token(t)) {
+                    code_len = Some(low.len());
+                }
+            }
+            b"beginbfchar" => {
+                i += 1;
+                while let (Some(src), Some(dst)) = (
+                    tokens.get(i).and_then(|t| hex_token(t)),
+                    tokens.get(i + 1).and_then(|t| hex_token(t)),
+                ) {
+                    code_len.get_or_insert(src.len());
+                    map.insert(code_value(&src), utf16_be(&dst));
+                    i += 2;
+                }
+                continue;
+            }
+            b"beginbfrange" => {
+                i += 1;
+                while let (Some(low), Some(high)) = (
+                    tokens.get(i).and_then(|t| hex_token(t)),
+                    tokens.get(i + 1).and_then(|t| hex_token(t)),
+                ) {
+                    code_len.get_or_insert(low.len());
+                    let (low, high) = (code_value(&low), code_value(&high));
+                    i += 2;
+                    if tokens.get(i) == Some(&&b"["[..]) {
+                        // One destination per code
+                        i += 1;
+                        let mut code = low;
+                        while let Some(dst) = tokens.get(i).and_then(|t| hex_token(t)) {
+                            map.insert(code, utf16_be(&dst));
+                            code = code.wrapping_add(1);
+                            i += 1;
+                        }
+                        i += 1;
+                    } else if let Some(dst) = tokens.get(i).and_then(|t| hex_token(t)) {
+                        // Consecutive codes map to consecutive characters
+                        if high >= low && high - low <= MAX_CMAP_RANGE {
+                            let mut units: Vec<u16> = utf16_units(&dst);
+                            for code in low..=high {
+                                map.insert(code, String::from_utf16_lossy(&units));
+                                if let Some(last) = units.last_mut() {
+                                    *last = last.wrapping_add(1);
+                                }
+                            }
+                        }
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let code_len = code_len.unwrap_or(default_code_len);
+    (!map.is_empty() && (1..=4).contains(&code_len)).then_some(CMap { code_len, map })
+}
+
+/// Split dictionary or CMap syntax into names, words, hex strings and brackets.
+fn tokens(data: &[u8]) -> Vec<&[u8]> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < data.len() {
+        let start = i;
+        match data[i] {
+            c if is_whitespace(c) => {
+                i += 1;
+                continue;
+            }
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'<' | b'>' if data.get(i + 1) == Some(&data[i]) => i += 2,
+            b'<' => {
+                i = data[i..]
+                    .iter()
+                    .position(|&c| c == b'>')
+                    .map_or(data.len(), |p| i + p + 1);
+            }
+            b'(' => i = literal_string(data, i + 1).1,
+            b'[' | b']' | b'{' | b'}' | b')' | b'>' => i += 1,
+            _ => {
+                i += 1;
+                while i < data.len() && !is_whitespace(data[i]) && !is_delimiter(data[i]) {
+                    i += 1;
+                }
+            }
+        }
+        tokens.push(&data[start..i]);
+    }
+
+    tokens
+}
+
+/// The object number of an `N G R` reference starting at `tokens[i]`.
+fn reference(tokens: &[&[u8]], i: usize) -> Option<u32> {
+    match tokens.get(i..i + 3)? {
+        [number, _, b"R"] => std::str::from_utf8(number).ok()?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The integer following the dictionary key `key`.
+fn int_after(tokens: &[&[u8]], key: &[u8]) -> Option<usize> {
+    let i = tokens.iter().position(|t| *t == key)?;
+    std::str::from_utf8(tokens.get(i + 1)?).ok()?.parse().ok()
+}
+
+/// The bytes of a `<...>` hex string token.
+fn hex_token(token: &[u8]) -> Option<Vec<u8>> {
+    let inner = token.strip_prefix(b"<")?.strip_suffix(b">")?;
+    (!inner.starts_with(b"<")).then(|| hex_string(inner))
+}
+
+/// A character code's value from its big-endian bytes.
+fn code_value(code: &[u8]) -> u32 {
+    code.iter().fold(0, |value, &b| (value << 8) | u32::from(b))
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(&utf16_units(bytes))
+}
+
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let decompressor = gio::ZlibDecompressor::new(gio::ZlibCompressorFormat::Zlib);
+    let input = gio::MemoryInputStream::from_bytes(&glib::Bytes::from(data));
+    let mut reader = gio::ConverterInputStream::new(&input, &decompressor).into_read();
+
+    // Streams are often padded past the end of the compressed data; keep what decoded
+    let mut out = Vec::new();
+    let _ = reader.read_to_end(&mut out);
+    (!out.is_empty()).then_some(out)
+}
+
+/// Collect the strings shown by text operators in a content stream, or `None`
+/// if any are drawn with an unreadable font.
+fn content_text(content: &[u8], fonts: &HashMap<Vec<u8>, FontEncoding>) -> Option<String> {
+    let mut out = String::new();
+    let mut strings: Vec<Vec<u8>> = Vec::new();
+    let mut numbers: Vec<f64> = Vec::new();
+    let mut name: Option<&[u8]> = None;
+    let mut font = &FontEncoding::Simple;
+    let mut in_array = false;
+    let mut line_y: Option<f64> = None;
+    let mut i = 0;
+
+    while i < content.len() {
+        match content[i] {
+            b'(' => {
+                let (s, next) = literal_string(content, i + 1);
+                strings.push(s);
+                i = next;
+            }
+            b'<' if content.get(i + 1) == Some(&b'<') => i += 2,
+            b'<' => {
+                let end = content[i..]
+                    .iter()
+                    .position(|&c| c == b'>')
+                    .map_or(content.len(), |p| i + p);
+                strings.push(hex_string(&content[i + 1..end]));
+                i = end + 1;
+            }
+            b'[' => {
+                in_array = true;
+                i += 1;
+            }
+            b']' => {
+                in_array = false;
+                i += 1;
+            }
+            b'%' => {
+                while i < content.len() && content[i] != b'\n' && content[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'/' => {
+                let start = i + 1;
+                i = start;
+                while i < content.len() && !is_whitespace(content[i]) && !is_delimiter(content[i]) {
+                    i += 1;
+                }
+                name = Some(&content[start..i]);
+            }
+            c if is_whitespace(c) || is_delimiter(c) => i += 1,
+            _ => {
+                let start = i;
+                while i < content.len() && !is_whitespace(content[i]) && !is_delimiter(content[i]) {
+                    i += 1;
+                }
+                let token = &content[start..i];
+
+                if let Some(n) = std::str::from_utf8(token)
+                    .ok()
+                    .and_then(|t| t.parse::<f64>().ok())
+                {
+                    // Wide negative kerning inside a TJ array stands in for a space
+                    if in_array && n <= -200.0 {
+                        strings.push(b" ".to_vec());
+                    }
+                    numbers.push(n);
+                    continue;
+                }
+
+                match token {
+                    b"Tf" => {
+                        font = name
+                            .and_then(|name| fonts.get(name))
+                            .unwrap_or(&FontEncoding::Simple);
+                    }
+                    b"Tj" | b"TJ" | b"'" | b"\"" => {
+                        if matches!(token, b"'" | b"\"") {
+                            new_line(&mut out);
+                        }
+                        for s in &strings {
+                            // Kerning spaces are inserted as text, not font codes
+                            match font {
+                                _ if s == b" " => out.push(' '),
+                                FontEncoding::Simple => out.push_str(&decode_string(s)),
+                                FontEncoding::ToUnicode(cmap) => out.push_str(&cmap.decode(s)),
+                                FontEncoding::Unreadable => return None,
+                            }
+                        }
+                    }
+                    b"T*" | b"ET" => new_line(&mut out),
+                    b"Td" | b"TD" => {
+                        if numbers.len() >= 2 && numbers[numbers.len() - 1] != 0.0 {
+                            new_line(&mut out);
+                        } else {
+                            space(&mut out);
+                        }
+                    }
+                    b"Tm" => {
+                        if let Some(&y) = numbers.last() {
+                            if line_y.is_some_and(|prev| (prev - y).abs() > 1.0) {
+                                new_line(&mut out);
+                            } else {
+                                space(&mut out);
+                            }
+                            line_y = Some(y);
+                        }
+                    }
+                    _ => {}
+                }
+                strings.clear();
+                numbers.clear();
+                name = None;
+            }
+        }
+    }
+
+    Some(
+        out.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+/// Parse a `( ... )` string starting after the opening parenthesis.
+/// Returns the bytes and the index just past the closing parenthesis.
+fn literal_string(content: &[u8], mut i: usize) -> (Vec<u8>, usize) {
+    let mut out = Vec::new();
+    let mut depth = 0;
+
+    while i < content.len() {
+        let c = content[i];
+        i += 1;
+        match c {
+            b'\\' => {
+                let Some(&next) = content.get(i) else {
+                    break;
+                };
+                i += 1;
+                match next {
+                    b'n' => out.push(b'\n'),
+                    b'r' => out.push(b'\r'),
+                    b't' => out.push(b'\t'),
+                    b'b' | b'f' => {}
+                    b'0'..=b'7' => {
+                        let mut value = u32::from(next - b'0');
+                        for _ in 0..2 {
+                            match content.get(i) {
+                                Some(&d @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(d - b'0');
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        out.push(value as u8);
+                    }
+                    // Backslash at end of line continues the string
+                    b'\r' => {
+                        if content.get(i) == Some(&b'\n') {
+                            i += 1;
+                        }
+                    }
+                    b'\n' => {}
+                    other => out.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                out.push(c);
+            }
+            b')' if depth == 0 => break,
+            b')' => {
+                depth -= 1;
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+
+    (out, i)
+}
+
+fn hex_string(hex: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = hex
+        .iter()
+        .filter_map(|&c| (c as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Decode a PDF string: UTF-16BE with a byte order mark, otherwise Latin-1.
+fn decode_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return utf16_be(utf16);
+    }
+
+    bytes
+        .iter()
+        .filter(|&&b| b >= 0x20 || b == b'\n' || b == b'\t')
+        .map(|&b| char::from(b))
+        .collect()
+}
+
+fn new_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn space(out: &mut String) {
+    if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+        out.push(' ');
+    }
+}
+
+fn is_whitespace(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\n' | b'\r' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(c: u8) -> bool {
+    matches!(
+        c,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_text_lines() {
+        let content = b"BT /F1 12 Tf 72 720 Td (Hello) Tj ( world) Tj 0 -14 Td (Second line) Tj ET";
+        assert_eq!(
+            content_text(content, &HashMap::new()).as_deref(),
+            Some("Hello world\nSecond line")
+        );
+    }
+
+    #[test]
+    fn test_content_text_tj_array_and_escapes() {
+        let content = b"BT [(Ke) 20 (rn) -400 (ed)] TJ T* (a\\(b\\) \\101) Tj ET";
+        assert_eq!(
+            content_text(content, &HashMap::new()).as_deref(),
+            Some("Kern ed\na(b) A")
+        );
+    }
+
+    #[test]
+    fn test_extract_uncompressed_pdf() {
+        let pdf = b"%PDF-1.4\n\
+            1 0 obj << /Type /Catalog >> endobj\n\
+            4 0 obj << /Length 44 >>\nstream\nBT /F1 24 Tf 100 700 Td (Hello PDF) Tj ET\nendstream\nendobj\n\
+            5 0 obj << /Subtype /Image /Length 3 >>\nstream\n(x) Tj\nendstream\nendobj\n\
+            %%EOF";
+        assert_eq!(extract_text(pdf).as_deref(), Some("Hello PDF"));
+    }
+
+    /// A page drawing "HI!" with an Identity-H font, as glyph IDs 1 to 3.
+    fn cid_font_pdf(to_unicode: &str) -> Vec<u8> {
+        let cmap = "/CIDInit /ProcSet findresource begin 12 dict begin begincmap\n\
+            /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+            1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+            1 beginbfchar <0003> <0021> endbfchar\n\
+            1 beginbfrange <0001> <0002> <0048> endbfrange\n\
+            endcmap CMapName currentdict /CMap defineresource pop end end";
+        format!(
+            "%PDF-1.4\n\
+            1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n\
+            3 0 obj << /Type /Page /Resources << /Font << /F1 5 0 R >> >> /Contents 4 0 R >> endobj\n\
+            4 0 obj << /Length 48 >>\nstream\nBT /F1 24 Tf 100 700 Td <000100020003> Tj ET\nendstream\nendobj\n\
+            5 0 obj << /Type /Font /Subtype /Type0 /BaseFont /Noto /Encoding /Identity-H{} >> endobj\n\
+            6 0 obj << /Length {} >>\nstream\n{}\nendstream\nendobj\n\
+            %%EOF",
+            to_unicode,
+            cmap.len(),
+            cmap
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_extract_cid_font_through_to_unicode() {
+        let pdf = cid_font_pdf(" /ToUnicode 6 0 R");
+        assert_eq!(extract_text(&pdf).as_deref(), Some("HI!"));
+    }
+
+    #[test]
+    fn test_cid_font_without_to_unicode_is_refused() {
+        let pdf = cid_font_pdf("");
+        assert_eq!(extract_text(&pdf), None);
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::models::PromptPreset;
+
+/// File formats for sharing the system prompt library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryFormat {
+    /// One JSON file holding an array of `{"name", "content"}` objects.
+    Json,
+    /// A folder with one Markdown file per preset, named by its `# Name` heading.
+    Markdown,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PresetEntry {
+    name: String,
+    content: String,
+}
+
+/// Write the presets to `path`: a file for JSON, a folder for Markdown.
+/// Returns how many presets were written.
+pub fn export(presets: &[PromptPreset], path: &Path, format: LibraryFormat) -> Result<usize> {
+    match format {
+        LibraryFormat::Json => {
+            std::fs::write(path, to_json(presets)?)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        LibraryFormat::Markdown => {
+            std::fs::create_dir_all(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            let mut used = HashSet::new();
+            for preset in presets {
+                let file = path.join(format!("{}.md", file_stem(&preset.name, &mut used)));
+                std::fs::write(&file, to_markdown(preset))
+                    .with_context(|| format!("Failed to write {}", file.display()))?;
+            }
+        }
+    }
+    Ok(presets.len())
+}
+
+/// Read `(name, content)` presets from `path`, skipping any without a name or content.
+pub fn import(path: &Path, format: LibraryFormat) -> Result<Vec<(String, String)>> {
+    let presets = match format {
+        LibraryFormat::Json => {
+            let json = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            from_json(&json)?
+        }
+        LibraryFormat::Markdown => {
+            let mut files = std::fs::read_dir(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+                .collect::<Vec<_>>();
+            files.sort();
+
+            let mut presets = Vec::new();
+            for file in files {
+                let text = std::fs::read_to_string(&file)
+                    .with_context(|| format!("Failed to read {}", file.display()))?;
+                let stem = file
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                presets.push(from_markdown(&stem, &text));
+            }
+            presets
+        }
+    };
+
+    Ok(presets
+        .into_iter()
+        .map(|(name, content)| (name.trim().to_string(), content.trim().to_string()))
+        .filter(|(name, content)| !name.is_empty() && !content.is_empty())
+        .collect())
+}
+
+fn to_json(presets: &[PromptPreset]) -> Result<String> {
+    let entries: Vec<PresetEntry> = presets
+        .iter()
+        .map(|p| PresetEntry {
+            name: p.name.clone(),
+            content: p.content.clone(),
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&entries)?)
+}
+
+fn from_json(json: &str) -> Result<Vec<(String, String)>> {
+    let entries: Vec<PresetEntry> =
+        serde_json::from_str(json).context("Not a system prompt library file")?;
+    Ok(entries.into_iter().map(|e| (e.name, e.content)).collect())
+}
+
+fn to_markdown(preset: &PromptPreset) -> String {
+    format!("# {}\n\n{}\n", preset.name, preset.content.trim_end())
+}
+
+/// Parse a Markdown preset. A leading `# ` heading is the name; without one the
+/// file name is used and the whole text is the content.
+fn from_markdown(file_stem: &str, text: &str) -> (String, String) {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    match text.split_once('\n') {
+        Some((first, rest)) if first.starts_with("# ") => {
+            (first[2..].trim().to_string(), rest.trim().to_string())
+        }
+        None if text.starts_with("# ") => (text[2..].trim().to_string(), String::new()),
+        _ => (file_stem.to_string(), text.trim().to_string()),
+    }
+}
+
+/// A file name for a preset, unique among `used`.
+fn file_stem(name: &str, used: &mut HashSet<String>) -> String {
+    let base: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_string();
+    let base = if base.is_empty() {
+        "prompt".to_string()
+    } else {
+        base
+    };
+
+    let mut stem = base.clone();
+    let mut n = 2;
+    while !used.insert(stem.to_lowercase()) {
+        stem = format!("{} ({})", base, n);
+        n += 1;
+    }
+    stem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn preset(name: &str, content: &str) -> PromptPreset {
+        PromptPreset {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_from_markdown() {
+        assert_eq!(
+            from_markdown("file", "# Reviewer\n\nReview the code.\n"),
+            ("Reviewer".to_string(), "Review the code.".to_string())
+        );
+        assert_eq!(
+            from_markdown("Translator", "Translate to French.\n"),
+            ("Translator".to_string(), "Translate to French.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let presets = vec![
+            preset("SQL / Helper", "Write SQLite.\n\n- Be brief"),
+            preset("sql _ helper", "Clashes with the file name above."),
+        ];
+        let dir = std::env::temp_dir().join(format!("echo-prompts-{}", uuid::Uuid::new_v4()));
+        let expected: Vec<(String, String)> = presets
+            .iter()
+            .map(|p| (p.name.clone(), p.content.clone()))
+            .collect();
+
+        let json_path = dir.join("library.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(
+            export(&presets, &json_path, LibraryFormat::Json).unwrap(),
+            2
+        );
+        assert_eq!(import(&json_path, LibraryFormat::Json).unwrap(), expected);
+
+        let md_dir = dir.join("markdown");
+        assert_eq!(
+            export(&presets, &md_dir, LibraryFormat::Markdown).unwrap(),
+            2
+        );
+        let mut imported = import(&md_dir, LibraryFormat::Markdown).unwrap();
+        imported.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(imported, expected);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -14,6 +14,31 @@ pub struct AppSettings {
     pub message_spacing: MessageSpacing,
     #[serde(default)]
     pub default_system_prompt: Option<String>,
+    #[serde(default)]
+    pub enable_tools: bool,
+    /// Tokens models may spend on extended thinking, 0 turning it off where the model
+    /// allows. `None` leaves it to each model.
+    #[serde(default)]
+    pub thinking_budget: Option<u32>,
+    /// Models tried in order when a request fails with a network, rate limit or server error.
+    #[serde(default)]
+    pub fallback_chain: Vec<FallbackTarget>,
+    /// Ask a model for a short title after a conversation's first reply.
+    #[serde(default)]
+    pub auto_title: bool,
+    /// Model that writes titles; `None` uses the conversation's own model.
+    #[serde(default)]
+    pub title_model: Option<FallbackTarget>,
+    /// Download images from the web that replies link to, without asking first.
+    #[serde(default)]
+    pub load_remote_images: bool,
+}
+
+/// A model on a specific account, used for fallbacks and titles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FallbackTarget {
+    pub account_id: String,
+    pub model: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -41,6 +66,12 @@ impl Default for AppSettings {
             code_font_size: 13,
             message_spacing: MessageSpacing::Comfortable,
             default_system_prompt: None,
+            enable_tools: false,
+            thinking_budget: None,
+            fallback_chain: Vec::new(),
+            auto_title: false,
+            title_model: None,
+            load_remote_images: false,
         }
     }
 }
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+/// Variable prefilled with the clipboard text when the template form opens.
+pub const CLIPBOARD_VARIABLE: &str = "clipboard";
+
+/// The `{{variable}}` names in a template, in order of first appearance.
+pub fn template_variables(content: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    substitute(content, |name| {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        None
+    });
+    names
+}
+
+/// Replace each `{{variable}}` with its value. Variables without a value are left as-is.
+pub fn fill_template(content: &str, values: &HashMap<String, String>) -> String {
+    substitute(content, |name| values.get(name).cloned())
+}
+
+/// Rebuild `content`, replacing each placeholder with `replace(name)` when that is `Some`.
+fn substitute(content: &str, mut replace: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let placeholder = &rest[start..start + 2 + end + 2];
+        let name = after[..end].trim();
+        match valid_name(name).then(|| replace(name)).flatten() {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(placeholder),
+        }
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Names are words of letters, digits, `_`, `-` and spaces, so stray braces in code
+/// samples aren't taken for placeholders.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ' '))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_variables() {
+        assert_eq!(
+            template_variables("Translate {{ clipboard }} to {{language}}, not {{language}}."),
+            vec!["clipboard", "language"]
+        );
+        // Not placeholders: empty, code, unterminated
+        assert!(template_variables("{{}} {{a.b}} fn f() {{ x }").is_empty());
+    }
+
+    #[test]
+    fn test_fill_template() {
+        let values = HashMap::from([("language".to_string(), "French".to_string())]);
+        assert_eq!(
+            fill_template("Into {{language}}: {{text}} {{", &values),
+            "Into French: {{text}} {{"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use crate::models::{Account, ProviderId, Role};
+use crate::providers::retry::{self, RetryPolicy};
+use crate::providers::{ChatMessage, ChatRequest, ProviderRouter};
+use crate::services::conversation::truncate_title;
+
+/// Longest excerpt of each message sent along; the opening is enough to name a chat.
+const MAX_EXCERPT_CHARS: usize = 2000;
+
+const TITLE_PROMPT: &str = "Write a short title (at most six words) for the conversation below. \
+Reply with the title only: no quotes, no trailing punctuation.";
+
+/// A small request asking `model` to name a conversation from its first exchange.
+///
+/// Thinking is turned off and output left uncapped: reasoning counts against the
+/// output limit on Gemini and OpenAI, and a tight cap would be spent before the title.
+pub fn title_request(
+    api_key: String,
+    account: &Account,
+    model: &str,
+    user_message: &str,
+    assistant_reply: &str,
+) -> ChatRequest {
+    let prompt = format!(
+        "{}\n\nUser: {}\n\nAssistant: {}",
+        TITLE_PROMPT,
+        excerpt(user_message),
+        excerpt(assistant_reply)
+    );
+
+    ChatRequest {
+        api_key,
+        model: model.to_string(),
+        messages: vec![ChatMessage::new(Role::User, prompt)],
+        base_url: account.api_base_url.clone(),
+        temperature: Some(0.3),
+        system_prompt: None,
+        max_tokens: None,
+        top_p: None,
+        top_k: None,
+        stop_sequences: Vec::new(),
+        seed: None,
+        tools: Vec::new(),
+        thinking_budget: Some(0),
+    }
+}
+
+/// Send a title request and tidy the reply into a title.
+pub async fn generate_title(
+    router: Arc<ProviderRouter>,
+    provider: ProviderId,
+    request: ChatRequest,
+) -> Result<String, String> {
+    // A missing title isn't worth waiting on, so only retry briefly
+    let policy = RetryPolicy {
+        max_retries: 1,
+        ..Default::default()
+    };
+    let response = retry::send_message(&router, &provider, request, &policy, |_| {})
+        .await
+        .map_err(|e| e.to_string())?;
+    clean_title(&response.content).ok_or_else(|| "Empty title".to_string())
+}
+
+/// The title in a model's reply, without the quotes, labels and Markdown models
+/// tend to add.
+pub fn clean_title(reply: &str) -> Option<String> {
+    let strip = |s: &str| {
+        s.trim()
+            .trim_matches(|c: char| matches!(c, '"' | '\'' | '`' | '*' | '#' | '“' | '”'))
+            .trim()
+            .to_string()
+    };
+    let line = strip(reply.lines().map(str::trim).find(|l| !l.is_empty())?);
+    let line = match line.get(..6) {
+        Some(label) if label.eq_ignore_ascii_case("title:") => strip(&line[6..]),
+        _ => line,
+    };
+    let title = line.trim_end_matches(['.', '!', ':']).trim();
+    (!title.is_empty()).then(|| truncate_title(title))
+}
+
+fn excerpt(text: &str) -> &str {
+    match text.char_indices().nth(MAX_EXCERPT_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AccountStatus;
+
+    #[test]
+    fn test_title_request_disables_thinking() {
+        let now = chrono::Utc::now();
+        let account = Account {
+            id: "account".to_string(),
+            provider: ProviderId::Gemini,
+            label: "Gemini".to_string(),
+            api_base_url: None,
+            default_model: "gemini-2.5-flash".to_string(),
+            is_default: true,
+            status: AccountStatus::Active,
+            total_tokens_in: 0,
+            total_tokens_out: 0,
+            created_at: now,
+            updated_at: now,
+        };
+        let request = title_request(
+            String::new(),
+            &account,
+            "gemini-2.5-flash",
+            "How do lifetimes work?",
+            "Lifetimes describe how long references are valid.",
+        );
+        assert_eq!(request.thinking_budget, Some(0));
+        assert_eq!(request.max_tokens, None);
+    }
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(
+            clean_title("\n**Title: \"Rust Lifetimes Explained.\"**\n"),
+            Some("Rust Lifetimes Explained".to_string())
+        );
+        assert_eq!(
+            clean_title("Sourdough starter tips"),
+            Some("Sourdough starter tips".to_string())
+        );
+        assert_eq!(clean_title("  \n\"\"\n"), None);
+    }
+}
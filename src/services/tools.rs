@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::providers::{ToolCall, ToolDefinition, ToolResult};
+
+/// A function the app exposes to models during a chat.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    fn definition(&self) -> ToolDefinition;
+
+    /// Run the tool. The `Err` text is reported back to the model as a failed call.
+    async fn call(&self, arguments: Value) -> Result<String, String>;
+}
+
+/// Registry of the tools offered to models, keyed by tool name.
+pub struct ToolRegistry {
+    tools: HashMap<String, Arc<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self {
+            tools: HashMap::new(),
+        }
+    }
+
+    /// A registry holding the built-in tools.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(Arc::new(CurrentDateTimeTool));
+        registry
+    }
+
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.insert(tool.definition().name, tool);
+    }
+
+    /// Definitions of all registered tools, sorted by name for stable requests.
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions: Vec<ToolDefinition> =
+            self.tools.values().map(|t| t.definition()).collect();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+
+    /// Run a tool call, turning unknown tools and failures into error results.
+    pub async fn execute(&self, call: &ToolCall) -> ToolResult {
+        let outcome = match self.tools.get(&call.name) {
+            Some(tool) => tool.call(call.arguments.clone()).await,
+            None => Err(format!("Unknown tool: {}", call.name)),
+        };
+
+        if let Err(e) = &outcome {
+            tracing::warn!("Tool call {} failed: {}", call.name, e);
+        }
+
+        let is_error = outcome.is_err();
+        ToolResult {
+            tool_call_id: call.id.clone(),
+            name: call.name.clone(),
+            content: outcome.unwrap_or_else(|e| e),
+            is_error,
+        }
+    }
+}
+
+/// Reports the user's local date, time and UTC offset.
+struct CurrentDateTimeTool;
+
+#[async_trait]
+impl Tool for CurrentDateTimeTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "get_current_datetime".to_string(),
+            description: "Get the current local date, time and UTC offset of the user.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {},
+            }),
+        }
+    }
+
+    async fn call(&self, _arguments: Value) -> Result<String, String> {
+        let now = chrono::Local::now();
+        Ok(now.format("%A, %Y-%m-%d %H:%M:%S (UTC%:z)").to_string())
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use gtk::prelude::*;
 use relm4::prelude::*;
 
-use crate::models::Account;
+use crate::models::{Account, ProviderId};
 
 pub struct AccountSelector {
     accounts: Vec<Account>,
@@ -23,10 +23,11 @@ pub enum AccountSelectorMsg {
     SetAccounts(Vec<Account>),
     AccountChanged(u32),
     ModelChanged(u32),
+    SelectModel(String),
+    SelectAccountModel(String, String), // (account_id, model)
     SyncToConversation(String, String), // (account_id, model)
     FinishSync,
     SetLocalModels(String, Vec<String>), // (account_id, model_ids)
-    GrabFocus,
 }
 
 #[derive(Debug)]
@@ -166,6 +167,23 @@ impl Component for AccountSelector {
                     ));
                 }
             }
+            AccountSelectorMsg::SelectModel(model) => {
+                self.select_model(model, &sender);
+            }
+            AccountSelectorMsg::SelectAccountModel(account_id, model) => {
+                let Some(acc_idx) = self.accounts.iter().position(|a| a.id == account_id) else {
+                    return;
+                };
+                if self.selected_account_index != Some(acc_idx) {
+                    self.updating = true;
+                    self.selected_account_index = Some(acc_idx);
+                    self.sync_account_dropdown(acc_idx);
+                    self.update_models_for_account(acc_idx);
+                    sender.input(AccountSelectorMsg::FinishSync);
+                    let _ = sender.output(AccountSelectorOutput::AccountSelected(account_id));
+                }
+                self.select_model(model, &sender);
+            }
             AccountSelectorMsg::SyncToConversation(account_id, model) => {
                 self.updating = true;
                 // Find account by ID
@@ -184,9 +202,6 @@ impl Component for AccountSelector {
             AccountSelectorMsg::FinishSync => {
                 self.updating = false;
             }
-            AccountSelectorMsg::GrabFocus => {
-                self.account_dropdown.grab_focus();
-            }
             AccountSelectorMsg::SetLocalModels(account_id, models) => {
                 self.local_models.insert(account_id.clone(), models);
                 // If this is the currently selected account, refresh the model dropdown
@@ -216,31 +231,47 @@ impl Component for AccountSelector {
 }
 
 impl AccountSelector {
+    /// Select `model` for the current account and announce it.
+    fn select_model(&mut self, model: String, sender: &ComponentSender<Self>) {
+        if self.selected_account_index.is_none() {
+            return;
+        }
+        // Models the account doesn't list yet are added, so new model IDs work
+        let index = match self
+            .models
+            .iter()
+            .position(|m| m.eq_ignore_ascii_case(&model))
+        {
+            Some(index) => index,
+            None => {
+                self.models.push(model);
+                self.models.len() - 1
+            }
+        };
+        self.updating = true;
+        self.selected_model_index = Some(index);
+        self.sync_model_dropdown(index);
+        sender.input(AccountSelectorMsg::FinishSync);
+        let _ = sender.output(AccountSelectorOutput::ModelSelected(
+            self.models[index].clone(),
+        ));
+    }
+
     fn update_models_for_account(&mut self, account_index: usize) {
         if let Some(account) = self.accounts.get(account_index) {
             // Use the default model and well-known models for the provider
             self.models = vec![account.default_model.clone()];
             // Add other known models if they're different
             let known = match account.provider {
-                crate::models::ProviderId::Gemini => {
-                    vec![
-                        "gemini-2.5-pro".to_string(),
-                        "gemini-2.5-flash".to_string(),
-                        "gemini-2.0-flash".to_string(),
-                    ]
-                }
-                crate::models::ProviderId::Claude => {
-                    vec![
-                        "claude-sonnet-4-20250514".to_string(),
-                        "claude-opus-4-20250514".to_string(),
-                        "claude-haiku-3-5-20241022".to_string(),
-                    ]
-                }
-                crate::models::ProviderId::Local => self
+                ProviderId::Local => self
                     .local_models
                     .get(&account.id)
                     .cloned()
                     .unwrap_or_default(),
+                provider => known_models(provider)
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect(),
             };
             for m in known {
                 if !self.models.contains(&m) {
@@ -265,3 +296,18 @@ impl AccountSelector {
         self.model_dropdown.set_selected(selected as u32);
     }
 }
+
+/// Well-known models offered for a provider besides the account's default.
+/// Local models are discovered from the server instead.
+pub fn known_models(provider: ProviderId) -> &'static [&'static str] {
+    match provider {
+        ProviderId::Gemini => &["gemini-2.5-pro", "gemini-2.5-flash", "gemini-2.0-flash"],
+        ProviderId::Claude => &[
+            "claude-sonnet-4-20250514",
+            "claude-opus-4-20250514",
+            "claude-haiku-3-5-20241022",
+        ],
+        ProviderId::OpenAi => &["gpt-4o", "gpt-4o-mini", "gpt-4.1", "o3-mini"],
+        ProviderId::Local => &[],
+    }
+}
@@ -1,14 +1,14 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use gtk::prelude::*;
 use relm4::factory::FactoryVecDeque;
 use relm4::prelude::*;
 
-use crate::models::Message;
+use crate::models::{BranchPosition, Message, PromptTemplate};
 use std::path::PathBuf;
 
-use crate::providers::ImageAttachment;
+use crate::providers::{DocumentAttachment, ImageAttachment, RetryNotice};
 use crate::ui::input_area::{InputArea, InputAreaMsg, InputAreaOutput};
 use crate::ui::message_widget::{
     MessageWidget, MessageWidgetInit, MessageWidgetMsg, MessageWidgetOutput,
@@ -18,6 +18,11 @@ pub struct ChatView {
     messages: FactoryVecDeque<MessageWidget>,
     input_area: Controller<InputArea>,
     loading: bool,
+    status_label: gtk::Label,
+    // Countdown to an automatic retry: the notice and seconds left
+    retry_countdown: Option<(RetryNotice, u64)>,
+    // Bumped to stop the running countdown timer
+    retry_timer_generation: Rc<Cell<u32>>,
     scrolled_window: gtk::ScrolledWindow,
     // Streaming state
     streaming_message_id: Option<String>,
@@ -36,8 +41,8 @@ pub struct ChatView {
 
 struct StreamBuffer {
     message_id: String,
-    accumulated_text: String,
-    needs_render: bool,
+    // Text received since the last render
+    pending_text: String,
 }
 
 #[derive(Debug)]
@@ -46,25 +51,42 @@ pub enum ChatViewMsg {
     LoadMessages(Vec<Message>),
     Clear,
     SetLoading(bool),
+    ShowRetry(RetryNotice),
     ScrollToBottom,
-    UserSendMessage(String, Vec<ImageAttachment>),
+    ScrollToMessage(String), // message_id
+    UserSendMessage(String, Vec<ImageAttachment>, Vec<DocumentAttachment>),
     // Streaming
     AddStreamingMessage(Message),
-    UpdateStreamingMessage(String, String), // (message_id, full_text)
-    StreamingComplete(String),              // message_id
-    RemoveMessage(String),                  // message_id (on error)
+    AppendStreamingText(String, String), // (message_id, delta)
+    AppendStreamingThinking(String, String), // (message_id, delta)
+    StreamingComplete(String),           // message_id
+    SetStreamingModel(String, String),   // message_id, fallback model
+    SetStreamingThinking(String, String), // message_id, thinking so far
+    RemoveMessage(String),               // message_id (on error)
     StopGeneration,
     // Internal
     RenderBuffered,
+    RetryTick,
     ScrollPositionChanged,
     // Forwarded from MessageWidget
     ForwardRegenerate(String),          // message_id
     ForwardEditMessage(String, String), // message_id, new_content
+    ForwardSwitchBranch(String, usize), // message_id, sibling index
+    ForwardFork(String),                // message_id
     CopyToClipboard(String),
+    // Templates
+    SetTemplates(Vec<PromptTemplate>),
+    ForwardSaveTemplate(String, String), // name, content
+    ForwardDeleteTemplate(String),       // template_id
+    InsertTemplate(PromptTemplate),
+    ForwardCommand(String),
+    ForwardAttachFailed(String),
     // Drag-and-drop
-    ImageDropped(PathBuf),
+    FileDropped(PathBuf),
+    SetVisionSupported(bool),
     // Tokens
-    SetMessageTokens(String, Option<i64>, Option<i64>), // message_id, tokens_in, tokens_out
+    SetMessageTokens(String, Option<i64>, Option<i64>, Option<i64>), // message_id, in, out, thinking
+    SetMessageBranch(String, BranchPosition),
     // Search
     ToggleSearch,
     SearchInConversation(String),
@@ -77,10 +99,17 @@ pub enum ChatViewOutput {
     SendMessage {
         text: String,
         images: Vec<ImageAttachment>,
+        documents: Vec<DocumentAttachment>,
     },
     StopGeneration,
-    RegenerateMessage(String),   // message_id
-    EditMessage(String, String), // message_id, new_content
+    RegenerateMessage(String),    // message_id
+    EditMessage(String, String),  // message_id, new_content
+    SwitchBranch(String, usize),  // message_id, sibling index
+    ForkFrom(String),             // message_id
+    SaveTemplate(String, String), // name, content
+    DeleteTemplate(String),       // template_id
+    RunCommand(String),
+    AttachFailed(String),
 }
 
 #[relm4::component(pub)]
@@ -175,14 +204,26 @@ impl Component for ChatView {
                 MessageWidgetOutput::CopyFullContent(content) => {
                     ChatViewMsg::CopyToClipboard(content)
                 }
+                MessageWidgetOutput::SwitchBranch(msg_id, index) => {
+                    ChatViewMsg::ForwardSwitchBranch(msg_id, index)
+                }
+                MessageWidgetOutput::Fork(msg_id) => ChatViewMsg::ForwardFork(msg_id),
             });
 
         let input_area = InputArea::builder()
             .launch(())
             .forward(sender.input_sender(), |output| match output {
-                InputAreaOutput::SendMessage { text, images } => {
-                    ChatViewMsg::UserSendMessage(text, images)
+                InputAreaOutput::SendMessage {
+                    text,
+                    images,
+                    documents,
+                } => ChatViewMsg::UserSendMessage(text, images, documents),
+                InputAreaOutput::SaveTemplate(name, content) => {
+                    ChatViewMsg::ForwardSaveTemplate(name, content)
                 }
+                InputAreaOutput::DeleteTemplate(id) => ChatViewMsg::ForwardDeleteTemplate(id),
+                InputAreaOutput::RunCommand(text) => ChatViewMsg::ForwardCommand(text),
+                InputAreaOutput::AttachFailed(reason) => ChatViewMsg::ForwardAttachFailed(reason),
             });
 
         let scrolled_window = gtk::ScrolledWindow::new();
@@ -193,9 +234,9 @@ impl Component for ChatView {
         let spinner = gtk::Spinner::builder().spinning(true).build();
         loading_box.append(&spinner);
 
-        let thinking_label = gtk::Label::builder().label("Generating...").build();
-        thinking_label.add_css_class("dim-label");
-        loading_box.append(&thinking_label);
+        let status_label = gtk::Label::builder().label("Generating...").build();
+        status_label.add_css_class("dim-label");
+        loading_box.append(&status_label);
 
         let stop_btn = gtk::Button::builder()
             .label("Stop")
@@ -213,6 +254,9 @@ impl Component for ChatView {
             messages,
             input_area,
             loading: false,
+            status_label,
+            retry_countdown: None,
+            retry_timer_generation: Rc::new(Cell::new(0)),
             scrolled_window: scrolled_window.clone(),
             streaming_message_id: None,
             streaming_buffer: Rc::new(RefCell::new(None)),
@@ -279,7 +323,7 @@ impl Component for ChatView {
         });
         search_entry.add_controller(key_ctrl);
 
-        // Set up drag-and-drop for images
+        // Set up drag-and-drop for attachments
         let drop_target =
             gtk::DropTarget::new(gio::File::static_type(), gtk::gdk::DragAction::COPY);
         let root_ref = root.clone();
@@ -287,18 +331,10 @@ impl Component for ChatView {
         drop_target.connect_drop(move |_, value, _, _| {
             if let Ok(file) = value.get::<gio::File>() {
                 if let Some(path) = file.path() {
-                    // Check if it's an image
-                    let is_image = path
-                        .extension()
-                        .and_then(|e| e.to_str())
-                        .is_some_and(|ext| {
-                            matches!(
-                                ext.to_lowercase().as_str(),
-                                "png" | "jpg" | "jpeg" | "gif" | "webp"
-                            )
-                        });
-                    if is_image {
-                        sender_drop.send(ChatViewMsg::ImageDropped(path)).unwrap();
+                    // Check if it's a supported attachment
+                    let is_supported = crate::ui::input_area::is_attachable(&path);
+                    if is_supported {
+                        sender_drop.send(ChatViewMsg::FileDropped(path)).unwrap();
                         return true;
                     }
                 }
@@ -381,6 +417,36 @@ impl Component for ChatView {
             ChatViewMsg::SetLoading(loading) => {
                 self.loading = loading;
                 self.input_area.emit(InputAreaMsg::SetSending(loading));
+                if !loading {
+                    self.stop_retry_countdown();
+                }
+            }
+            ChatViewMsg::ShowRetry(notice) => {
+                self.retry_countdown = Some((notice, notice.delay_secs));
+                self.update_status_label();
+
+                // Tick the countdown once a second, replacing any earlier timer
+                let generation = self.retry_timer_generation.get().wrapping_add(1);
+                self.retry_timer_generation.set(generation);
+                let current_generation = self.retry_timer_generation.clone();
+                let sender_timer = sender.input_sender().clone();
+                glib::timeout_add_seconds_local(1, move || {
+                    if current_generation.get() != generation {
+                        return glib::ControlFlow::Break;
+                    }
+                    sender_timer.send(ChatViewMsg::RetryTick).unwrap();
+                    glib::ControlFlow::Continue
+                });
+            }
+            ChatViewMsg::RetryTick => {
+                if let Some((_, seconds_left)) = self.retry_countdown.as_mut() {
+                    *seconds_left = seconds_left.saturating_sub(1);
+                    if *seconds_left == 0 {
+                        self.stop_retry_countdown();
+                        return;
+                    }
+                }
+                self.update_status_label();
             }
             ChatViewMsg::ScrollToBottom => {
                 self.user_scrolled_up = false;
@@ -389,13 +455,49 @@ impl Component for ChatView {
                     adj.set_value(adj.upper());
                 });
             }
+            ChatViewMsg::ScrollToMessage(message_id) => {
+                let guard = self.messages.guard();
+                let pos = guard.iter().position(|m| m.message.id == message_id);
+                drop(guard);
+                let Some(pos) = pos else {
+                    return;
+                };
+
+                // Wait for freshly loaded messages to be laid out, and for the
+                // scroll-to-bottom that loading queues, before scrolling to the target
+                self.user_scrolled_up = true;
+                let message_list = self.messages.widget().clone();
+                let adj = self.scrolled_window.vadjustment();
+                glib::timeout_add_local_once(std::time::Duration::from_millis(150), move || {
+                    let Some(widget) = message_list.observe_children().item(pos as u32) else {
+                        return;
+                    };
+                    let Ok(widget) = widget.downcast::<gtk::Widget>() else {
+                        return;
+                    };
+                    if let Some(point) =
+                        widget.compute_point(&message_list, &gtk::graphene::Point::new(0.0, 0.0))
+                    {
+                        adj.set_value(f64::from(point.y()) - 12.0);
+                    }
+
+                    widget.add_css_class("message-search-target");
+                    glib::timeout_add_seconds_local_once(2, move || {
+                        widget.remove_css_class("message-search-target");
+                    });
+                });
+            }
             ChatViewMsg::ScrollPositionChanged => {
                 let adj = self.scrolled_window.vadjustment();
                 let at_bottom = adj.value() >= adj.upper() - adj.page_size() - 50.0;
                 self.user_scrolled_up = !at_bottom;
             }
-            ChatViewMsg::UserSendMessage(text, images) => {
-                let _ = sender.output(ChatViewOutput::SendMessage { text, images });
+            ChatViewMsg::UserSendMessage(text, images, documents) => {
+                let _ = sender.output(ChatViewOutput::SendMessage {
+                    text,
+                    images,
+                    documents,
+                });
             }
             // Streaming messages
             ChatViewMsg::AddStreamingMessage(message) => {
@@ -416,14 +518,20 @@ impl Component for ChatView {
                 drop(guard);
                 self.auto_scroll_to_bottom(&sender);
             }
-            ChatViewMsg::UpdateStreamingMessage(message_id, full_text) => {
+            ChatViewMsg::AppendStreamingText(message_id, delta) => {
                 // Buffer the update for timer-based rendering
                 let mut buf = self.streaming_buffer.borrow_mut();
-                *buf = Some(StreamBuffer {
-                    message_id,
-                    accumulated_text: full_text,
-                    needs_render: true,
-                });
+                match buf.as_mut() {
+                    Some(buffer) if buffer.message_id == message_id => {
+                        buffer.pending_text.push_str(&delta);
+                    }
+                    _ => {
+                        *buf = Some(StreamBuffer {
+                            message_id,
+                            pending_text: delta,
+                        });
+                    }
+                }
                 drop(buf);
 
                 // Start render timer if not active
@@ -441,33 +549,20 @@ impl Component for ChatView {
                     });
                 }
             }
+            ChatViewMsg::AppendStreamingThinking(message_id, delta) => {
+                self.send_to_streaming_widget(&message_id, MessageWidgetMsg::AppendThinking(delta));
+            }
             ChatViewMsg::RenderBuffered => {
-                let mut buf = self.streaming_buffer.borrow_mut();
-                if let Some(buffer) = buf.as_mut() {
-                    if buffer.needs_render {
-                        buffer.needs_render = false;
-                        let text = buffer.accumulated_text.clone();
-                        let msg_id = buffer.message_id.clone();
-                        drop(buf);
-
-                        // Find and update the streaming message
-                        self.update_message_content(&msg_id, &text);
-                        self.auto_scroll_to_bottom(&sender);
-                    }
+                if self.flush_stream_buffer() {
+                    self.auto_scroll_to_bottom(&sender);
                 }
             }
             ChatViewMsg::StreamingComplete(message_id) => {
                 // Stop the render timer
                 *self.render_timer_active.borrow_mut() = false;
 
-                // Do a final render from the buffer
-                let final_text = {
-                    let buf = self.streaming_buffer.borrow();
-                    buf.as_ref().map(|b| b.accumulated_text.clone())
-                };
-                if let Some(text) = final_text {
-                    self.update_message_content(&message_id, &text);
-                }
+                // Hand over text that arrived since the last tick
+                self.flush_stream_buffer();
 
                 // Send streaming complete to the widget
                 self.send_to_streaming_widget(&message_id, MessageWidgetMsg::StreamingComplete);
@@ -475,6 +570,15 @@ impl Component for ChatView {
                 self.streaming_message_id = None;
                 *self.streaming_buffer.borrow_mut() = None;
             }
+            ChatViewMsg::SetStreamingThinking(message_id, thinking) => {
+                self.send_to_streaming_widget(&message_id, MessageWidgetMsg::SetThinking(thinking));
+            }
+            ChatViewMsg::SetStreamingModel(message_id, model) => {
+                self.send_to_streaming_widget(
+                    &message_id,
+                    MessageWidgetMsg::SetFallbackModel(model),
+                );
+            }
             ChatViewMsg::RemoveMessage(message_id) => {
                 // Stop render timer
                 *self.render_timer_active.borrow_mut() = false;
@@ -500,19 +604,60 @@ impl Component for ChatView {
             ChatViewMsg::ForwardEditMessage(msg_id, new_content) => {
                 let _ = sender.output(ChatViewOutput::EditMessage(msg_id, new_content));
             }
+            ChatViewMsg::ForwardSwitchBranch(msg_id, index) => {
+                // Switching mid-reply would attach the reply to the wrong branch
+                if !self.loading {
+                    let _ = sender.output(ChatViewOutput::SwitchBranch(msg_id, index));
+                }
+            }
+            ChatViewMsg::ForwardFork(msg_id) => {
+                let _ = sender.output(ChatViewOutput::ForkFrom(msg_id));
+            }
+            ChatViewMsg::SetTemplates(templates) => {
+                self.input_area.emit(InputAreaMsg::SetTemplates(templates));
+            }
+            ChatViewMsg::ForwardSaveTemplate(name, content) => {
+                let _ = sender.output(ChatViewOutput::SaveTemplate(name, content));
+            }
+            ChatViewMsg::ForwardDeleteTemplate(id) => {
+                let _ = sender.output(ChatViewOutput::DeleteTemplate(id));
+            }
+            ChatViewMsg::InsertTemplate(template) => {
+                self.input_area.emit(InputAreaMsg::InsertTemplate(template));
+            }
+            ChatViewMsg::ForwardCommand(text) => {
+                let _ = sender.output(ChatViewOutput::RunCommand(text));
+            }
+            ChatViewMsg::ForwardAttachFailed(reason) => {
+                let _ = sender.output(ChatViewOutput::AttachFailed(reason));
+            }
             ChatViewMsg::CopyToClipboard(content) => {
                 if let Some(display) = gtk::gdk::Display::default() {
                     display.clipboard().set_text(&content);
                 }
             }
-            ChatViewMsg::ImageDropped(path) => {
-                self.input_area.emit(InputAreaMsg::AddImageFromPath(path));
+            ChatViewMsg::SetVisionSupported(supported) => {
+                self.input_area
+                    .emit(InputAreaMsg::SetVisionSupported(supported));
+            }
+            ChatViewMsg::FileDropped(path) => {
+                self.input_area.emit(InputAreaMsg::AddFileFromPath(path));
+            }
+            ChatViewMsg::SetMessageTokens(message_id, tokens_in, tokens_out, thinking_tokens) => {
+                let guard = self.messages.guard();
+                let pos = guard.iter().position(|m| m.message.id == message_id);
+                if let Some(idx) = pos {
+                    guard.send(
+                        idx,
+                        MessageWidgetMsg::SetTokens(tokens_in, tokens_out, thinking_tokens),
+                    );
+                }
             }
-            ChatViewMsg::SetMessageTokens(message_id, tokens_in, tokens_out) => {
+            ChatViewMsg::SetMessageBranch(message_id, branch) => {
                 let guard = self.messages.guard();
                 let pos = guard.iter().position(|m| m.message.id == message_id);
                 if let Some(idx) = pos {
-                    guard.send(idx, MessageWidgetMsg::SetTokens(tokens_in, tokens_out));
+                    guard.send(idx, MessageWidgetMsg::SetBranch(branch));
                 }
             }
             ChatViewMsg::ToggleSearch => {
@@ -541,6 +686,23 @@ impl Component for ChatView {
 }
 
 impl ChatView {
+    fn stop_retry_countdown(&mut self) {
+        self.retry_countdown = None;
+        let generation = self.retry_timer_generation.get().wrapping_add(1);
+        self.retry_timer_generation.set(generation);
+        self.update_status_label();
+    }
+
+    fn update_status_label(&self) {
+        match &self.retry_countdown {
+            Some((notice, seconds_left)) => self.status_label.set_label(&format!(
+                "Provider busy, retrying in {}s (attempt {} of {})...",
+                seconds_left, notice.attempt, notice.max_retries
+            )),
+            None => self.status_label.set_label("Generating..."),
+        }
+    }
+
     fn auto_scroll_to_bottom(&mut self, sender: &ComponentSender<Self>) {
         let adj = self.scrolled_window.vadjustment();
         let at_bottom = adj.value() >= adj.upper() - adj.page_size() - 50.0;
@@ -551,12 +713,24 @@ impl ChatView {
         }
     }
 
-    fn update_message_content(&mut self, message_id: &str, text: &str) {
-        let guard = self.messages.guard();
-        let pos = guard.iter().position(|m| m.message.id == *message_id);
-        if let Some(idx) = pos {
-            guard.send(idx, MessageWidgetMsg::UpdateContent(text.to_string()));
-        }
+    /// Send buffered streaming text to its message. Returns whether there was any.
+    fn flush_stream_buffer(&mut self) -> bool {
+        let pending = self
+            .streaming_buffer
+            .borrow_mut()
+            .as_mut()
+            .filter(|buffer| !buffer.pending_text.is_empty())
+            .map(|buffer| {
+                (
+                    buffer.message_id.clone(),
+                    std::mem::take(&mut buffer.pending_text),
+                )
+            });
+        let Some((message_id, text)) = pending else {
+            return false;
+        };
+        self.send_to_streaming_widget(&message_id, MessageWidgetMsg::AppendContent(text));
+        true
     }
 
     fn send_to_streaming_widget(&mut self, message_id: &str, msg: MessageWidgetMsg) {
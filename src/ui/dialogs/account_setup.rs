@@ -189,6 +189,7 @@ impl AsyncComponent for AccountSetupDialog {
         let default_model = match provider {
             ProviderId::Gemini => "gemini-2.5-flash".to_string(),
             ProviderId::Claude => "claude-sonnet-4-20250514".to_string(),
+            ProviderId::OpenAi => "gpt-4o".to_string(),
             ProviderId::Local => String::new(),
         };
 
@@ -218,7 +219,8 @@ impl AsyncComponent for AccountSetupDialog {
         let provider_index = match model.provider {
             ProviderId::Gemini => 0,
             ProviderId::Claude => 1,
-            ProviderId::Local => 2,
+            ProviderId::OpenAi => 2,
+            ProviderId::Local => 3,
         };
         widgets.provider_dropdown.set_selected(provider_index);
 
@@ -246,6 +248,7 @@ impl AsyncComponent for AccountSetupDialog {
                 let new_provider = match index {
                     0 => ProviderId::Gemini,
                     1 => ProviderId::Claude,
+                    2 => ProviderId::OpenAi,
                     _ => ProviderId::Local,
                 };
                 if self.provider != new_provider {
@@ -253,6 +256,7 @@ impl AsyncComponent for AccountSetupDialog {
                     self.default_model = match new_provider {
                         ProviderId::Gemini => "gemini-2.5-flash".to_string(),
                         ProviderId::Claude => "claude-sonnet-4-20250514".to_string(),
+                        ProviderId::OpenAi => "gpt-4o".to_string(),
                         ProviderId::Local => String::new(),
                     };
                     self.discovered_models.clear();
@@ -405,6 +409,7 @@ impl AccountSetupDialog {
         gtk::StringList::new(&[
             "Google Gemini",
             "Anthropic Claude",
+            "OpenAI",
             "Local (OpenAI Compatible)",
         ])
     }
@@ -427,6 +432,12 @@ impl AccountSetupDialog {
                 "Claude Opus 4".to_string(),
                 "Claude 3.5 Haiku".to_string(),
             ],
+            ProviderId::OpenAi => vec![
+                "GPT-4o".to_string(),
+                "GPT-4o mini".to_string(),
+                "GPT-4.1".to_string(),
+                "o3-mini".to_string(),
+            ],
             ProviderId::Local => vec![],
         }
     }
@@ -443,6 +454,12 @@ impl AccountSetupDialog {
                 "claude-opus-4-20250514".to_string(),
                 "claude-haiku-3-5-20241022".to_string(),
             ],
+            ProviderId::OpenAi => vec![
+                "gpt-4o".to_string(),
+                "gpt-4o-mini".to_string(),
+                "gpt-4.1".to_string(),
+                "o3-mini".to_string(),
+            ],
             ProviderId::Local => vec![],
         }
     }
@@ -0,0 +1,521 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use chrono::Utc;
+use relm4::prelude::*;
+use uuid::Uuid;
+
+use crate::models::{Account, Assistant, AssistantFile};
+use crate::ui::input_area::{attachment_mime_type, document_icon_name, MAX_TEXT_FILE_BYTES};
+
+/// Icon for assistants saved without one.
+const DEFAULT_ICON: &str = "🤖";
+
+pub struct AssistantsDialog {
+    assistants: Vec<Assistant>,
+    accounts: Vec<Account>,
+    // Assistant in the editor; `None` while creating a new one
+    selected_id: Option<String>,
+    files: Vec<AssistantFile>,
+    list: gtk::ListBox,
+    name_row: adw::EntryRow,
+    icon_row: adw::EntryRow,
+    account_row: adw::ComboRow,
+    model_row: adw::EntryRow,
+    temperature_row: adw::EntryRow,
+    prompt_buffer: gtk::TextBuffer,
+    files_group: adw::PreferencesGroup,
+    file_rows: Vec<adw::ActionRow>,
+}
+
+#[derive(Debug)]
+pub enum AssistantsMsg {
+    SetAssistants(Vec<Assistant>, Option<String>), // assistants, id to select
+    Select(usize),
+    New,
+    Save,
+    Delete,
+    StartChat,
+    AddFiles,
+    FilesSelected(Vec<PathBuf>),
+    RemoveFile(usize),
+    Close,
+}
+
+#[derive(Debug)]
+pub enum AssistantsOutput {
+    Save(Box<Assistant>),
+    Delete(String),
+    StartChat(String),
+    Closed,
+}
+
+pub struct AssistantsInit {
+    pub assistants: Vec<Assistant>,
+    pub accounts: Vec<Account>,
+}
+
+#[relm4::component(pub, async)]
+impl AsyncComponent for AssistantsDialog {
+    type Init = AssistantsInit;
+    type Input = AssistantsMsg;
+    type Output = AssistantsOutput;
+    type CommandOutput = ();
+
+    view! {
+        adw::Window {
+            set_title: Some("Assistants"),
+            set_default_width: 820,
+            set_default_height: 620,
+            set_modal: true,
+            connect_close_request[sender] => move |_| {
+                sender.input(AssistantsMsg::Close);
+                gtk::glib::Propagation::Proceed
+            },
+
+            adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    pack_start = &gtk::Button {
+                        set_icon_name: "list-add-symbolic",
+                        set_tooltip_text: Some("New Assistant"),
+                        connect_clicked => AssistantsMsg::New,
+                    },
+                },
+
+                #[wrap(Some)]
+                set_content = &gtk::Paned {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_position: 240,
+                    set_shrink_start_child: false,
+                    set_shrink_end_child: false,
+
+                    #[wrap(Some)]
+                    set_start_child = &gtk::ScrolledWindow {
+                        set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                        #[local_ref]
+                        list -> gtk::ListBox {
+                            add_css_class: "navigation-sidebar",
+                            connect_row_activated[sender] => move |_, row| {
+                                sender.input(AssistantsMsg::Select(row.index() as usize));
+                            },
+                        },
+                    },
+
+                    #[wrap(Some)]
+                    set_end_child = &gtk::ScrolledWindow {
+                        set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                        adw::Clamp {
+                            set_maximum_size: 560,
+                            set_margin_all: 16,
+
+                            gtk::Box {
+                                set_orientation: gtk::Orientation::Vertical,
+                                set_spacing: 18,
+
+                                adw::PreferencesGroup {
+                                    #[local_ref]
+                                    name_row -> adw::EntryRow {
+                                        set_title: "Name",
+                                    },
+                                    #[local_ref]
+                                    icon_row -> adw::EntryRow {
+                                        set_title: "Icon (emoji)",
+                                    },
+                                },
+
+                                adw::PreferencesGroup {
+                                    set_title: "Model",
+                                    set_description: Some("Leave the model or temperature empty to use the default."),
+
+                                    #[local_ref]
+                                    account_row -> adw::ComboRow {
+                                        set_title: "Account",
+                                    },
+                                    #[local_ref]
+                                    model_row -> adw::EntryRow {
+                                        set_title: "Model",
+                                    },
+                                    #[local_ref]
+                                    temperature_row -> adw::EntryRow {
+                                        set_title: "Temperature (0.0 to 2.0)",
+                                        set_input_purpose: gtk::InputPurpose::Number,
+                                    },
+                                },
+
+                                adw::PreferencesGroup {
+                                    set_title: "System Prompt",
+
+                                    gtk::Frame {
+                                        gtk::TextView {
+                                            set_buffer: Some(&model.prompt_buffer),
+                                            set_wrap_mode: gtk::WrapMode::WordChar,
+                                            set_height_request: 140,
+                                            set_top_margin: 8,
+                                            set_bottom_margin: 8,
+                                            set_left_margin: 8,
+                                            set_right_margin: 8,
+                                        },
+                                    },
+                                },
+
+                                #[local_ref]
+                                files_group -> adw::PreferencesGroup {
+                                    set_title: "Reference Files",
+                                    set_description: Some("Text and PDF files sent to the model with every message."),
+                                    #[wrap(Some)]
+                                    set_header_suffix = &gtk::Button {
+                                        set_icon_name: "list-add-symbolic",
+                                        set_tooltip_text: Some("Add Files"),
+                                        set_valign: gtk::Align::Center,
+                                        add_css_class: "flat",
+                                        connect_clicked => AssistantsMsg::AddFiles,
+                                    },
+                                },
+
+                                gtk::Box {
+                                    set_orientation: gtk::Orientation::Horizontal,
+                                    set_spacing: 6,
+
+                                    gtk::Button {
+                                        set_label: "Delete",
+                                        add_css_class: "destructive-action",
+                                        #[watch]
+                                        set_sensitive: model.selected_id.is_some(),
+                                        connect_clicked => AssistantsMsg::Delete,
+                                    },
+
+                                    gtk::Button {
+                                        set_label: "Start Chat",
+                                        set_halign: gtk::Align::End,
+                                        set_hexpand: true,
+                                        #[watch]
+                                        set_sensitive: model.selected_id.is_some(),
+                                        connect_clicked => AssistantsMsg::StartChat,
+                                    },
+
+                                    gtk::Button {
+                                        set_label: "Save",
+                                        add_css_class: "suggested-action",
+                                        connect_clicked => AssistantsMsg::Save,
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let list = gtk::ListBox::new();
+        let name_row = adw::EntryRow::new();
+        let icon_row = adw::EntryRow::new();
+        let model_row = adw::EntryRow::new();
+        let temperature_row = adw::EntryRow::new();
+        let files_group = adw::PreferencesGroup::new();
+
+        // The first entry keeps whichever account is selected when the chat starts
+        let mut labels = vec!["Selected Account"];
+        labels.extend(init.accounts.iter().map(|a| a.label.as_str()));
+        let account_row = adw::ComboRow::new();
+        account_row.set_model(Some(&gtk::StringList::new(&labels)));
+
+        let mut model = Self {
+            assistants: Vec::new(),
+            accounts: init.accounts,
+            selected_id: None,
+            files: Vec::new(),
+            list: list.clone(),
+            name_row: name_row.clone(),
+            icon_row: icon_row.clone(),
+            account_row: account_row.clone(),
+            model_row: model_row.clone(),
+            temperature_row: temperature_row.clone(),
+            prompt_buffer: gtk::TextBuffer::new(None::<&gtk::TextTagTable>),
+            files_group: files_group.clone(),
+            file_rows: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        // Open on the first assistant, if any
+        let first = init.assistants.first().map(|a| a.id.clone());
+        model.set_assistants(init.assistants, first, &sender);
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        sender: AsyncComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match msg {
+            AssistantsMsg::SetAssistants(assistants, select) => {
+                let select = select.or_else(|| self.selected_id.clone());
+                self.set_assistants(assistants, select, &sender);
+            }
+            AssistantsMsg::Select(index) => {
+                self.select(index, &sender);
+            }
+            AssistantsMsg::New => {
+                self.list.unselect_all();
+                self.clear_editor(&sender);
+                self.name_row.grab_focus();
+            }
+            AssistantsMsg::Save => {
+                if let Some(assistant) = self.editor_assistant() {
+                    let _ = sender.output(AssistantsOutput::Save(Box::new(assistant)));
+                }
+            }
+            AssistantsMsg::Delete => {
+                if let Some(id) = self.selected_id.clone() {
+                    let _ = sender.output(AssistantsOutput::Delete(id));
+                }
+            }
+            AssistantsMsg::StartChat => {
+                // Save first so the chat starts with what is in the editor
+                let Some(assistant) = self.editor_assistant() else {
+                    return;
+                };
+                let id = assistant.id.clone();
+                let _ = sender.output(AssistantsOutput::Save(Box::new(assistant)));
+                let _ = sender.output(AssistantsOutput::StartChat(id));
+                root.close();
+            }
+            AssistantsMsg::AddFiles => {
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some("Text and PDF files"));
+                filter.add_mime_type("application/pdf");
+                for extension in crate::ui::input_area::TEXT_EXTENSIONS {
+                    filter.add_suffix(extension);
+                }
+                let filters = gio::ListStore::new::<gtk::FileFilter>();
+                filters.append(&filter);
+
+                let dialog = gtk::FileDialog::builder()
+                    .title("Add Reference Files")
+                    .filters(&filters)
+                    .build();
+                let input = sender.input_sender().clone();
+                dialog.open_multiple(Some(root), None::<&gio::Cancellable>, move |result| {
+                    if let Ok(files) = result {
+                        let paths = files
+                            .iter::<gio::File>()
+                            .filter_map(|f| f.ok().and_then(|f| f.path()))
+                            .collect();
+                        input.send(AssistantsMsg::FilesSelected(paths)).unwrap();
+                    }
+                });
+            }
+            AssistantsMsg::FilesSelected(paths) => {
+                for path in paths {
+                    match read_reference_file(&path) {
+                        Some(file) => self.files.push(file),
+                        None => tracing::warn!("Not adding {}: unsupported file", path.display()),
+                    }
+                }
+                self.refresh_files(&sender);
+            }
+            AssistantsMsg::RemoveFile(index) => {
+                if index < self.files.len() {
+                    self.files.remove(index);
+                    self.refresh_files(&sender);
+                }
+            }
+            AssistantsMsg::Close => {
+                let _ = sender.output(AssistantsOutput::Closed);
+            }
+        }
+    }
+}
+
+impl AssistantsDialog {
+    fn set_assistants(
+        &mut self,
+        assistants: Vec<Assistant>,
+        select: Option<String>,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        self.assistants = assistants;
+
+        while let Some(row) = self.list.row_at_index(0) {
+            self.list.remove(&row);
+        }
+        for assistant in &self.assistants {
+            let label = gtk::Label::builder()
+                .label(format!("{}  {}", assistant.icon, assistant.name))
+                .halign(gtk::Align::Start)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .margin_top(6)
+                .margin_bottom(6)
+                .build();
+            self.list.append(&label);
+        }
+
+        match select.and_then(|id| self.assistants.iter().position(|a| a.id == id)) {
+            Some(index) => self.select(index, sender),
+            None => self.clear_editor(sender),
+        }
+    }
+
+    fn select(&mut self, index: usize, sender: &AsyncComponentSender<Self>) {
+        let Some(assistant) = self.assistants.get(index).cloned() else {
+            return;
+        };
+        if let Some(row) = self.list.row_at_index(index as i32) {
+            self.list.select_row(Some(&row));
+        }
+
+        self.selected_id = Some(assistant.id.clone());
+        self.name_row.set_text(&assistant.name);
+        self.icon_row.set_text(&assistant.icon);
+        let account_index = assistant
+            .account_id
+            .as_ref()
+            .and_then(|id| self.accounts.iter().position(|a| &a.id == id))
+            .map_or(0, |i| i + 1);
+        self.account_row.set_selected(account_index as u32);
+        self.model_row
+            .set_text(assistant.model.as_deref().unwrap_or_default());
+        self.temperature_row.set_text(
+            &assistant
+                .temperature
+                .map(|t| t.to_string())
+                .unwrap_or_default(),
+        );
+        self.temperature_row.remove_css_class("error");
+        self.prompt_buffer
+            .set_text(assistant.system_prompt.as_deref().unwrap_or_default());
+        self.files = assistant.files;
+        self.refresh_files(sender);
+    }
+
+    fn clear_editor(&mut self, sender: &AsyncComponentSender<Self>) {
+        self.selected_id = None;
+        self.name_row.set_text("");
+        self.icon_row.set_text("");
+        self.account_row.set_selected(0);
+        self.model_row.set_text("");
+        self.temperature_row.set_text("");
+        self.temperature_row.remove_css_class("error");
+        self.prompt_buffer.set_text("");
+        self.files.clear();
+        self.refresh_files(sender);
+    }
+
+    fn refresh_files(&mut self, sender: &AsyncComponentSender<Self>) {
+        for row in self.file_rows.drain(..) {
+            self.files_group.remove(&row);
+        }
+
+        for (index, file) in self.files.iter().enumerate() {
+            let row = adw::ActionRow::builder()
+                .title(gtk::glib::markup_escape_text(&file.filename))
+                .subtitle(gtk::glib::format_size(file.data.len() as u64))
+                .build();
+            row.add_prefix(&gtk::Image::from_icon_name(document_icon_name(
+                &file.mime_type,
+            )));
+
+            let remove = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text("Remove")
+                .valign(gtk::Align::Center)
+                .build();
+            remove.add_css_class("flat");
+            let input = sender.input_sender().clone();
+            remove.connect_clicked(move |_| {
+                input.send(AssistantsMsg::RemoveFile(index)).unwrap();
+            });
+            row.add_suffix(&remove);
+
+            self.files_group.add(&row);
+            self.file_rows.push(row);
+        }
+    }
+
+    /// The assistant described by the editor, or `None` if a field is invalid.
+    fn editor_assistant(&self) -> Option<Assistant> {
+        let name = self.name_row.text().trim().to_string();
+        if name.is_empty() {
+            self.name_row.grab_focus();
+            return None;
+        }
+
+        let temperature = self.temperature_row.text().trim().to_string();
+        let temperature = if temperature.is_empty() {
+            None
+        } else {
+            match temperature.parse::<f32>() {
+                Ok(t) if (0.0..=2.0).contains(&t) => Some(t),
+                _ => {
+                    self.temperature_row.add_css_class("error");
+                    return None;
+                }
+            }
+        };
+        self.temperature_row.remove_css_class("error");
+
+        let icon = self.icon_row.text().trim().to_string();
+        let model = self.model_row.text().trim().to_string();
+        let start = self.prompt_buffer.start_iter();
+        let end = self.prompt_buffer.end_iter();
+        let prompt = self.prompt_buffer.text(&start, &end, false).to_string();
+        let account_id = (self.account_row.selected() as usize)
+            .checked_sub(1)
+            .and_then(|i| self.accounts.get(i))
+            .map(|a| a.id.clone());
+
+        let existing = self
+            .selected_id
+            .as_ref()
+            .and_then(|id| self.assistants.iter().find(|a| &a.id == id));
+        let now = Utc::now();
+
+        Some(Assistant {
+            id: existing.map_or_else(|| Uuid::new_v4().to_string(), |a| a.id.clone()),
+            name,
+            icon: if icon.is_empty() {
+                DEFAULT_ICON.to_string()
+            } else {
+                icon
+            },
+            system_prompt: (!prompt.trim().is_empty()).then_some(prompt),
+            account_id,
+            model: (!model.is_empty()).then_some(model),
+            temperature,
+            files: self.files.clone(),
+            created_at: existing.map_or(now, |a| a.created_at),
+            updated_at: now,
+        })
+    }
+}
+
+/// Read a text or PDF file to attach to an assistant.
+fn read_reference_file(path: &std::path::Path) -> Option<AssistantFile> {
+    let mime_type = attachment_mime_type(path)?;
+    if mime_type.starts_with("image/") {
+        return None;
+    }
+
+    let data = std::fs::read(path).ok()?;
+    if mime_type == "text/plain" && (data.len() > MAX_TEXT_FILE_BYTES || data.contains(&0)) {
+        return None;
+    }
+
+    Some(AssistantFile {
+        id: Uuid::new_v4().to_string(),
+        filename: path.file_name()?.to_string_lossy().to_string(),
+        mime_type: mime_type.to_string(),
+        data,
+    })
+}
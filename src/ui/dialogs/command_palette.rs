@@ -0,0 +1,246 @@
+use adw::prelude::*;
+use chrono::Utc;
+use relm4::prelude::*;
+
+use crate::models::MessageSearchHit;
+use crate::services::palette::{self, PaletteItem, PaletteTarget};
+use crate::ui::sidebar::snippet_markup;
+
+/// Rows shown for conversations, models and actions; message hits come after them.
+const MAX_RESULTS: usize = 50;
+
+pub struct CommandPalette {
+    items: Vec<PaletteItem>,
+    query: String,
+    // Message content matches for `query`
+    hits: Vec<MessageSearchHit>,
+    // What each row of the list opens, in row order
+    shown: Vec<PaletteTarget>,
+    list: gtk::ListBox,
+    search_entry: gtk::SearchEntry,
+}
+
+#[derive(Debug)]
+pub enum CommandPaletteMsg {
+    QueryChanged(String),
+    SetMessageHits(String, Vec<MessageSearchHit>), // query, hits
+    Move(i32),                                     // rows to move the selection by
+    Activate(Option<usize>),                       // row; `None` for the selected one
+    Dismiss,
+    Close,
+}
+
+#[derive(Debug)]
+pub enum CommandPaletteOutput {
+    SearchMessages(String),
+    Chosen(PaletteTarget),
+    Closed,
+}
+
+#[relm4::component(pub, async)]
+impl AsyncComponent for CommandPalette {
+    type Init = Vec<PaletteItem>;
+    type Input = CommandPaletteMsg;
+    type Output = CommandPaletteOutput;
+    type CommandOutput = ();
+
+    view! {
+        adw::Window {
+            set_title: Some("Command Palette"),
+            set_default_width: 560,
+            set_default_height: 440,
+            set_modal: true,
+            connect_close_request[sender] => move |_| {
+                sender.input(CommandPaletteMsg::Close);
+                gtk::glib::Propagation::Proceed
+            },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+
+                #[local_ref]
+                search_entry -> gtk::SearchEntry {
+                    set_margin_all: 12,
+                    set_placeholder_text: Some("Search conversations, models and actions"),
+                    connect_search_changed[sender] => move |entry| {
+                        sender.input(CommandPaletteMsg::QueryChanged(entry.text().to_string()));
+                    },
+                    connect_activate => CommandPaletteMsg::Activate(None),
+                    connect_stop_search => CommandPaletteMsg::Dismiss,
+                },
+
+                gtk::Separator {},
+
+                gtk::ScrolledWindow {
+                    set_vexpand: true,
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                    #[local_ref]
+                    list -> gtk::ListBox {
+                        add_css_class: "navigation-sidebar",
+                        connect_row_activated[sender] => move |_, row| {
+                            sender.input(CommandPaletteMsg::Activate(Some(row.index() as usize)));
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        items: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let list = gtk::ListBox::new();
+        let search_entry = gtk::SearchEntry::new();
+        list.set_placeholder(Some(
+            &gtk::Label::builder()
+                .label("No matches")
+                .margin_top(24)
+                .css_classes(["dim-label"])
+                .build(),
+        ));
+
+        let mut model = Self {
+            items,
+            query: String::new(),
+            hits: Vec::new(),
+            shown: Vec::new(),
+            list: list.clone(),
+            search_entry: search_entry.clone(),
+        };
+        model.refresh();
+
+        let widgets = view_output!();
+
+        // Arrow keys move through the results while typing
+        let sender_key = sender.clone();
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        key_controller.connect_key_pressed(move |_, key, _code, _modifier| match key {
+            gtk::gdk::Key::Up => {
+                sender_key.input(CommandPaletteMsg::Move(-1));
+                gtk::glib::Propagation::Stop
+            }
+            gtk::gdk::Key::Down => {
+                sender_key.input(CommandPaletteMsg::Move(1));
+                gtk::glib::Propagation::Stop
+            }
+            _ => gtk::glib::Propagation::Proceed,
+        });
+        search_entry.add_controller(key_controller);
+        search_entry.grab_focus();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        sender: AsyncComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match msg {
+            CommandPaletteMsg::QueryChanged(query) => {
+                self.query = query.trim().to_string();
+                self.hits.clear();
+                self.refresh();
+                if !self.query.is_empty() {
+                    let _ = sender.output(CommandPaletteOutput::SearchMessages(self.query.clone()));
+                }
+            }
+            CommandPaletteMsg::SetMessageHits(query, hits) => {
+                // Results for an older query arrive after the user kept typing
+                if query == self.query {
+                    self.hits = hits;
+                    self.refresh();
+                }
+            }
+            CommandPaletteMsg::Move(delta) => {
+                let current = self.list.selected_row().map(|row| row.index()).unwrap_or(0);
+                let last = self.shown.len() as i32 - 1;
+                if let Some(row) = self
+                    .list
+                    .row_at_index((current + delta).clamp(0, last.max(0)))
+                {
+                    self.list.select_row(Some(&row));
+                    // Focusing the row scrolls it into view; typing continues in the entry
+                    row.grab_focus();
+                    self.search_entry.grab_focus();
+                }
+            }
+            CommandPaletteMsg::Activate(row) => {
+                let row = row.or_else(|| self.list.selected_row().map(|r| r.index() as usize));
+                if let Some(target) = row.and_then(|i| self.shown.get(i)) {
+                    let _ = sender.output(CommandPaletteOutput::Chosen(target.clone()));
+                    root.close();
+                }
+            }
+            CommandPaletteMsg::Dismiss => {
+                root.close();
+            }
+            CommandPaletteMsg::Close => {
+                let _ = sender.output(CommandPaletteOutput::Closed);
+            }
+        }
+    }
+}
+
+impl CommandPalette {
+    /// List the items matching the query, best first, followed by matching messages.
+    fn refresh(&mut self) {
+        while let Some(row) = self.list.row_at_index(0) {
+            self.list.remove(&row);
+        }
+        self.shown.clear();
+
+        let ranked = palette::rank(&self.items, &self.query, Utc::now());
+        for &i in ranked.iter().take(MAX_RESULTS) {
+            let item = &self.items[i];
+            let row = adw::ActionRow::builder()
+                .title(gtk::glib::markup_escape_text(&item.title))
+                .subtitle(gtk::glib::markup_escape_text(&item.subtitle))
+                .subtitle_lines(1)
+                .activatable(true)
+                .build();
+            let (icon, kind) = match &item.target {
+                PaletteTarget::Conversation(_) => ("chat-symbolic", "Conversation"),
+                PaletteTarget::Model { .. } => ("system-run-symbolic", "Model"),
+                PaletteTarget::Action(action) => (action.icon_name(), "Action"),
+                PaletteTarget::Message { .. } => ("edit-find-symbolic", "Message"),
+            };
+            row.add_prefix(&gtk::Image::from_icon_name(icon));
+            row.add_suffix(&kind_label(kind));
+            self.list.append(&row);
+            self.shown.push(item.target.clone());
+        }
+
+        for hit in &self.hits {
+            let row = adw::ActionRow::builder()
+                .title(gtk::glib::markup_escape_text(&hit.conversation_title))
+                .subtitle(snippet_markup(&hit.snippet))
+                .subtitle_lines(2)
+                .activatable(true)
+                .build();
+            row.add_prefix(&gtk::Image::from_icon_name("edit-find-symbolic"));
+            row.add_suffix(&kind_label("Message"));
+            self.list.append(&row);
+            self.shown.push(PaletteTarget::Message {
+                conversation_id: hit.conversation_id.clone(),
+                message_id: hit.message_id.clone(),
+            });
+        }
+
+        if let Some(row) = self.list.row_at_index(0) {
+            self.list.select_row(Some(&row));
+        }
+    }
+}
+
+fn kind_label(kind: &str) -> gtk::Label {
+    gtk::Label::builder()
+        .label(kind)
+        .css_classes(["dim-label", "caption"])
+        .build()
+}
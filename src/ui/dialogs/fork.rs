@@ -0,0 +1,178 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+
+use crate::models::Account;
+
+pub struct ForkDialog {
+    message_id: String,
+    accounts: Vec<Account>,
+    account_row: adw::ComboRow,
+    model_row: adw::EntryRow,
+    // Model of the conversation being forked, kept when its account stays selected
+    current_model: String,
+    current_account_id: String,
+}
+
+#[derive(Debug)]
+pub enum ForkMsg {
+    AccountChanged,
+    Fork,
+    Cancel,
+}
+
+#[derive(Debug)]
+pub enum ForkOutput {
+    Fork {
+        message_id: String,
+        account_id: String,
+        model: String,
+    },
+    Cancelled,
+}
+
+pub struct ForkInit {
+    pub message_id: String,
+    pub accounts: Vec<Account>,
+    pub account_id: String,
+    pub model: String,
+}
+
+#[relm4::component(pub, async)]
+impl AsyncComponent for ForkDialog {
+    type Init = ForkInit;
+    type Input = ForkMsg;
+    type Output = ForkOutput;
+    type CommandOutput = ();
+
+    view! {
+        adw::Window {
+            set_title: Some("Fork Conversation"),
+            set_default_width: 450,
+            set_default_height: -1,
+            set_modal: true,
+
+            adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    pack_start = &gtk::Button {
+                        set_label: "Cancel",
+                        connect_clicked => ForkMsg::Cancel,
+                    },
+                    pack_end = &gtk::Button {
+                        set_label: "Fork",
+                        add_css_class: "suggested-action",
+                        connect_clicked => ForkMsg::Fork,
+                    },
+                },
+
+                #[wrap(Some)]
+                set_content = &adw::Clamp {
+                    set_maximum_size: 400,
+                    set_margin_all: 16,
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 16,
+
+                        gtk::Label {
+                            set_label: "Copy the conversation up to this message into a new conversation. The original is left unchanged.",
+                            set_wrap: true,
+                            set_halign: gtk::Align::Start,
+                            add_css_class: "dim-label",
+                        },
+
+                        adw::PreferencesGroup {
+                            #[local_ref]
+                            account_row -> adw::ComboRow {
+                                set_title: "Account",
+                                connect_selected_notify => ForkMsg::AccountChanged,
+                            },
+
+                            #[local_ref]
+                            model_row -> adw::EntryRow {
+                                set_title: "Model",
+                                connect_entry_activated => ForkMsg::Fork,
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let labels: Vec<&str> = init.accounts.iter().map(|a| a.label.as_str()).collect();
+        let account_row = adw::ComboRow::new();
+        account_row.set_model(Some(&gtk::StringList::new(&labels)));
+        if let Some(index) = init.accounts.iter().position(|a| a.id == init.account_id) {
+            account_row.set_selected(index as u32);
+        }
+
+        let model_row = adw::EntryRow::new();
+        model_row.set_text(&init.model);
+
+        let model = Self {
+            message_id: init.message_id,
+            accounts: init.accounts,
+            account_row: account_row.clone(),
+            model_row: model_row.clone(),
+            current_model: init.model,
+            current_account_id: init.account_id,
+        };
+
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        sender: AsyncComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match msg {
+            ForkMsg::AccountChanged => {
+                // Suggest the conversation's model on its own account, else the default
+                if let Some(account) = self.selected_account() {
+                    let model = if account.id == self.current_account_id {
+                        self.current_model.clone()
+                    } else {
+                        account.default_model.clone()
+                    };
+                    self.model_row.set_text(&model);
+                }
+            }
+            ForkMsg::Fork => {
+                let Some(account) = self.selected_account() else {
+                    return;
+                };
+                let model = self.model_row.text().trim().to_string();
+                let model = if model.is_empty() {
+                    account.default_model.clone()
+                } else {
+                    model
+                };
+                let _ = sender.output(ForkOutput::Fork {
+                    message_id: self.message_id.clone(),
+                    account_id: account.id.clone(),
+                    model,
+                });
+                root.close();
+            }
+            ForkMsg::Cancel => {
+                let _ = sender.output(ForkOutput::Cancelled);
+                root.close();
+            }
+        }
+    }
+}
+
+impl ForkDialog {
+    fn selected_account(&self) -> Option<&Account> {
+        self.accounts.get(self.account_row.selected() as usize)
+    }
+}
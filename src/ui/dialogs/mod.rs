@@ -1,2 +1,6 @@
 pub mod account_setup;
+pub mod assistants;
+pub mod command_palette;
+pub mod fork;
+pub mod prompt_library;
 pub mod system_prompt;
@@ -0,0 +1,392 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use relm4::prelude::*;
+
+use crate::models::PromptPreset;
+use crate::services::prompt_library::LibraryFormat;
+
+pub struct PromptLibraryDialog {
+    presets: Vec<PromptPreset>,
+    // Indices into `presets` of the rows matching the search, in list order
+    shown: Vec<usize>,
+    search_term: String,
+    // Preset in the editor; `None` while writing a new one
+    selected_id: Option<String>,
+    list: gtk::ListBox,
+    name_row: adw::EntryRow,
+    buffer: gtk::TextBuffer,
+}
+
+#[derive(Debug)]
+pub enum PromptLibraryMsg {
+    SetPresets(Vec<PromptPreset>, Option<String>), // presets, id to select
+    SearchChanged(String),
+    Select(usize), // row index
+    New,
+    Save,
+    Delete,
+    Import(LibraryFormat),
+    Export(LibraryFormat),
+    Close,
+}
+
+#[derive(Debug)]
+pub enum PromptLibraryOutput {
+    Save {
+        id: Option<String>,
+        name: String,
+        content: String,
+    },
+    Delete(String),
+    Import(PathBuf, LibraryFormat),
+    Export(PathBuf, LibraryFormat),
+    Closed,
+}
+
+#[relm4::component(pub, async)]
+impl AsyncComponent for PromptLibraryDialog {
+    type Init = Vec<PromptPreset>;
+    type Input = PromptLibraryMsg;
+    type Output = PromptLibraryOutput;
+    type CommandOutput = ();
+
+    view! {
+        adw::Window {
+            set_title: Some("Prompt Library"),
+            set_default_width: 760,
+            set_default_height: 520,
+            set_modal: true,
+            connect_close_request[sender] => move |_| {
+                sender.input(PromptLibraryMsg::Close);
+                gtk::glib::Propagation::Proceed
+            },
+
+            adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    pack_start = &gtk::Button {
+                        set_icon_name: "list-add-symbolic",
+                        set_tooltip_text: Some("New Prompt"),
+                        connect_clicked => PromptLibraryMsg::New,
+                    },
+                    pack_end = &gtk::MenuButton {
+                        set_icon_name: "view-more-symbolic",
+                        set_tooltip_text: Some("Import and Export"),
+                        set_menu_model: Some(&library_menu),
+                    },
+                },
+
+                #[wrap(Some)]
+                set_content = &gtk::Paned {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_position: 260,
+                    set_shrink_start_child: false,
+                    set_shrink_end_child: false,
+
+                    #[wrap(Some)]
+                    set_start_child = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+
+                        gtk::SearchEntry {
+                            set_margin_all: 8,
+                            set_placeholder_text: Some("Search prompts"),
+                            connect_search_changed[sender] => move |entry| {
+                                sender.input(PromptLibraryMsg::SearchChanged(entry.text().to_string()));
+                            },
+                        },
+
+                        gtk::ScrolledWindow {
+                            set_vexpand: true,
+                            set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                            #[local_ref]
+                            list -> gtk::ListBox {
+                                add_css_class: "navigation-sidebar",
+                                connect_row_activated[sender] => move |_, row| {
+                                    sender.input(PromptLibraryMsg::Select(row.index() as usize));
+                                },
+                            },
+                        },
+                    },
+
+                    #[wrap(Some)]
+                    set_end_child = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 12,
+                        set_margin_all: 12,
+
+                        adw::PreferencesGroup {
+                            #[local_ref]
+                            name_row -> adw::EntryRow {
+                                set_title: "Name",
+                            },
+                        },
+
+                        gtk::ScrolledWindow {
+                            set_vexpand: true,
+                            set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                            #[name = "text_view"]
+                            gtk::TextView {
+                                set_wrap_mode: gtk::WrapMode::WordChar,
+                                set_top_margin: 8,
+                                set_bottom_margin: 8,
+                                set_left_margin: 8,
+                                set_right_margin: 8,
+                                add_css_class: "card",
+                            },
+                        },
+
+                        gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+
+                            gtk::Button {
+                                set_label: "Delete",
+                                add_css_class: "destructive-action",
+                                #[watch]
+                                set_sensitive: model.selected_id.is_some(),
+                                connect_clicked => PromptLibraryMsg::Delete,
+                            },
+
+                            gtk::Button {
+                                set_label: "Save",
+                                set_halign: gtk::Align::End,
+                                set_hexpand: true,
+                                add_css_class: "suggested-action",
+                                connect_clicked => PromptLibraryMsg::Save,
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        presets: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let library_menu = gio::Menu::new();
+        library_menu.append(Some("Import JSON…"), Some("library.import-json"));
+        library_menu.append(
+            Some("Import Markdown Folder…"),
+            Some("library.import-markdown"),
+        );
+        library_menu.append(Some("Export JSON…"), Some("library.export-json"));
+        library_menu.append(
+            Some("Export Markdown Folder…"),
+            Some("library.export-markdown"),
+        );
+
+        let action_group = gio::SimpleActionGroup::new();
+        for (name, import, format) in [
+            ("import-json", true, LibraryFormat::Json),
+            ("import-markdown", true, LibraryFormat::Markdown),
+            ("export-json", false, LibraryFormat::Json),
+            ("export-markdown", false, LibraryFormat::Markdown),
+        ] {
+            let action = gio::SimpleAction::new(name, None);
+            let sender = sender.input_sender().clone();
+            action.connect_activate(move |_, _| {
+                let msg = if import {
+                    PromptLibraryMsg::Import(format)
+                } else {
+                    PromptLibraryMsg::Export(format)
+                };
+                sender.send(msg).unwrap();
+            });
+            action_group.add_action(&action);
+        }
+        root.insert_action_group("library", Some(&action_group));
+
+        let list = gtk::ListBox::new();
+        let name_row = adw::EntryRow::new();
+        let buffer = gtk::TextBuffer::new(None::<&gtk::TextTagTable>);
+
+        let mut model = Self {
+            presets,
+            shown: Vec::new(),
+            search_term: String::new(),
+            selected_id: None,
+            list: list.clone(),
+            name_row: name_row.clone(),
+            buffer: buffer.clone(),
+        };
+        model.refresh_list();
+        // Open on the first preset, if any
+        if !model.shown.is_empty() {
+            model.select_row(0);
+        }
+
+        let widgets = view_output!();
+        widgets.text_view.set_buffer(Some(&buffer));
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        sender: AsyncComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match msg {
+            PromptLibraryMsg::SetPresets(presets, select) => {
+                self.presets = presets;
+                self.refresh_list();
+                let selected = select.or_else(|| self.selected_id.clone());
+                let row = selected
+                    .and_then(|id| self.shown.iter().position(|&i| self.presets[i].id == id));
+                match row {
+                    Some(row) => self.select_row(row),
+                    None => self.clear_editor(),
+                }
+            }
+            PromptLibraryMsg::SearchChanged(term) => {
+                self.search_term = term.trim().to_lowercase();
+                self.refresh_list();
+                if let Some(row) = self
+                    .selected_id
+                    .as_ref()
+                    .and_then(|id| self.shown.iter().position(|&i| &self.presets[i].id == id))
+                {
+                    if let Some(list_row) = self.list.row_at_index(row as i32) {
+                        self.list.select_row(Some(&list_row));
+                    }
+                }
+            }
+            PromptLibraryMsg::Select(row) => {
+                self.select_row(row);
+            }
+            PromptLibraryMsg::New => {
+                self.list.unselect_all();
+                self.clear_editor();
+                self.name_row.grab_focus();
+            }
+            PromptLibraryMsg::Save => {
+                let name = self.name_row.text().trim().to_string();
+                let start = self.buffer.start_iter();
+                let end = self.buffer.end_iter();
+                let content = self.buffer.text(&start, &end, false).to_string();
+                if name.is_empty() || content.trim().is_empty() {
+                    return;
+                }
+                let _ = sender.output(PromptLibraryOutput::Save {
+                    id: self.selected_id.clone(),
+                    name,
+                    content,
+                });
+            }
+            PromptLibraryMsg::Delete => {
+                if let Some(id) = self.selected_id.clone() {
+                    let _ = sender.output(PromptLibraryOutput::Delete(id));
+                }
+            }
+            PromptLibraryMsg::Import(format) => {
+                let output = sender.output_sender().clone();
+                let callback = move |result: Result<gio::File, gtk::glib::Error>| {
+                    if let Some(path) = result.ok().and_then(|f| f.path()) {
+                        let _ = output.send(PromptLibraryOutput::Import(path, format));
+                    }
+                };
+                match format {
+                    LibraryFormat::Json => {
+                        let filter = gtk::FileFilter::new();
+                        filter.set_name(Some("JSON"));
+                        filter.add_pattern("*.json");
+                        let filters = gio::ListStore::new::<gtk::FileFilter>();
+                        filters.append(&filter);
+                        gtk::FileDialog::builder()
+                            .title("Import Prompts")
+                            .filters(&filters)
+                            .build()
+                            .open(Some(root), None::<&gio::Cancellable>, callback);
+                    }
+                    LibraryFormat::Markdown => {
+                        gtk::FileDialog::builder()
+                            .title("Import Prompts from Folder")
+                            .build()
+                            .select_folder(Some(root), None::<&gio::Cancellable>, callback);
+                    }
+                }
+            }
+            PromptLibraryMsg::Export(format) => {
+                let output = sender.output_sender().clone();
+                let callback = move |result: Result<gio::File, gtk::glib::Error>| {
+                    if let Some(path) = result.ok().and_then(|f| f.path()) {
+                        let _ = output.send(PromptLibraryOutput::Export(path, format));
+                    }
+                };
+                match format {
+                    LibraryFormat::Json => {
+                        gtk::FileDialog::builder()
+                            .title("Export Prompts")
+                            .initial_name("system-prompts.json")
+                            .build()
+                            .save(Some(root), None::<&gio::Cancellable>, callback);
+                    }
+                    LibraryFormat::Markdown => {
+                        gtk::FileDialog::builder()
+                            .title("Export Prompts to Folder")
+                            .build()
+                            .select_folder(Some(root), None::<&gio::Cancellable>, callback);
+                    }
+                }
+            }
+            PromptLibraryMsg::Close => {
+                let _ = sender.output(PromptLibraryOutput::Closed);
+            }
+        }
+    }
+}
+
+impl PromptLibraryDialog {
+    /// List the presets matching the search by name or content.
+    fn refresh_list(&mut self) {
+        while let Some(row) = self.list.row_at_index(0) {
+            self.list.remove(&row);
+        }
+
+        self.shown = self
+            .presets
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| {
+                self.search_term.is_empty()
+                    || p.name.to_lowercase().contains(&self.search_term)
+                    || p.content.to_lowercase().contains(&self.search_term)
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        for &i in &self.shown {
+            let label = gtk::Label::builder()
+                .label(&self.presets[i].name)
+                .halign(gtk::Align::Start)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .margin_top(6)
+                .margin_bottom(6)
+                .build();
+            self.list.append(&label);
+        }
+    }
+
+    fn select_row(&mut self, row: usize) {
+        let Some(preset) = self.shown.get(row).and_then(|&i| self.presets.get(i)) else {
+            return;
+        };
+        self.selected_id = Some(preset.id.clone());
+        self.name_row.set_text(&preset.name);
+        self.buffer.set_text(&preset.content);
+        if let Some(list_row) = self.list.row_at_index(row as i32) {
+            self.list.select_row(Some(&list_row));
+        }
+    }
+
+    fn clear_editor(&mut self) {
+        self.selected_id = None;
+        self.name_row.set_text("");
+        self.buffer.set_text("");
+    }
+}
@@ -1,9 +1,17 @@
 use adw::prelude::*;
 use relm4::prelude::*;
 
+use crate::models::PromptPreset;
+use crate::ui::window::ask_for_name;
+
 pub struct SystemPromptDialog {
     buffer: gtk::TextBuffer,
     conversation_id: String,
+    // Library presets, and which of them match the search, in list order
+    presets: Vec<PromptPreset>,
+    shown: Vec<usize>,
+    search_term: String,
+    preset_list: gtk::ListBox,
 }
 
 #[derive(Debug)]
@@ -11,17 +19,25 @@ pub enum SystemPromptMsg {
     Save,
     Cancel,
     Clear,
+    SetPresets(Vec<PromptPreset>),
+    SearchChanged(String),
+    ApplyPreset(usize), // row index
+    SaveToLibrary,
+    ManageLibrary,
 }
 
 #[derive(Debug)]
 pub enum SystemPromptOutput {
     Updated(String, Option<String>), // (conversation_id, new_prompt)
+    SaveToLibrary(String, String),   // (name, content)
+    ManageLibrary,
     Cancelled,
 }
 
 pub struct SystemPromptInit {
     pub conversation_id: String,
     pub current_prompt: Option<String>,
+    pub presets: Vec<PromptPreset>,
 }
 
 #[relm4::component(pub, async)]
@@ -35,7 +51,7 @@ impl AsyncComponent for SystemPromptDialog {
         adw::Window {
             set_title: Some("System Prompt"),
             set_default_width: 500,
-            set_default_height: 400,
+            set_default_height: 560,
             set_modal: true,
 
             adw::ToolbarView {
@@ -64,6 +80,41 @@ impl AsyncComponent for SystemPromptDialog {
                         add_css_class: "dim-label",
                     },
 
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 6,
+
+                        gtk::SearchEntry {
+                            set_hexpand: true,
+                            set_placeholder_text: Some("Search prompt library"),
+                            connect_search_changed[sender] => move |entry| {
+                                sender.input(SystemPromptMsg::SearchChanged(entry.text().to_string()));
+                            },
+                        },
+
+                        gtk::Button {
+                            set_label: "Manage…",
+                            set_tooltip_text: Some("Open the prompt library"),
+                            connect_clicked => SystemPromptMsg::ManageLibrary,
+                        },
+                    },
+
+                    gtk::ScrolledWindow {
+                        set_hscrollbar_policy: gtk::PolicyType::Never,
+                        set_min_content_height: 60,
+                        set_max_content_height: 160,
+                        set_propagate_natural_height: true,
+
+                        #[local_ref]
+                        preset_list -> gtk::ListBox {
+                            set_selection_mode: gtk::SelectionMode::None,
+                            add_css_class: "boxed-list",
+                            connect_row_activated[sender] => move |_, row| {
+                                sender.input(SystemPromptMsg::ApplyPreset(row.index() as usize));
+                            },
+                        },
+                    },
+
                     gtk::ScrolledWindow {
                         set_vexpand: true,
                         set_hscrollbar_policy: gtk::PolicyType::Never,
@@ -79,11 +130,22 @@ impl AsyncComponent for SystemPromptDialog {
                         },
                     },
 
-                    gtk::Button {
-                        set_label: "Clear",
-                        set_halign: gtk::Align::Start,
-                        add_css_class: "destructive-action",
-                        connect_clicked => SystemPromptMsg::Clear,
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+
+                        gtk::Button {
+                            set_label: "Clear",
+                            set_halign: gtk::Align::Start,
+                            add_css_class: "destructive-action",
+                            connect_clicked => SystemPromptMsg::Clear,
+                        },
+
+                        gtk::Button {
+                            set_label: "Save to Library…",
+                            set_halign: gtk::Align::End,
+                            set_hexpand: true,
+                            connect_clicked => SystemPromptMsg::SaveToLibrary,
+                        },
                     },
                 },
             },
@@ -100,10 +162,25 @@ impl AsyncComponent for SystemPromptDialog {
             buffer.set_text(prompt);
         }
 
-        let model = Self {
+        let preset_list = gtk::ListBox::new();
+        preset_list.set_placeholder(Some(
+            &gtk::Label::builder()
+                .label("No saved prompts")
+                .margin_top(12)
+                .margin_bottom(12)
+                .css_classes(["dim-label"])
+                .build(),
+        ));
+
+        let mut model = Self {
             buffer: buffer.clone(),
             conversation_id: init.conversation_id,
+            presets: init.presets,
+            shown: Vec::new(),
+            search_term: String::new(),
+            preset_list: preset_list.clone(),
         };
+        model.refresh_list();
 
         let widgets = view_output!();
         widgets.text_view.set_buffer(Some(&buffer));
@@ -119,9 +196,7 @@ impl AsyncComponent for SystemPromptDialog {
     ) {
         match msg {
             SystemPromptMsg::Save => {
-                let start = self.buffer.start_iter();
-                let end = self.buffer.end_iter();
-                let text = self.buffer.text(&start, &end, false).to_string();
+                let text = self.text();
                 let prompt = if text.trim().is_empty() {
                     None
                 } else {
@@ -140,6 +215,82 @@ impl AsyncComponent for SystemPromptDialog {
             SystemPromptMsg::Clear => {
                 self.buffer.set_text("");
             }
+            SystemPromptMsg::SetPresets(presets) => {
+                self.presets = presets;
+                self.refresh_list();
+            }
+            SystemPromptMsg::SearchChanged(term) => {
+                self.search_term = term.trim().to_lowercase();
+                self.refresh_list();
+            }
+            SystemPromptMsg::ApplyPreset(row) => {
+                if let Some(preset) = self.shown.get(row).and_then(|&i| self.presets.get(i)) {
+                    let _ = sender.output(SystemPromptOutput::Updated(
+                        self.conversation_id.clone(),
+                        Some(preset.content.clone()),
+                    ));
+                    root.close();
+                }
+            }
+            SystemPromptMsg::SaveToLibrary => {
+                let content = self.text();
+                if content.trim().is_empty() {
+                    return;
+                }
+                let output = sender.output_sender().clone();
+                ask_for_name(root, "Save to Library", "", "Save", move |name| {
+                    let _ = output.send(SystemPromptOutput::SaveToLibrary(name, content.clone()));
+                });
+            }
+            SystemPromptMsg::ManageLibrary => {
+                let _ = sender.output(SystemPromptOutput::ManageLibrary);
+                root.close();
+            }
+        }
+    }
+}
+
+impl SystemPromptDialog {
+    fn text(&self) -> String {
+        let start = self.buffer.start_iter();
+        let end = self.buffer.end_iter();
+        self.buffer.text(&start, &end, false).to_string()
+    }
+
+    /// Show the presets matching the search by name or content.
+    fn refresh_list(&mut self) {
+        while let Some(row) = self.preset_list.row_at_index(0) {
+            self.preset_list.remove(&row);
+        }
+
+        self.shown = self
+            .presets
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| {
+                self.search_term.is_empty()
+                    || p.name.to_lowercase().contains(&self.search_term)
+                    || p.content.to_lowercase().contains(&self.search_term)
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        for &i in &self.shown {
+            let preset = &self.presets[i];
+            let row = adw::ActionRow::builder()
+                .title(gtk::glib::markup_escape_text(&preset.name))
+                .subtitle(gtk::glib::markup_escape_text(
+                    &preset
+                        .content
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ))
+                .subtitle_lines(1)
+                .activatable(true)
+                .tooltip_text("Apply to this conversation")
+                .build();
+            self.preset_list.append(&row);
         }
     }
 }
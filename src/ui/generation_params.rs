@@ -0,0 +1,254 @@
+use std::str::FromStr;
+
+use gtk::prelude::*;
+use relm4::prelude::*;
+
+use crate::models::GenerationParams;
+
+/// Header popover for editing the active conversation's sampling parameters.
+pub struct GenerationParamsPopover {
+    conversation_id: Option<String>,
+    temperature: gtk::Entry,
+    max_tokens: gtk::Entry,
+    top_p: gtk::Entry,
+    top_k: gtk::Entry,
+    seed: gtk::Entry,
+    stop_buffer: gtk::TextBuffer,
+}
+
+#[derive(Debug)]
+pub enum GenerationParamsMsg {
+    Opened,
+    Load(String, GenerationParams), // (conversation_id, params)
+    Apply,
+    Reset,
+}
+
+#[derive(Debug)]
+pub enum GenerationParamsOutput {
+    /// The popover was opened; the owner should answer with `Load`.
+    Opened,
+    Changed(String, GenerationParams), // (conversation_id, params)
+}
+
+#[relm4::component(pub)]
+impl Component for GenerationParamsPopover {
+    type Init = ();
+    type Input = GenerationParamsMsg;
+    type Output = GenerationParamsOutput;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Popover {
+            connect_show => GenerationParamsMsg::Opened,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 12,
+                set_margin_all: 6,
+
+                gtk::Label {
+                    set_label: "Generation Parameters",
+                    set_halign: gtk::Align::Start,
+                    add_css_class: "heading",
+                },
+
+                gtk::Label {
+                    set_label: "Leave a field empty to use the default.",
+                    set_halign: gtk::Align::Start,
+                    add_css_class: "dim-label",
+                    add_css_class: "caption",
+                },
+
+                #[local_ref]
+                grid -> gtk::Grid {
+                    set_row_spacing: 6,
+                    set_column_spacing: 12,
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+                    set_halign: gtk::Align::End,
+
+                    gtk::Button {
+                        set_label: "Reset",
+                        connect_clicked => GenerationParamsMsg::Reset,
+                    },
+                    gtk::Button {
+                        set_label: "Apply",
+                        add_css_class: "suggested-action",
+                        connect_clicked => GenerationParamsMsg::Apply,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let grid = gtk::Grid::new();
+
+        let entry = |row: i32, label: &str, tooltip: &str| {
+            let title = gtk::Label::builder()
+                .label(label)
+                .halign(gtk::Align::Start)
+                .build();
+            let entry = gtk::Entry::builder()
+                .placeholder_text("Default")
+                .tooltip_text(tooltip)
+                .width_chars(10)
+                .build();
+            let sender = sender.input_sender().clone();
+            entry.connect_activate(move |_| {
+                sender.send(GenerationParamsMsg::Apply).unwrap();
+            });
+            grid.attach(&title, 0, row, 1, 1);
+            grid.attach(&entry, 1, row, 1, 1);
+            entry
+        };
+
+        let temperature = entry(0, "Temperature", "0.0 to 2.0");
+        let max_tokens = entry(1, "Max tokens", "Longest reply, in tokens");
+        let top_p = entry(2, "Top P", "0.0 to 1.0");
+        let top_k = entry(3, "Top K", "Ignored by OpenAI");
+        let seed = entry(4, "Seed", "Ignored by Claude");
+
+        let stop_label = gtk::Label::builder()
+            .label("Stop sequences")
+            .halign(gtk::Align::Start)
+            .valign(gtk::Align::Start)
+            .build();
+        let stop_buffer = gtk::TextBuffer::new(None::<&gtk::TextTagTable>);
+        let stop_view = gtk::TextView::builder()
+            .buffer(&stop_buffer)
+            .tooltip_text("One per line")
+            .top_margin(4)
+            .bottom_margin(4)
+            .left_margin(4)
+            .right_margin(4)
+            .height_request(60)
+            .build();
+        stop_view.add_css_class("card");
+        grid.attach(&stop_label, 0, 5, 1, 1);
+        grid.attach(&stop_view, 1, 5, 1, 1);
+
+        let model = Self {
+            conversation_id: None,
+            temperature,
+            max_tokens,
+            top_p,
+            top_k,
+            seed,
+            stop_buffer,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
+        match msg {
+            GenerationParamsMsg::Opened => {
+                self.conversation_id = None;
+                let _ = sender.output(GenerationParamsOutput::Opened);
+            }
+            GenerationParamsMsg::Load(conversation_id, params) => {
+                self.conversation_id = Some(conversation_id);
+                self.show_params(&params);
+            }
+            GenerationParamsMsg::Apply => {
+                let Some(conversation_id) = self.conversation_id.clone() else {
+                    return;
+                };
+                if let Some(params) = self.read_params() {
+                    let _ = sender.output(GenerationParamsOutput::Changed(conversation_id, params));
+                    root.popdown();
+                }
+            }
+            GenerationParamsMsg::Reset => {
+                self.show_params(&GenerationParams::default());
+            }
+        }
+    }
+}
+
+impl GenerationParamsPopover {
+    fn show_params(&self, params: &GenerationParams) {
+        let text = |value: Option<String>| value.unwrap_or_default();
+        self.temperature
+            .set_text(&text(params.temperature.map(|v| v.to_string())));
+        self.max_tokens
+            .set_text(&text(params.max_tokens.map(|v| v.to_string())));
+        self.top_p
+            .set_text(&text(params.top_p.map(|v| v.to_string())));
+        self.top_k
+            .set_text(&text(params.top_k.map(|v| v.to_string())));
+        self.seed
+            .set_text(&text(params.seed.map(|v| v.to_string())));
+        self.stop_buffer.set_text(&params.stop_sequences.join("\n"));
+
+        for entry in [
+            &self.temperature,
+            &self.max_tokens,
+            &self.top_p,
+            &self.top_k,
+            &self.seed,
+        ] {
+            entry.remove_css_class("error");
+        }
+    }
+
+    /// Parse the fields, marking invalid ones. Returns `None` if any field is invalid.
+    fn read_params(&self) -> Option<GenerationParams> {
+        let temperature = parse_field::<f32>(&self.temperature, |v| (0.0..=2.0).contains(&v));
+        let max_tokens = parse_field::<u32>(&self.max_tokens, |v| v > 0);
+        let top_p = parse_field::<f32>(&self.top_p, |v| (0.0..=1.0).contains(&v));
+        let top_k = parse_field::<u32>(&self.top_k, |v| v > 0);
+        let seed = parse_field::<i64>(&self.seed, |_| true);
+
+        let start = self.stop_buffer.start_iter();
+        let end = self.stop_buffer.end_iter();
+        let stop_sequences = self
+            .stop_buffer
+            .text(&start, &end, false)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Some(GenerationParams {
+            temperature: temperature?,
+            max_tokens: max_tokens?,
+            top_p: top_p?,
+            top_k: top_k?,
+            stop_sequences,
+            seed: seed?,
+        })
+    }
+}
+
+/// Parse an optional numeric entry: empty is `Some(None)`, invalid is `None`.
+fn parse_field<T: FromStr + Copy>(
+    entry: &gtk::Entry,
+    valid: impl Fn(T) -> bool,
+) -> Option<Option<T>> {
+    let text = entry.text();
+    let text = text.trim();
+    let value = if text.is_empty() {
+        Some(None)
+    } else {
+        text.parse::<T>().ok().filter(|v| valid(*v)).map(Some)
+    };
+
+    if value.is_some() {
+        entry.remove_css_class("error");
+    } else {
+        entry.add_css_class("error");
+    }
+    value
+}
@@ -1,33 +1,99 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use gtk::prelude::*;
+use adw::prelude::*;
 use relm4::prelude::*;
 
-use crate::providers::ImageAttachment;
+use crate::commands::{self, SlashCommand};
+use crate::models::PromptTemplate;
+use crate::providers::{DocumentAttachment, ImageAttachment};
+use crate::services::templates::{fill_template, template_variables, CLIPBOARD_VARIABLE};
+use crate::ui::window::ask_for_name;
+
+/// Plain-text and source files, inlined into the prompt as fenced blocks.
+pub const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "rst", "log", "csv", "tsv", "json", "jsonl", "yaml", "yml", "toml",
+    "ini", "cfg", "conf", "xml", "html", "htm", "css", "scss", "rs", "py", "js", "mjs", "ts",
+    "jsx", "tsx", "c", "h", "cc", "cpp", "hpp", "cs", "go", "java", "kt", "swift", "rb", "php",
+    "lua", "pl", "r", "scala", "dart", "zig", "hs", "ml", "ex", "exs", "sql", "sh", "bash", "zsh",
+    "fish", "nix", "vue", "svelte", "tex", "proto", "diff", "patch",
+];
+
+/// Larger text files would crowd the rest of the conversation out of the context window.
+pub const MAX_TEXT_FILE_BYTES: usize = 512 * 1024;
+
+/// MIME type of an attachable file, judged by its extension.
+pub fn attachment_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "pdf" => Some("application/pdf"),
+        ext if TEXT_EXTENSIONS.contains(&ext) => Some("text/plain"),
+        _ => None,
+    }
+}
+
+/// Whether `path` is an image, PDF, or text file that can be attached.
+pub fn is_attachable(path: &Path) -> bool {
+    attachment_mime_type(path).is_some()
+}
 
-pub struct PendingImage {
+pub struct PendingAttachment {
     pub mime_type: String,
+    pub filename: String,
     pub data: Vec<u8>,
     pub container: gtk::Box,
 }
 
+impl PendingAttachment {
+    fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
 pub struct InputArea {
     buffer: gtk::TextBuffer,
     sending: bool,
-    pending_images: Vec<PendingImage>,
+    pending_attachments: Vec<PendingAttachment>,
     attachment_strip: gtk::FlowBox,
     char_count: i32,
+    vision_supported: bool,
+    // Message templates
+    templates: Vec<PromptTemplate>,
+    template_list: gtk::ListBox,
+    template_popover: gtk::Popover,
+    // Name of the template last loaded for editing, suggested when saving
+    editing_template: Option<String>,
+    // Slash command completions for the `/name` being typed
+    completions: Vec<&'static SlashCommand>,
+    command_list: gtk::ListBox,
+    command_popover: gtk::Popover,
 }
 
 #[derive(Debug)]
 pub enum InputAreaMsg {
     SendClicked,
     SetSending(bool),
-    AttachImage,
-    AddImageFromPath(PathBuf),
+    SetVisionSupported(bool),
+    AttachFile,
+    AddFileFromPath(PathBuf),
     RemoveAttachment(usize),
+    SetTemplates(Vec<PromptTemplate>),
+    UseTemplate(usize),
+    EditTemplate(usize),
+    DeleteTemplate(usize),
+    SaveAsTemplate,
+    InsertTemplate(PromptTemplate),
+    MoveCompletion(i32),     // rows to move the selection by
+    Complete(Option<usize>), // completion row; `None` for the selected one
+    DismissCompletions,
     // Internal
-    ImageFileSelected(PathBuf),
+    ShowTemplateForm(PromptTemplate, Option<String>), // template, clipboard text
+    InsertText(String),
+    FileSelected(PathBuf),
     TextChanged,
     PasteImage(Vec<u8>),
 }
@@ -37,7 +103,13 @@ pub enum InputAreaOutput {
     SendMessage {
         text: String,
         images: Vec<ImageAttachment>,
+        documents: Vec<DocumentAttachment>,
     },
+    SaveTemplate(String, String), // name, content
+    DeleteTemplate(String),       // template_id
+    RunCommand(String),
+    /// A file couldn't be attached; the message says why.
+    AttachFailed(String),
 }
 
 #[relm4::component(pub)]
@@ -64,10 +136,24 @@ impl Component for InputArea {
                 set_margin_end: 8,
                 set_margin_top: 4,
                 #[watch]
-                set_visible: !model.pending_images.is_empty(),
+                set_visible: !model.pending_attachments.is_empty(),
                 add_css_class: "attachment-strip",
             },
 
+            // Shown when the selected model can't see the attached images
+            gtk::Label {
+                set_label: "The selected model doesn't support images; attachments will be ignored",
+                set_halign: gtk::Align::Start,
+                set_wrap: true,
+                set_margin_start: 16,
+                set_margin_end: 16,
+                set_margin_top: 4,
+                add_css_class: "warning",
+                add_css_class: "caption",
+                #[watch]
+                set_visible: !model.vision_supported && model.pending_attachments.iter().any(PendingAttachment::is_image),
+            },
+
             // Input card
             gtk::Box {
                 set_orientation: gtk::Orientation::Vertical,
@@ -127,11 +213,20 @@ impl Component for InputArea {
                     // Attach button
                     gtk::Button {
                         set_icon_name: "list-add-symbolic",
-                        set_tooltip_text: Some("Attach image"),
+                        set_tooltip_text: Some("Attach file"),
                         set_halign: gtk::Align::Start,
                         add_css_class: "flat",
                         add_css_class: "circular",
-                        connect_clicked => InputAreaMsg::AttachImage,
+                        connect_clicked => InputAreaMsg::AttachFile,
+                    },
+
+                    // Template picker
+                    gtk::MenuButton {
+                        set_icon_name: "insert-text-symbolic",
+                        set_tooltip_text: Some("Templates"),
+                        add_css_class: "flat",
+                        add_css_class: "circular",
+                        set_popover: Some(&template_popover),
                     },
 
                     // Spacer
@@ -147,7 +242,7 @@ impl Component for InputArea {
                         add_css_class: "suggested-action",
                         add_css_class: "circular",
                         #[watch]
-                        set_sensitive: !model.sending && (model.buffer.char_count() > 0 || !model.pending_images.is_empty()),
+                        set_sensitive: !model.sending && (model.buffer.char_count() > 0 || !model.pending_attachments.is_empty()),
                         connect_clicked => InputAreaMsg::SendClicked,
                     },
                 },
@@ -175,16 +270,48 @@ impl Component for InputArea {
     ) -> ComponentParts<Self> {
         let buffer = gtk::TextBuffer::new(None::<&gtk::TextTagTable>);
         let attachment_strip = gtk::FlowBox::new();
+        let (template_popover, template_list) = build_template_popover(&sender);
+        let (command_popover, command_list) = build_command_popover(&sender);
 
         let model = Self {
             buffer: buffer.clone(),
             sending: false,
-            pending_images: Vec::new(),
+            pending_attachments: Vec::new(),
             attachment_strip: attachment_strip.clone(),
             char_count: 0,
+            vision_supported: true,
+            templates: Vec::new(),
+            template_list,
+            template_popover: template_popover.clone(),
+            editing_template: None,
+            completions: Vec::new(),
+            command_list,
+            command_popover: command_popover.clone(),
         };
 
         let widgets = view_output!();
+        command_popover.set_parent(&widgets.text_view);
+
+        // While completions show, arrows pick one, Tab completes it and Escape hides them
+        let sender_nav = sender.clone();
+        let popover_nav = command_popover.clone();
+        let nav_controller = gtk::EventControllerKey::new();
+        nav_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        nav_controller.connect_key_pressed(move |_, key, _code, _modifier| {
+            if !popover_nav.is_visible() {
+                return gtk::glib::Propagation::Proceed;
+            }
+            let msg = match key {
+                gtk::gdk::Key::Up => InputAreaMsg::MoveCompletion(-1),
+                gtk::gdk::Key::Down => InputAreaMsg::MoveCompletion(1),
+                gtk::gdk::Key::Tab => InputAreaMsg::Complete(None),
+                gtk::gdk::Key::Escape => InputAreaMsg::DismissCompletions,
+                _ => return gtk::glib::Propagation::Proceed,
+            };
+            sender_nav.input(msg);
+            gtk::glib::Propagation::Stop
+        });
+        widgets.text_view.add_controller(nav_controller);
 
         // Connect key press on text_view: Enter sends, Shift+Enter newline, Ctrl+V paste image
         let sender_key = sender.clone();
@@ -233,21 +360,41 @@ impl Component for InputArea {
             InputAreaMsg::SendClicked => {
                 let text = self.get_text();
                 let trimmed = text.trim().to_string();
-                let has_images = !self.pending_images.is_empty();
-
-                if (!trimmed.is_empty() || has_images) && !self.sending {
-                    let images: Vec<ImageAttachment> = self
-                        .pending_images
-                        .drain(..)
-                        .map(|pi| ImageAttachment {
-                            mime_type: pi.mime_type,
-                            data: pi.data,
-                        })
-                        .collect();
+                let has_attachments = !self.pending_attachments.is_empty();
+
+                if commands::parse(&trimmed).is_some() {
+                    let _ = sender.output(InputAreaOutput::RunCommand(trimmed));
+                    self.buffer.set_text("");
+                    return;
+                }
+                // Enter on a partial `/name` completes it instead of sending
+                if self.command_popover.is_visible() {
+                    sender.input(InputAreaMsg::Complete(None));
+                    return;
+                }
+
+                if (!trimmed.is_empty() || has_attachments) && !self.sending {
+                    let mut images = Vec::new();
+                    let mut documents = Vec::new();
+                    for pending in self.pending_attachments.drain(..) {
+                        if pending.is_image() {
+                            images.push(ImageAttachment {
+                                mime_type: pending.mime_type,
+                                data: pending.data,
+                            });
+                        } else {
+                            documents.push(DocumentAttachment {
+                                mime_type: pending.mime_type,
+                                filename: pending.filename,
+                                data: pending.data,
+                            });
+                        }
+                    }
 
                     let _ = sender.output(InputAreaOutput::SendMessage {
                         text: trimmed,
                         images,
+                        documents,
                     });
                     self.buffer.set_text("");
                     self.clear_attachment_strip();
@@ -256,18 +403,36 @@ impl Component for InputArea {
             InputAreaMsg::SetSending(sending) => {
                 self.sending = sending;
             }
-            InputAreaMsg::AttachImage => {
-                let dialog = gtk::FileDialog::builder().title("Attach Image").build();
-
-                // Set image file filter
-                let filter = gtk::FileFilter::new();
-                filter.set_name(Some("Images"));
-                filter.add_mime_type("image/png");
-                filter.add_mime_type("image/jpeg");
-                filter.add_mime_type("image/gif");
-                filter.add_mime_type("image/webp");
+            InputAreaMsg::SetVisionSupported(supported) => {
+                self.vision_supported = supported;
+            }
+            InputAreaMsg::AttachFile => {
+                let dialog = gtk::FileDialog::builder().title("Attach File").build();
+
+                // Set image and document file filters
+                let supported = gtk::FileFilter::new();
+                supported.set_name(Some("Supported files"));
+                let images = gtk::FileFilter::new();
+                images.set_name(Some("Images"));
+                for mime_type in ["image/png", "image/jpeg", "image/gif", "image/webp"] {
+                    supported.add_mime_type(mime_type);
+                    images.add_mime_type(mime_type);
+                }
+                let pdfs = gtk::FileFilter::new();
+                pdfs.set_name(Some("PDF documents"));
+                supported.add_mime_type("application/pdf");
+                pdfs.add_mime_type("application/pdf");
                 let filters = gio::ListStore::new::<gtk::FileFilter>();
-                filters.append(&filter);
+                let text = gtk::FileFilter::new();
+                text.set_name(Some("Text and source files"));
+                for extension in TEXT_EXTENSIONS {
+                    supported.add_suffix(extension);
+                    text.add_suffix(extension);
+                }
+                filters.append(&supported);
+                filters.append(&images);
+                filters.append(&pdfs);
+                filters.append(&text);
                 dialog.set_filters(Some(&filters));
 
                 let sender_dlg = sender.input_sender().clone();
@@ -275,9 +440,7 @@ impl Component for InputArea {
                     dialog.open(Some(&window), None::<&gio::Cancellable>, move |result| {
                         if let Ok(file) = result {
                             if let Some(path) = file.path() {
-                                sender_dlg
-                                    .send(InputAreaMsg::ImageFileSelected(path))
-                                    .unwrap();
+                                sender_dlg.send(InputAreaMsg::FileSelected(path)).unwrap();
                             }
                         }
                     });
@@ -285,13 +448,48 @@ impl Component for InputArea {
             }
             InputAreaMsg::TextChanged => {
                 self.char_count = self.buffer.char_count();
+                self.update_completions();
             }
-            InputAreaMsg::ImageFileSelected(path) | InputAreaMsg::AddImageFromPath(path) => {
-                self.add_image_from_path(path, &sender);
+            InputAreaMsg::MoveCompletion(delta) => {
+                let current = self
+                    .command_list
+                    .selected_row()
+                    .map(|row| row.index())
+                    .unwrap_or(0);
+                let last = self.completions.len() as i32 - 1;
+                if let Some(row) = self
+                    .command_list
+                    .row_at_index((current + delta).clamp(0, last.max(0)))
+                {
+                    self.command_list.select_row(Some(&row));
+                }
+            }
+            InputAreaMsg::Complete(index) => {
+                let index = index.or_else(|| {
+                    self.command_list
+                        .selected_row()
+                        .map(|row| row.index() as usize)
+                });
+                if let Some(command) = index.and_then(|i| self.completions.get(i)) {
+                    let text = if command.args.is_empty() {
+                        format!("/{}", command.name)
+                    } else {
+                        format!("/{} ", command.name)
+                    };
+                    self.buffer.set_text(&text);
+                    self.buffer.place_cursor(&self.buffer.end_iter());
+                }
+                self.command_popover.popdown();
+            }
+            InputAreaMsg::DismissCompletions => {
+                self.command_popover.popdown();
+            }
+            InputAreaMsg::FileSelected(path) | InputAreaMsg::AddFileFromPath(path) => {
+                self.add_file_from_path(path, &sender);
             }
             InputAreaMsg::RemoveAttachment(index) => {
-                if index < self.pending_images.len() {
-                    let removed = self.pending_images.remove(index);
+                if index < self.pending_attachments.len() {
+                    let removed = self.pending_attachments.remove(index);
                     // Remove the widget from the FlowBox
                     if let Some(parent) = removed.container.parent() {
                         if let Ok(child) = parent.downcast::<gtk::FlowBoxChild>() {
@@ -303,6 +501,49 @@ impl Component for InputArea {
             InputAreaMsg::PasteImage(png_data) => {
                 self.add_image_from_bytes(png_data, "image/png", "clipboard.png", &sender);
             }
+            InputAreaMsg::SetTemplates(templates) => {
+                self.templates = templates;
+                self.refresh_templates(&sender);
+            }
+            InputAreaMsg::UseTemplate(index) => {
+                self.template_popover.popdown();
+                if let Some(template) = self.templates.get(index) {
+                    self.insert_template(template.clone(), &sender, root);
+                }
+            }
+            InputAreaMsg::InsertTemplate(template) => {
+                self.insert_template(template, &sender, root);
+            }
+            InputAreaMsg::ShowTemplateForm(template, clipboard) => {
+                show_template_form(root, &template, clipboard, sender.input_sender().clone());
+            }
+            InputAreaMsg::InsertText(text) => {
+                self.buffer.insert_at_cursor(&text);
+            }
+            InputAreaMsg::EditTemplate(index) => {
+                self.template_popover.popdown();
+                if let Some(template) = self.templates.get(index) {
+                    self.buffer.set_text(&template.content);
+                    self.editing_template = Some(template.name.clone());
+                }
+            }
+            InputAreaMsg::DeleteTemplate(index) => {
+                if let Some(template) = self.templates.get(index) {
+                    let _ = sender.output(InputAreaOutput::DeleteTemplate(template.id.clone()));
+                }
+            }
+            InputAreaMsg::SaveAsTemplate => {
+                self.template_popover.popdown();
+                let content = self.get_text();
+                if content.trim().is_empty() {
+                    return;
+                }
+                let initial = self.editing_template.take().unwrap_or_default();
+                let output = sender.output_sender().clone();
+                ask_for_name(root, "Save as Template", &initial, "Save", move |name| {
+                    let _ = output.send(InputAreaOutput::SaveTemplate(name, content.clone()));
+                });
+            }
         }
     }
 }
@@ -314,39 +555,171 @@ impl InputArea {
         self.buffer.text(&start, &end, false).to_string()
     }
 
+    /// Insert a template, first asking for its variables if it has any.
+    fn insert_template(
+        &self,
+        template: PromptTemplate,
+        sender: &ComponentSender<Self>,
+        root: &gtk::Box,
+    ) {
+        let variables = template_variables(&template.content);
+        if variables.is_empty() {
+            self.buffer.insert_at_cursor(&template.content);
+        } else if variables.iter().any(|v| v == CLIPBOARD_VARIABLE) {
+            // Read the clipboard first so the form opens prefilled
+            let sender_form = sender.input_sender().clone();
+            root.clipboard()
+                .read_text_async(None::<&gio::Cancellable>, move |result| {
+                    let text = result.ok().flatten().map(|t| t.to_string());
+                    sender_form
+                        .send(InputAreaMsg::ShowTemplateForm(template, text))
+                        .unwrap();
+                });
+        } else {
+            sender.input(InputAreaMsg::ShowTemplateForm(template, None));
+        }
+    }
+
+    /// Show the commands matching a partial `/name`, or hide them once it's complete.
+    fn update_completions(&mut self) {
+        let completions = commands::completions(&self.get_text());
+        let unchanged = completions.len() == self.completions.len()
+            && completions
+                .iter()
+                .zip(&self.completions)
+                .all(|(a, b)| a.name == b.name);
+        if !unchanged {
+            self.completions = completions;
+            while let Some(row) = self.command_list.row_at_index(0) {
+                self.command_list.remove(&row);
+            }
+            for command in &self.completions {
+                self.command_list.append(&command_row(command));
+            }
+            if let Some(row) = self.command_list.row_at_index(0) {
+                self.command_list.select_row(Some(&row));
+            }
+        }
+
+        if self.completions.is_empty() {
+            self.command_popover.popdown();
+        } else if !self.command_popover.is_visible() {
+            self.command_popover.popup();
+        }
+    }
+
+    /// List the templates in the picker, each with edit and delete buttons.
+    fn refresh_templates(&self, sender: &ComponentSender<Self>) {
+        while let Some(row) = self.template_list.row_at_index(0) {
+            self.template_list.remove(&row);
+        }
+
+        for (index, template) in self.templates.iter().enumerate() {
+            let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+            let label = gtk::Label::builder()
+                .label(&template.name)
+                .halign(gtk::Align::Start)
+                .hexpand(true)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .max_width_chars(28)
+                .build();
+            row_box.append(&label);
+
+            for (icon, tooltip, msg) in [
+                (
+                    "document-edit-symbolic",
+                    "Edit",
+                    InputAreaMsg::EditTemplate as fn(usize) -> InputAreaMsg,
+                ),
+                (
+                    "user-trash-symbolic",
+                    "Delete",
+                    InputAreaMsg::DeleteTemplate,
+                ),
+            ] {
+                let button = gtk::Button::builder()
+                    .icon_name(icon)
+                    .tooltip_text(tooltip)
+                    .build();
+                button.add_css_class("flat");
+                let sender_btn = sender.input_sender().clone();
+                button.connect_clicked(move |_| {
+                    sender_btn.send(msg(index)).unwrap();
+                });
+                row_box.append(&button);
+            }
+
+            self.template_list.append(&row_box);
+        }
+    }
+
     fn clear_attachment_strip(&self) {
         while let Some(child) = self.attachment_strip.first_child() {
             self.attachment_strip.remove(&child);
         }
     }
 
-    fn add_image_from_path(&mut self, path: PathBuf, sender: &ComponentSender<Self>) {
+    fn add_file_from_path(&mut self, path: PathBuf, sender: &ComponentSender<Self>) {
         // Read file data
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("attachment")
+            .to_string();
+        let fail = |reason: &str| {
+            tracing::warn!("Not attaching {}: {}", filename, reason);
+            let _ = sender.output(InputAreaOutput::AttachFailed(format!(
+                "Couldn't attach {}: {}",
+                filename, reason
+            )));
+        };
+
+        // Determine MIME type from extension
+        let Some(mime_type) = attachment_mime_type(&path) else {
+            fail("unsupported file type");
+            return;
+        };
+
         let data = match std::fs::read(&path) {
             Ok(d) => d,
             Err(e) => {
-                tracing::error!("Failed to read image file: {}", e);
+                fail(&e.to_string());
                 return;
             }
         };
 
-        // Determine MIME type from extension
-        let mime_type = match path.extension().and_then(|e| e.to_str()) {
-            Some("png") => "image/png",
-            Some("jpg") | Some("jpeg") => "image/jpeg",
-            Some("gif") => "image/gif",
-            Some("webp") => "image/webp",
-            _ => "image/png", // fallback
+        if mime_type == "text/plain" {
+            if data.len() > MAX_TEXT_FILE_BYTES {
+                fail("file is too large");
+                return;
+            }
+            if data.contains(&0) {
+                fail("file looks binary");
+                return;
+            }
         }
-        .to_string();
 
-        let filename = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("image")
-            .to_string();
+        if mime_type.starts_with("image/") {
+            self.add_image_from_bytes(data, mime_type, &filename, sender);
+        } else {
+            self.add_document(data, mime_type, &filename, sender);
+        }
+    }
+
+    /// Add a non-image file, shown as an icon with its name.
+    fn add_document(
+        &mut self,
+        data: Vec<u8>,
+        mime_type: &str,
+        filename: &str,
+        sender: &ComponentSender<Self>,
+    ) {
+        let icon = gtk::Image::from_icon_name(document_icon_name(mime_type));
+        icon.set_pixel_size(48);
+        icon.set_size_request(80, 80);
+        icon.add_css_class("attachment-document");
 
-        self.add_image_from_bytes(data, &mime_type, &filename, sender);
+        self.append_attachment(&icon, data, mime_type, filename, sender);
     }
 
     fn add_image_from_bytes(
@@ -362,6 +735,10 @@ impl InputArea {
             Ok(t) => t,
             Err(e) => {
                 tracing::error!("Failed to create texture: {}", e);
+                let _ = sender.output(InputAreaOutput::AttachFailed(format!(
+                    "Couldn't attach {}: not a readable image",
+                    filename
+                )));
                 return;
             }
         };
@@ -370,6 +747,18 @@ impl InputArea {
         image.set_pixel_size(80);
         image.add_css_class("attachment-thumbnail");
 
+        self.append_attachment(&image, data, mime_type, filename, sender);
+    }
+
+    /// Add a preview to the strip with a remove button and the file name.
+    fn append_attachment(
+        &mut self,
+        preview: &gtk::Image,
+        data: Vec<u8>,
+        mime_type: &str,
+        filename: &str,
+        sender: &ComponentSender<Self>,
+    ) {
         // Build container with remove button
         let container = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
@@ -379,7 +768,7 @@ impl InputArea {
             .build();
 
         let overlay = gtk::Overlay::new();
-        overlay.set_child(Some(&image));
+        overlay.set_child(Some(preview));
 
         let remove_btn = gtk::Button::builder()
             .icon_name("window-close-symbolic")
@@ -389,7 +778,7 @@ impl InputArea {
         remove_btn.add_css_class("circular");
         remove_btn.add_css_class("osd");
 
-        let index = self.pending_images.len();
+        let index = self.pending_attachments.len();
         let sender_rm = sender.input_sender().clone();
         remove_btn.connect_clicked(move |_| {
             sender_rm
@@ -410,10 +799,164 @@ impl InputArea {
 
         self.attachment_strip.append(&container);
 
-        self.pending_images.push(PendingImage {
+        self.pending_attachments.push(PendingAttachment {
             mime_type: mime_type.to_string(),
+            filename: filename.to_string(),
             data,
             container,
         });
     }
 }
+
+/// The template picker: a list of templates above a button saving the input as one.
+fn build_template_popover(sender: &ComponentSender<InputArea>) -> (gtk::Popover, gtk::ListBox) {
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::None);
+    list.set_placeholder(Some(
+        &gtk::Label::builder()
+            .label("No templates yet")
+            .margin_top(12)
+            .margin_bottom(12)
+            .css_classes(["dim-label"])
+            .build(),
+    ));
+    let sender_list = sender.input_sender().clone();
+    list.connect_row_activated(move |_, row| {
+        sender_list
+            .send(InputAreaMsg::UseTemplate(row.index() as usize))
+            .unwrap();
+    });
+
+    let scroll = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .max_content_height(300)
+        .propagate_natural_height(true)
+        .child(&list)
+        .build();
+
+    let save_btn = gtk::Button::builder()
+        .label("Save Input as Template…")
+        .tooltip_text("Use {{name}} for fields to fill in, {{clipboard}} for the clipboard")
+        .build();
+    save_btn.add_css_class("flat");
+    let sender_save = sender.input_sender().clone();
+    save_btn.connect_clicked(move |_| {
+        sender_save.send(InputAreaMsg::SaveAsTemplate).unwrap();
+    });
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    content.set_width_request(260);
+    content.append(&scroll);
+    content.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+    content.append(&save_btn);
+
+    let popover = gtk::Popover::new();
+    popover.set_child(Some(&content));
+    (popover, list)
+}
+
+/// Completions for slash commands, shown above the text while a `/name` is typed.
+fn build_command_popover(sender: &ComponentSender<InputArea>) -> (gtk::Popover, gtk::ListBox) {
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::Single);
+    let sender_list = sender.input_sender().clone();
+    list.connect_row_activated(move |_, row| {
+        sender_list
+            .send(InputAreaMsg::Complete(Some(row.index() as usize)))
+            .unwrap();
+    });
+
+    let popover = gtk::Popover::builder()
+        .child(&list)
+        .position(gtk::PositionType::Top)
+        .halign(gtk::Align::Start)
+        .has_arrow(false)
+        // Keep typing in the text view while completions show
+        .autohide(false)
+        .can_focus(false)
+        .build();
+    (popover, list)
+}
+
+/// A completion row: `/name args` over the command's description.
+fn command_row(command: &SlashCommand) -> gtk::ListBoxRow {
+    let usage = gtk::Label::builder()
+        .label(format!("/{} {}", command.name, command.args).trim_end())
+        .halign(gtk::Align::Start)
+        .build();
+    usage.add_css_class("heading");
+    let description = gtk::Label::builder()
+        .label(command.description)
+        .halign(gtk::Align::Start)
+        .build();
+    description.add_css_class("dim-label");
+    description.add_css_class("caption");
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    content.set_margin_top(4);
+    content.set_margin_bottom(4);
+    content.append(&usage);
+    content.append(&description);
+
+    gtk::ListBoxRow::builder().child(&content).build()
+}
+
+/// Ask for the template's variables, then insert the filled-in text into the input.
+fn show_template_form(
+    widget: &gtk::Box,
+    template: &PromptTemplate,
+    clipboard: Option<String>,
+    sender: relm4::Sender<InputAreaMsg>,
+) {
+    let dialog = adw::AlertDialog::builder()
+        .heading(&template.name)
+        .body("Fill in the template's fields.")
+        .build();
+
+    let group = adw::PreferencesGroup::new();
+    let mut rows = Vec::new();
+    for variable in template_variables(&template.content) {
+        let row = adw::EntryRow::builder().title(&variable).build();
+        if variable == CLIPBOARD_VARIABLE {
+            row.set_text(clipboard.as_deref().unwrap_or_default());
+        }
+        group.add(&row);
+        rows.push((variable, row));
+    }
+    if let Some((_, first)) = rows.first() {
+        first.set_activates_default(true);
+    }
+
+    dialog.set_extra_child(Some(&group));
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("insert", "Insert");
+    dialog.set_response_appearance("insert", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("insert"));
+    dialog.set_close_response("cancel");
+
+    let content = template.content.clone();
+    dialog.connect_response(None, move |_dialog, response| {
+        if response == "insert" {
+            let values: HashMap<String, String> = rows
+                .iter()
+                .map(|(variable, row)| (variable.clone(), row.text().to_string()))
+                .collect();
+            sender
+                .send(InputAreaMsg::InsertText(fill_template(&content, &values)))
+                .unwrap();
+        }
+    });
+
+    if let Some(window) = widget.root().and_then(|r| r.downcast::<gtk::Window>().ok()) {
+        dialog.present(Some(&window));
+    }
+}
+
+/// Icon shown for a non-image attachment.
+pub fn document_icon_name(mime_type: &str) -> &'static str {
+    if mime_type == "application/pdf" {
+        "x-office-document-symbolic"
+    } else {
+        "text-x-generic-symbolic"
+    }
+}
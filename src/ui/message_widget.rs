@@ -1,8 +1,43 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use gtk::prelude::*;
 use relm4::prelude::*;
 
-use crate::models::{Message, Role};
-use crate::services::markdown::{parse_markdown, spans_to_pango_markup, MessageBlock};
+use crate::models::{BranchPosition, Message, Role};
+use crate::services::highlight;
+use crate::services::images::{self, ImageSource};
+use crate::services::markdown::{
+    open_block_start, parse_markdown, spans_to_pango_markup, spans_to_tex, ColumnAlignment,
+    Footnote, InlineSpan, ListItem, MessageBlock,
+};
+use crate::services::math::tex_to_markup;
+
+/// Tallest an image in a message is drawn; larger ones are scaled down.
+const MAX_IMAGE_HEIGHT: i32 = 400;
+
+/// Images decoded for messages, kept so re-rendering (as while streaming) doesn't
+/// load them again.
+const MAX_CACHED_IMAGES: usize = 32;
+
+/// Whether images from the web load without a click (Preferences → Appearance).
+static REMOTE_IMAGES_ALLOWED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_remote_images_allowed(allowed: bool) {
+    REMOTE_IMAGES_ALLOWED.store(allowed, Ordering::Relaxed);
+}
+
+enum CachedImage {
+    Loaded(gtk::gdk::Texture),
+    /// Downloading; these containers show the image when it arrives.
+    Loading(Vec<glib::WeakRef<gtk::Box>>),
+    Failed,
+}
+
+thread_local! {
+    static IMAGES: RefCell<HashMap<String, CachedImage>> = RefCell::new(HashMap::new());
+}
 
 /// Wrapper struct for MessageWidget initialization.
 pub struct MessageWidgetInit {
@@ -14,11 +49,19 @@ pub struct MessageWidget {
     pub message: Message,
     show_date_separator: Option<String>,
     content_box: gtk::Box,
+    thinking_expander: gtk::Expander,
+    thinking_label: gtk::Label,
     bubble: gtk::Box,
     action_bar: gtk::Box,
     outer_box: gtk::Box, // outermost container (includes date separator)
     message_row: Option<gtk::Box>,
+    role_label: Option<gtk::Label>,
+    branch_box: gtk::Box,
     is_user: bool,
+    // Streaming: bytes of content rendered as complete blocks, and the widgets of the
+    // still-open blocks after them
+    streamed_len: usize,
+    open_widgets: Vec<gtk::Widget>,
     // Edit mode state
     editing: bool,
     edit_buffer: Option<gtk::TextBuffer>,
@@ -28,9 +71,13 @@ pub struct MessageWidget {
 
 #[derive(Debug)]
 pub enum MessageWidgetMsg {
-    UpdateContent(String),
+    AppendContent(String),  // streamed text
+    AppendThinking(String), // streamed reasoning
+    SetThinking(String),
     StreamingComplete,
-    SetTokens(Option<i64>, Option<i64>),
+    SetTokens(Option<i64>, Option<i64>, Option<i64>), // in, out, thinking
+    SetFallbackModel(String),
+    SetBranch(BranchPosition),
     // Edit
     StartEdit,
     SaveEdit,
@@ -48,6 +95,8 @@ pub enum MessageWidgetOutput {
     Regenerate(String),          // message_id
     EditMessage(String, String), // message_id, new_content
     CopyFullContent(String),     // content
+    SwitchBranch(String, usize), // message_id, sibling index
+    Fork(String),                // message_id
 }
 
 #[relm4::factory(pub)]
@@ -75,6 +124,25 @@ impl FactoryComponent for MessageWidget {
             .margin_bottom(8)
             .build();
 
+        // Reasoning stays collapsed so the answer remains the focus
+        let thinking_label = gtk::Label::builder()
+            .halign(gtk::Align::Start)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .selectable(true)
+            .build();
+        thinking_label.add_css_class("thinking-text");
+
+        let thinking_expander = gtk::Expander::builder()
+            .label("Thinking")
+            .expanded(false)
+            .child(&thinking_label)
+            .margin_start(8)
+            .margin_end(8)
+            .visible(false)
+            .build();
+        thinking_expander.add_css_class("thinking-expander");
+
         let bubble = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .spacing(4)
@@ -97,16 +165,30 @@ impl FactoryComponent for MessageWidget {
             .spacing(0)
             .build();
 
+        // "< 2/3 >" switcher between edits or regenerations of this message
+        let branch_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(2)
+            .visible(false)
+            .build();
+        branch_box.add_css_class("message-branch-switcher");
+
         let is_user = init.message.role == Role::User;
         Self {
             message: init.message,
             show_date_separator: init.show_date_separator,
             content_box,
+            thinking_expander,
+            thinking_label,
             bubble,
             action_bar,
             outer_box,
             message_row: None,
+            role_label: None,
+            branch_box,
             is_user,
+            streamed_len: 0,
+            open_widgets: Vec::new(),
             editing: false,
             edit_buffer: None,
             edit_container: None,
@@ -164,6 +246,10 @@ impl FactoryComponent for MessageWidget {
         role_label.add_css_class("caption");
         role_label.add_css_class("dim-label");
         role_time_box.append(&role_label);
+        self.role_label = Some(role_label);
+
+        self.show_branch(&sender);
+        role_time_box.append(&self.branch_box);
 
         let time_label = gtk::Label::builder()
             .label(self.message.created_at.format("%H:%M").to_string())
@@ -177,13 +263,33 @@ impl FactoryComponent for MessageWidget {
         self.bubble.append(&role_time_box);
 
         if is_user {
-            // Render attachment thumbnails for user messages
+            // Render attachments for user messages
             if !self.message.attachments.is_empty() {
                 let images_box = gtk::Box::builder()
                     .orientation(gtk::Orientation::Horizontal)
                     .spacing(4)
                     .build();
                 for att in &self.message.attachments {
+                    if !att.mime_type.starts_with("image/") {
+                        // Documents show as an icon with the file name
+                        let doc_box = gtk::Box::builder()
+                            .orientation(gtk::Orientation::Horizontal)
+                            .spacing(6)
+                            .build();
+                        doc_box.add_css_class("attachment-document");
+                        doc_box.append(&gtk::Image::from_icon_name(
+                            crate::ui::input_area::document_icon_name(&att.mime_type),
+                        ));
+                        let name_label = gtk::Label::builder()
+                            .label(att.filename.as_deref().unwrap_or("Document"))
+                            .max_width_chars(24)
+                            .ellipsize(gtk::pango::EllipsizeMode::Middle)
+                            .build();
+                        doc_box.append(&name_label);
+                        images_box.append(&doc_box);
+                        continue;
+                    }
+
                     let bytes = glib::Bytes::from(&att.data);
                     if let Ok(texture) = gtk::gdk::Texture::from_bytes(&bytes) {
                         let image = gtk::Image::from_paintable(Some(&texture));
@@ -204,6 +310,12 @@ impl FactoryComponent for MessageWidget {
                 .build();
             self.content_box.append(&label);
         } else {
+            if let Some(thinking) = &self.message.thinking {
+                self.thinking_label.set_text(thinking);
+                self.thinking_expander.set_visible(true);
+            }
+            self.bubble.append(&self.thinking_expander);
+
             render_markdown_blocks(&self.content_box, &self.message.content);
         }
 
@@ -211,16 +323,7 @@ impl FactoryComponent for MessageWidget {
 
         // Token info for assistant messages
         if !is_user {
-            if let (Some(ti), Some(to)) = (self.message.tokens_in, self.message.tokens_out) {
-                let token_label = gtk::Label::builder()
-                    .label(format!("\u{2193}{} \u{2191}{} tokens", ti, to))
-                    .halign(gtk::Align::End)
-                    .margin_end(8)
-                    .margin_bottom(2)
-                    .build();
-                token_label.add_css_class("token-info");
-                token_label.add_css_class("dim-label");
-                token_label.add_css_class("caption");
+            if let Some(token_label) = token_label(&self.message) {
                 self.bubble.append(&token_label);
             }
         }
@@ -271,6 +374,20 @@ impl FactoryComponent for MessageWidget {
             self.action_bar.append(&edit_btn);
         }
 
+        // Fork button for all messages
+        let msg_id = self.message.id.clone();
+        let fork_btn = gtk::Button::builder()
+            .icon_name("document-new-symbolic")
+            .tooltip_text("Fork from here")
+            .build();
+        fork_btn.add_css_class("flat");
+        fork_btn.add_css_class("circular");
+        let sender_fork = sender.output_sender().clone();
+        fork_btn.connect_clicked(move |_| {
+            let _ = sender_fork.send(MessageWidgetOutput::Fork(msg_id.clone()));
+        });
+        self.action_bar.append(&fork_btn);
+
         overlay.add_overlay(&self.action_bar);
         self.action_bar.set_halign(gtk::Align::End);
         self.action_bar.set_valign(gtk::Align::Start);
@@ -314,29 +431,47 @@ impl FactoryComponent for MessageWidget {
 
     fn update(&mut self, msg: Self::Input, sender: FactorySender<Self>) {
         match msg {
-            MessageWidgetMsg::UpdateContent(text) => {
-                self.message.content = text.clone();
-                render_markdown_blocks(&self.content_box, &text);
+            MessageWidgetMsg::AppendContent(text) => {
+                self.message.content.push_str(&text);
+                self.render_streamed();
+            }
+            MessageWidgetMsg::AppendThinking(text) => {
+                let thinking = self.message.thinking.get_or_insert_with(String::new);
+                thinking.push_str(&text);
+                self.thinking_label.set_text(thinking);
+                self.thinking_expander.set_visible(!thinking.is_empty());
+            }
+            MessageWidgetMsg::SetThinking(thinking) => {
+                self.thinking_label.set_text(&thinking);
+                self.thinking_expander.set_visible(!thinking.is_empty());
+                self.message.thinking = Some(thinking).filter(|t| !t.is_empty());
             }
             MessageWidgetMsg::StreamingComplete => {
-                // Final re-render already done via UpdateContent
+                // One full render, for what only the whole reply decides, like
+                // footnote numbers and reference links
+                self.streamed_len = 0;
+                self.open_widgets.clear();
+                render_markdown_blocks(&self.content_box, &self.message.content);
             }
-            MessageWidgetMsg::SetTokens(tokens_in, tokens_out) => {
+            MessageWidgetMsg::SetTokens(tokens_in, tokens_out, thinking_tokens) => {
                 self.message.tokens_in = tokens_in;
                 self.message.tokens_out = tokens_out;
-                if let (Some(ti), Some(to)) = (tokens_in, tokens_out) {
-                    let token_label = gtk::Label::builder()
-                        .label(format!("\u{2193}{} \u{2191}{} tokens", ti, to))
-                        .halign(gtk::Align::End)
-                        .margin_end(8)
-                        .margin_bottom(2)
-                        .build();
-                    token_label.add_css_class("token-info");
-                    token_label.add_css_class("dim-label");
-                    token_label.add_css_class("caption");
+                self.message.thinking_tokens = thinking_tokens;
+                if let Some(token_label) = token_label(&self.message) {
                     self.bubble.append(&token_label);
                 }
             }
+            MessageWidgetMsg::SetFallbackModel(model) => {
+                if let Some(label) = &self.role_label {
+                    label.set_text(&format!("{} (fallback)", model));
+                    label.set_tooltip_text(Some("Answered by a fallback model"));
+                }
+                self.message.model = Some(model);
+            }
+            MessageWidgetMsg::SetBranch(branch) => {
+                self.message.branch = Some(branch);
+                self.show_branch(&sender);
+            }
             MessageWidgetMsg::StartEdit => {
                 if self.editing {
                     return;
@@ -442,6 +577,26 @@ impl FactoryComponent for MessageWidget {
 }
 
 impl MessageWidget {
+    /// Render streamed text. Blocks that are now complete get widgets for good; only
+    /// the trailing, still-open blocks are parsed and built again.
+    fn render_streamed(&mut self) {
+        for widget in self.open_widgets.drain(..) {
+            self.content_box.remove(&widget);
+        }
+
+        let content = &self.message.content;
+        let open_start = self.streamed_len + open_block_start(&content[self.streamed_len..]);
+        for block in parse_markdown(&content[self.streamed_len..open_start]) {
+            self.content_box.append(&block_to_widget(&block));
+        }
+        for block in parse_markdown(&content[open_start..]) {
+            let widget = block_to_widget(&block);
+            self.content_box.append(&widget);
+            self.open_widgets.push(widget);
+        }
+        self.streamed_len = open_start;
+    }
+
     fn cleanup_edit(&mut self) {
         self.editing = false;
         if let Some(container) = self.edit_container.take() {
@@ -451,6 +606,55 @@ impl MessageWidget {
         self.content_box.set_visible(true);
         self.original_content_visible = true;
     }
+
+    /// Rebuild the branch switcher for `self.message.branch`.
+    fn show_branch(&self, sender: &FactorySender<Self>) {
+        while let Some(child) = self.branch_box.first_child() {
+            self.branch_box.remove(&child);
+        }
+
+        let Some(branch) = self.message.branch else {
+            self.branch_box.set_visible(false);
+            return;
+        };
+
+        let button = |icon: &str, tooltip: &str, target: Option<usize>| {
+            let btn = gtk::Button::builder()
+                .icon_name(icon)
+                .tooltip_text(tooltip)
+                .sensitive(target.is_some())
+                .build();
+            btn.add_css_class("flat");
+            btn.add_css_class("circular");
+            if let Some(target) = target {
+                let msg_id = self.message.id.clone();
+                let output = sender.output_sender().clone();
+                btn.connect_clicked(move |_| {
+                    let _ = output.send(MessageWidgetOutput::SwitchBranch(msg_id.clone(), target));
+                });
+            }
+            btn
+        };
+
+        let previous = button(
+            "go-previous-symbolic",
+            "Previous version",
+            branch.index.checked_sub(1),
+        );
+        let next = button(
+            "go-next-symbolic",
+            "Next version",
+            Some(branch.index + 1).filter(|i| *i < branch.count),
+        );
+        let position = gtk::Label::new(Some(&format!("{}/{}", branch.index + 1, branch.count)));
+        position.add_css_class("caption");
+        position.add_css_class("dim-label");
+
+        self.branch_box.append(&previous);
+        self.branch_box.append(&position);
+        self.branch_box.append(&next);
+        self.branch_box.set_visible(true);
+    }
 }
 
 fn render_markdown_blocks(content_box: &gtk::Box, text: &str) {
@@ -482,6 +686,8 @@ fn block_to_widget(block: &MessageBlock) -> gtk::Widget {
                 .use_markup(true)
                 .build();
             label.set_markup(&markup);
+            add_copy_latex_menu(&label, spans);
+            connect_links(&label);
             label.upcast()
         }
         MessageBlock::CodeBlock { language, code } => build_code_block(language.as_deref(), code),
@@ -495,6 +701,8 @@ fn block_to_widget(block: &MessageBlock) -> gtk::Widget {
                 .use_markup(true)
                 .build();
             label.set_markup(&markup);
+            add_copy_latex_menu(&label, spans);
+            connect_links(&label);
             let css_class = match level {
                 1 => "heading-1",
                 2 => "heading-2",
@@ -527,6 +735,14 @@ fn block_to_widget(block: &MessageBlock) -> gtk::Widget {
                 .build();
             sep.upcast()
         }
+        MessageBlock::Table {
+            alignments,
+            header,
+            rows,
+        } => build_table(alignments, header, rows),
+        MessageBlock::Footnotes(footnotes) => build_footnotes(footnotes),
+        MessageBlock::Math(tex) => build_math_block(tex),
+        MessageBlock::Image { url, alt } => build_image(url, alt),
     }
 }
 
@@ -590,43 +806,464 @@ fn build_code_block(language: Option<&str>, code: &str) -> gtk::Widget {
     text_view.buffer().set_text(code);
     text_view.add_css_class("code-block-content");
 
+    if let Some(language) = language.filter(|l| !l.is_empty()) {
+        // Recolor when the app switches between light and dark
+        let style_manager = adw::StyleManager::default();
+        let buffer = text_view.buffer();
+        apply_highlighting(&buffer, language, style_manager.is_dark());
+
+        let weak_buffer = buffer.downgrade();
+        let language = language.to_string();
+        let handler = style_manager.connect_dark_notify(move |manager| {
+            if let Some(buffer) = weak_buffer.upgrade() {
+                apply_highlighting(&buffer, &language, manager.is_dark());
+            }
+        });
+        let handler = std::cell::Cell::new(Some(handler));
+        text_view.connect_destroy(move |_| {
+            if let Some(handler) = handler.take() {
+                style_manager.disconnect(handler);
+            }
+        });
+    }
+
     outer.append(&text_view);
 
     outer.upcast()
 }
 
-fn build_list(items: &[Vec<MessageBlock>], ordered: bool) -> gtk::Widget {
+/// Color the code in `buffer` with tags, one per distinct style.
+fn apply_highlighting(buffer: &gtk::TextBuffer, language: &str, dark: bool) {
+    let (start, end) = buffer.bounds();
+    buffer.remove_all_tags(&start, &end);
+    let code = buffer.text(&start, &end, false);
+
+    let tags = buffer.tag_table();
+    for span in highlight::highlight(&code, language, dark) {
+        let (r, g, b) = span.color.unwrap_or_default();
+        let name = format!(
+            "hl-{}-{:02x}{:02x}{:02x}-{}{}",
+            span.color.is_some() as u8,
+            r,
+            g,
+            b,
+            span.bold as u8,
+            span.italic as u8
+        );
+        let tag = tags.lookup(&name).unwrap_or_else(|| {
+            let tag = gtk::TextTag::new(Some(&name));
+            if span.color.is_some() {
+                tag.set_foreground_rgba(Some(&gtk::gdk::RGBA::new(
+                    r as f32 / 255.0,
+                    g as f32 / 255.0,
+                    b as f32 / 255.0,
+                    1.0,
+                )));
+            }
+            if span.bold {
+                tag.set_weight(700);
+            }
+            if span.italic {
+                tag.set_style(gtk::pango::Style::Italic);
+            }
+            tags.add(&tag);
+            tag
+        });
+        buffer.apply_tag(
+            &tag,
+            &buffer.iter_at_offset(span.start as i32),
+            &buffer.iter_at_offset(span.end as i32),
+        );
+    }
+}
+
+fn build_math_block(tex: &str) -> gtk::Widget {
+    let math_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(8)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    math_box.add_css_class("math-display");
+
+    let label = gtk::Label::builder()
+        .hexpand(true)
+        .justify(gtk::Justification::Center)
+        .wrap(true)
+        .wrap_mode(gtk::pango::WrapMode::WordChar)
+        .selectable(true)
+        .use_markup(true)
+        .build();
+    label.set_markup(&tex_to_markup(tex, true));
+    set_copy_latex_menu(&label, tex.to_string());
+    math_box.append(&label);
+
+    let copy_button = gtk::Button::builder()
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text("Copy LaTeX")
+        .valign(gtk::Align::Center)
+        .build();
+    copy_button.add_css_class("flat");
+    copy_button.add_css_class("circular");
+    let tex = tex.to_string();
+    copy_button.connect_clicked(move |_| copy_to_clipboard(&tex));
+    math_box.append(&copy_button);
+
+    math_box.upcast()
+}
+
+/// Token counts under an assistant message, with reasoning apart when the provider
+/// reports it.
+fn token_label(message: &Message) -> Option<gtk::Label> {
+    let (Some(tokens_in), Some(tokens_out)) = (message.tokens_in, message.tokens_out) else {
+        return None;
+    };
+    let mut text = format!("\u{2193}{} \u{2191}{} tokens", tokens_in, tokens_out);
+    if let Some(thinking) = message.thinking_tokens.filter(|t| *t > 0) {
+        text.push_str(&format!(" \u{b7} {} thinking", thinking));
+    }
+    let label = gtk::Label::builder()
+        .label(text)
+        .halign(gtk::Align::End)
+        .margin_end(8)
+        .margin_bottom(2)
+        .build();
+    label.add_css_class("token-info");
+    label.add_css_class("dim-label");
+    label.add_css_class("caption");
+    Some(label)
+}
+
+fn build_image(url: &str, alt: &str) -> gtk::Widget {
+    let container = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .halign(gtk::Align::Start)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    container.add_css_class("message-image");
+
+    let cached = IMAGES.with_borrow_mut(|cache| match cache.get_mut(url) {
+        Some(CachedImage::Loaded(texture)) => Some(Ok(texture.clone())),
+        Some(CachedImage::Loading(waiting)) => {
+            waiting.push(container.downgrade());
+            Some(Err(true))
+        }
+        Some(CachedImage::Failed) => Some(Err(false)),
+        None => None,
+    });
+    match cached {
+        Some(Ok(texture)) => show_image(&container, &texture, alt),
+        Some(Err(true)) => container.append(&gtk::Spinner::builder().spinning(true).build()),
+        Some(Err(false)) => container.append(&image_link(url, alt)),
+        None => match images::image_source(url) {
+            Some(ImageSource::Data(bytes)) => {
+                let texture = gtk::gdk::Texture::from_bytes(&glib::Bytes::from_owned(bytes));
+                finish_image(&container, url, alt, texture.map_err(|e| e.to_string()));
+            }
+            Some(ImageSource::File(path)) => load_image(
+                &container,
+                url.to_string(),
+                alt.to_string(),
+                images::read_local_image(path),
+            ),
+            Some(ImageSource::Remote(url)) => {
+                if REMOTE_IMAGES_ALLOWED.load(Ordering::Relaxed) {
+                    load_remote_image(&container, url, alt.to_string());
+                } else {
+                    container.append(&remote_image_placeholder(&container, &url, alt));
+                }
+            }
+            None => container.append(&image_link(url, alt)),
+        },
+    }
+
+    container.upcast()
+}
+
+/// A button that downloads the image when clicked, for remote images that don't
+/// load on their own.
+fn remote_image_placeholder(container: &gtk::Box, url: &str, alt: &str) -> gtk::Button {
+    let host = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest.split('/').next().unwrap_or(rest));
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    content.append(&gtk::Image::from_icon_name("image-x-generic-symbolic"));
+    content.append(
+        &gtk::Label::builder()
+            .label(if alt.is_empty() {
+                format!("Load image from {}", host)
+            } else {
+                format!("Load “{}” from {}", alt, host)
+            })
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .build(),
+    );
+
+    let button = gtk::Button::builder()
+        .child(&content)
+        .tooltip_text(url)
+        .build();
+    let container = container.downgrade();
+    let url = url.to_string();
+    let alt = alt.to_string();
+    button.connect_clicked(move |_| {
+        if let Some(container) = container.upgrade() {
+            load_remote_image(&container, url.clone(), alt.clone());
+        }
+    });
+    button
+}
+
+/// Download a remote image into `container`.
+fn load_remote_image(container: &gtk::Box, url: String, alt: String) {
+    let fetch_url = url.clone();
+    let read = async move { images::fetch_remote_image(&fetch_url).await };
+    load_image(container, url, alt, read);
+}
+
+/// Read and decode an image off the main thread, showing a spinner meanwhile.
+/// Containers asking for the same image while it loads wait for the same load.
+fn load_image<F>(container: &gtk::Box, url: String, alt: String, read: F)
+where
+    F: std::future::Future<Output = Result<Vec<u8>, String>> + Send + 'static,
+{
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    container.append(&gtk::Spinner::builder().spinning(true).build());
+    let already_loading = IMAGES.with_borrow_mut(|cache| match cache.get_mut(&url) {
+        Some(CachedImage::Loading(waiting)) => {
+            waiting.push(container.downgrade());
+            true
+        }
+        _ => {
+            cache.insert(
+                url.clone(),
+                CachedImage::Loading(vec![container.downgrade()]),
+            );
+            false
+        }
+    });
+    if already_loading {
+        return;
+    }
+
+    let load = relm4::spawn(async move {
+        let bytes = read.await?;
+        // Decoding a texture is thread-safe
+        tokio::task::spawn_blocking(move || {
+            gtk::gdk::Texture::from_bytes(&glib::Bytes::from_owned(bytes))
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    });
+    glib::spawn_future_local(async move {
+        let texture = match load.await {
+            Ok(texture) => texture,
+            Err(e) => Err(e.to_string()),
+        };
+        let waiting = IMAGES.with_borrow_mut(|cache| match cache.remove(&url) {
+            Some(CachedImage::Loading(waiting)) => waiting,
+            _ => Vec::new(),
+        });
+        for container in waiting.iter().filter_map(|c| c.upgrade()) {
+            finish_image(&container, &url, &alt, texture.clone());
+        }
+        if waiting.is_empty() {
+            cache_image(&url, texture);
+        }
+    });
+}
+
+/// Show a loaded image, or a link to it when it couldn't be loaded, and remember the
+/// outcome.
+fn finish_image(
+    container: &gtk::Box,
+    url: &str,
+    alt: &str,
+    texture: Result<gtk::gdk::Texture, String>,
+) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    match &texture {
+        Ok(texture) => show_image(container, texture, alt),
+        Err(e) => {
+            tracing::warn!("Failed to load image {}: {}", url, e);
+            container.append(&image_link(url, alt));
+        }
+    }
+    cache_image(url, texture);
+}
+
+fn cache_image(url: &str, texture: Result<gtk::gdk::Texture, String>) {
+    IMAGES.with_borrow_mut(|cache| {
+        if cache.len() >= MAX_CACHED_IMAGES {
+            cache.retain(|_, image| matches!(image, CachedImage::Loading(_)));
+        }
+        let image = match texture {
+            Ok(texture) => CachedImage::Loaded(texture),
+            Err(_) => CachedImage::Failed,
+        };
+        cache.insert(url.to_string(), image);
+    });
+}
+
+fn show_image(container: &gtk::Box, texture: &gtk::gdk::Texture, alt: &str) {
+    let picture = gtk::Picture::builder()
+        .paintable(texture)
+        .can_shrink(true)
+        .content_fit(gtk::ContentFit::Contain)
+        .halign(gtk::Align::Start)
+        .height_request(texture.height().min(MAX_IMAGE_HEIGHT))
+        .build();
+    if !alt.is_empty() {
+        picture.set_alternative_text(Some(alt));
+        picture.set_tooltip_text(Some(alt));
+    }
+    container.append(&picture);
+}
+
+/// A link standing in for an image that can't be shown.
+fn image_link(url: &str, alt: &str) -> gtk::Label {
+    let url_markup = glib::markup_escape_text(url);
+    let text = if alt.is_empty() { url } else { alt };
+    let label = gtk::Label::builder()
+        .halign(gtk::Align::Start)
+        .wrap(true)
+        .wrap_mode(gtk::pango::WrapMode::WordChar)
+        .use_markup(true)
+        .build();
+    label.set_markup(&format!(
+        "<a href=\"{}\" title=\"{}\">{}</a>",
+        url_markup,
+        url_markup,
+        glib::markup_escape_text(text)
+    ));
+    connect_links(&label);
+    label
+}
+
+/// Ask before opening links in a label, since a link's text can hide where it goes.
+/// Links without a scheme, like `#section`, lead nowhere outside the message.
+fn connect_links(label: &gtk::Label) {
+    label.connect_activate_link(|label, uri| {
+        if glib::Uri::peek_scheme(uri).is_some() {
+            confirm_open_link(label, uri);
+        }
+        glib::Propagation::Stop
+    });
+}
+
+fn confirm_open_link(widget: &gtk::Label, uri: &str) {
+    use adw::prelude::{AdwDialogExt, AlertDialogExt};
+
+    let Some(window) = widget.root().and_then(|r| r.downcast::<gtk::Window>().ok()) else {
+        return;
+    };
+
+    let dialog = adw::AlertDialog::builder()
+        .heading("Open Link?")
+        .body(uri)
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("open", "Open");
+    dialog.set_response_appearance("open", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+
+    let uri = uri.to_string();
+    let parent = window.clone();
+    dialog.connect_response(None, move |_dialog, response| {
+        if response == "open" {
+            gtk::UriLauncher::new(&uri).launch(
+                Some(&parent),
+                None::<&gtk::gio::Cancellable>,
+                |result| {
+                    if let Err(e) = result {
+                        tracing::warn!("Failed to open link: {}", e);
+                    }
+                },
+            );
+        }
+    });
+    dialog.present(Some(&window));
+}
+
+/// Offer "Copy as LaTeX" in the context menu of a label showing inline math.
+fn add_copy_latex_menu(label: &gtk::Label, spans: &[InlineSpan]) {
+    if spans.iter().any(|span| span.math) {
+        set_copy_latex_menu(label, spans_to_tex(spans));
+    }
+}
+
+fn set_copy_latex_menu(label: &gtk::Label, latex: String) {
+    let action = gtk::gio::SimpleAction::new("copy-latex", None);
+    action.connect_activate(move |_, _| copy_to_clipboard(&latex));
+    let actions = gtk::gio::SimpleActionGroup::new();
+    actions.add_action(&action);
+    label.insert_action_group("math", Some(&actions));
+
+    let menu = gtk::gio::Menu::new();
+    menu.append(Some("Copy as LaTeX"), Some("math.copy-latex"));
+    label.set_extra_menu(Some(&menu));
+}
+
+fn copy_to_clipboard(text: &str) {
+    if let Some(display) = gtk::gdk::Display::default() {
+        display.clipboard().set_text(text);
+    }
+}
+
+fn build_list(items: &[ListItem], ordered: bool) -> gtk::Widget {
     let list_box = gtk::Box::builder()
         .orientation(gtk::Orientation::Vertical)
         .spacing(2)
         .margin_start(4)
         .build();
 
-    for (i, item_blocks) in items.iter().enumerate() {
+    for (i, item) in items.iter().enumerate() {
         let item_row = gtk::Box::builder()
             .orientation(gtk::Orientation::Horizontal)
             .spacing(4)
             .build();
 
-        let bullet_text = if ordered {
-            format!("{}.", i + 1)
+        if let Some(checked) = item.task {
+            // Shows the state only; clicks go nowhere
+            let check = gtk::CheckButton::builder()
+                .active(checked)
+                .can_target(false)
+                .can_focus(false)
+                .valign(gtk::Align::Start)
+                .build();
+            check.add_css_class("task-check");
+            item_row.append(&check);
         } else {
-            "\u{2022}".to_string()
-        };
+            let bullet_text = if ordered {
+                format!("{}.", i + 1)
+            } else {
+                "\u{2022}".to_string()
+            };
 
-        let bullet = gtk::Label::builder()
-            .label(&bullet_text)
-            .valign(gtk::Align::Start)
-            .build();
-        bullet.add_css_class("list-bullet");
-        item_row.append(&bullet);
+            let bullet = gtk::Label::builder()
+                .label(&bullet_text)
+                .valign(gtk::Align::Start)
+                .build();
+            bullet.add_css_class("list-bullet");
+            item_row.append(&bullet);
+        }
 
         let item_content = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .spacing(2)
             .build();
 
-        for block in item_blocks {
+        for block in &item.blocks {
             let widget = block_to_widget(block);
             item_content.append(&widget);
         }
@@ -637,3 +1274,96 @@ fn build_list(items: &[Vec<MessageBlock>], ordered: bool) -> gtk::Widget {
 
     list_box.upcast()
 }
+
+fn build_table(
+    alignments: &[ColumnAlignment],
+    header: &[Vec<InlineSpan>],
+    rows: &[Vec<Vec<InlineSpan>>],
+) -> gtk::Widget {
+    let grid = gtk::Grid::builder()
+        .column_spacing(0)
+        .row_spacing(0)
+        .build();
+    grid.add_css_class("markdown-table");
+
+    let cell = |spans: &[InlineSpan], column: usize| {
+        let xalign = match alignments.get(column) {
+            Some(ColumnAlignment::Center) => 0.5,
+            Some(ColumnAlignment::Right) => 1.0,
+            _ => 0.0,
+        };
+        let label = gtk::Label::builder()
+            .xalign(xalign)
+            .hexpand(true)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .max_width_chars(40)
+            .selectable(true)
+            .use_markup(true)
+            .build();
+        label.set_markup(&spans_to_pango_markup(spans));
+        add_copy_latex_menu(&label, spans);
+        connect_links(&label);
+        label.add_css_class("table-cell");
+        label
+    };
+
+    for (column, spans) in header.iter().enumerate() {
+        let label = cell(spans, column);
+        label.add_css_class("table-header");
+        grid.attach(&label, column as i32, 0, 1, 1);
+    }
+    for (row, cells) in rows.iter().enumerate() {
+        for (column, spans) in cells.iter().enumerate() {
+            grid.attach(&cell(spans, column), column as i32, row as i32 + 1, 1, 1);
+        }
+    }
+
+    // Wide tables scroll sideways instead of stretching the message
+    let scrolled = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Automatic)
+        .vscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_width(true)
+        .propagate_natural_height(true)
+        .halign(gtk::Align::Start)
+        .margin_top(4)
+        .margin_bottom(4)
+        .child(&grid)
+        .build();
+    scrolled.upcast()
+}
+
+fn build_footnotes(footnotes: &[Footnote]) -> gtk::Widget {
+    let footnotes_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(2)
+        .margin_top(4)
+        .build();
+    footnotes_box.add_css_class("footnotes");
+    footnotes_box.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+
+    for footnote in footnotes {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(4)
+            .build();
+        let number = gtk::Label::builder()
+            .label(format!("{}.", footnote.number))
+            .valign(gtk::Align::Start)
+            .build();
+        number.add_css_class("list-bullet");
+        row.append(&number);
+
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(2)
+            .build();
+        for block in &footnote.blocks {
+            content.append(&block_to_widget(block));
+        }
+        row.append(&content);
+        footnotes_box.append(&row);
+    }
+
+    footnotes_box.upcast()
+}
@@ -1,6 +1,7 @@
 pub mod account_selector;
 pub mod chat_view;
 pub mod dialogs;
+pub mod generation_params;
 pub mod input_area;
 pub mod message_widget;
 pub mod onboarding;
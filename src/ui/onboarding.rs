@@ -92,6 +92,14 @@ impl AsyncComponent for OnboardingWindow {
                                 connect_clicked => OnboardingMsg::SelectProvider(ProviderId::Claude),
                             },
 
+                            gtk::Button {
+                                set_label: "Set up OpenAI",
+                                add_css_class: "suggested-action",
+                                add_css_class: "pill",
+                                set_hexpand: false,
+                                connect_clicked => OnboardingMsg::SelectProvider(ProviderId::OpenAi),
+                            },
+
                             gtk::Button {
                                 set_label: "Set up Local (OpenAI Compatible)",
                                 add_css_class: "suggested-action",
@@ -14,6 +14,7 @@ pub enum AppearancePageMsg {
     MessageSpacingChanged(u32),
     MessageFontSizeChanged(f64),
     CodeFontSizeChanged(f64),
+    LoadRemoteImagesChanged(bool),
 }
 
 #[derive(Debug)]
@@ -105,6 +106,19 @@ impl Component for AppearancePage {
                     },
                 },
             },
+
+            adw::PreferencesGroup {
+                set_title: "Images",
+
+                adw::SwitchRow {
+                    set_title: "Load remote images",
+                    set_subtitle: "Show web images in replies right away. When off, each one loads only when you click it",
+                    set_active: model.settings.load_remote_images,
+                    connect_active_notify[sender] => move |row| {
+                        sender.input(AppearancePageMsg::LoadRemoteImagesChanged(row.is_active()));
+                    },
+                },
+            },
         }
     }
 
@@ -147,6 +161,10 @@ impl Component for AppearancePage {
                 self.settings.code_font_size = val as u32;
                 let _ = sender.output(AppearancePageOutput::SettingsChanged(self.settings.clone()));
             }
+            AppearancePageMsg::LoadRemoteImagesChanged(load) => {
+                self.settings.load_remote_images = load;
+                let _ = sender.output(AppearancePageOutput::SettingsChanged(self.settings.clone()));
+            }
         }
     }
 }
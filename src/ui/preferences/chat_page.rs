@@ -1,20 +1,39 @@
 use adw::prelude::*;
 use relm4::prelude::*;
 
-use crate::services::settings::AppSettings;
+use crate::models::Account;
+use crate::services::settings::{AppSettings, FallbackTarget};
 
 pub struct ChatPage {
     settings: AppSettings,
+    accounts: Vec<Account>,
     temp_scale: gtk::Scale,
     system_prompt_buffer: gtk::TextBuffer,
+    fallback_list: gtk::ListBox,
+    fallback_account: gtk::DropDown,
+    fallback_model: gtk::Entry,
+    title_account_row: adw::ComboRow,
+    title_model_row: adw::EntryRow,
+    /// The budget row's value, kept while no budget is set.
+    thinking_budget: u32,
 }
 
 #[derive(Debug)]
 pub enum ChatPageMsg {
     SetStreamingToggled(bool),
     SetSendWithEnter(bool),
+    SetToolsEnabled(bool),
     TemperatureChanged,
+    SetThinkingBudgetEnabled(bool),
+    ThinkingBudgetChanged(u32),
     SystemPromptChanged,
+    SetAccounts(Vec<Account>),
+    FallbackAccountSelected,
+    AddFallback,
+    MoveFallbackUp(usize),
+    RemoveFallback(usize),
+    SetAutoTitle(bool),
+    TitleModelChanged,
 }
 
 #[derive(Debug)]
@@ -24,7 +43,7 @@ pub enum ChatPageOutput {
 
 #[relm4::component(pub)]
 impl Component for ChatPage {
-    type Init = AppSettings;
+    type Init = (AppSettings, Vec<Account>);
     type Input = ChatPageMsg;
     type Output = ChatPageOutput;
     type CommandOutput = ();
@@ -56,6 +75,16 @@ impl Component for ChatPage {
                         sender.input(ChatPageMsg::SetSendWithEnter(row.is_active()));
                     },
                 },
+
+                #[name = "tools_row"]
+                adw::SwitchRow {
+                    set_title: "Allow tool use",
+                    set_subtitle: "Let models call built-in tools, such as the current date and time",
+                    set_active: model.settings.enable_tools,
+                    connect_active_notify[sender] => move |row| {
+                        sender.input(ChatPageMsg::SetToolsEnabled(row.is_active()));
+                    },
+                },
             },
 
             adw::PreferencesGroup {
@@ -66,6 +95,34 @@ impl Component for ChatPage {
                     set_title: "Temperature",
                     set_subtitle: "Controls response randomness (0.0 = focused, 2.0 = creative)",
                 },
+
+                adw::SwitchRow {
+                    set_title: "Set thinking budget",
+                    set_subtitle: "When off, Gemini decides how much to think and Claude doesn't think",
+                    set_active: model.settings.thinking_budget.is_some(),
+                    connect_active_notify[sender] => move |row| {
+                        sender.input(ChatPageMsg::SetThinkingBudgetEnabled(row.is_active()));
+                    },
+                },
+
+                #[name = "thinking_row"]
+                adw::SpinRow {
+                    set_title: "Thinking budget",
+                    set_subtitle: "Tokens Claude and Gemini may spend reasoning before answering (0 = off; Gemini Pro models always think a little)",
+                    #[watch]
+                    set_sensitive: model.settings.thinking_budget.is_some(),
+                    set_adjustment: Some(&gtk::Adjustment::new(
+                        model.thinking_budget as f64,
+                        0.0,
+                        32768.0,
+                        1024.0,
+                        4096.0,
+                        0.0,
+                    )),
+                    connect_value_notify[sender] => move |row| {
+                        sender.input(ChatPageMsg::ThinkingBudgetChanged(row.value() as u32));
+                    },
+                },
             },
 
             #[local_ref]
@@ -73,11 +130,48 @@ impl Component for ChatPage {
                 set_title: "System Prompt",
                 set_description: Some("Default instructions sent to the AI for all conversations"),
             },
+
+            adw::PreferencesGroup {
+                set_title: "Titles",
+
+                #[name = "auto_title_row"]
+                adw::SwitchRow {
+                    set_title: "Generate titles",
+                    set_subtitle: "After the first reply, ask a model for a short title. Renamed conversations keep their name",
+                    set_active: model.settings.auto_title,
+                    connect_active_notify[sender] => move |row| {
+                        sender.input(ChatPageMsg::SetAutoTitle(row.is_active()));
+                    },
+                },
+
+                #[local_ref]
+                title_account_row -> adw::ComboRow {
+                    set_title: "Title account",
+                    set_subtitle: "A small or local model keeps titles cheap",
+                    #[watch]
+                    set_sensitive: model.settings.auto_title,
+                    connect_selected_notify => ChatPageMsg::TitleModelChanged,
+                },
+
+                #[local_ref]
+                title_model_row -> adw::EntryRow {
+                    set_title: "Title model",
+                    #[watch]
+                    set_sensitive: model.settings.auto_title && model.settings.title_model.is_some(),
+                    connect_changed => ChatPageMsg::TitleModelChanged,
+                },
+            },
+
+            #[local_ref]
+            fallback_group -> adw::PreferencesGroup {
+                set_title: "Fallback Models",
+                set_description: Some("Tried in order when a request fails with a network error, rate limit or server error"),
+            },
         }
     }
 
     fn init(
-        settings: Self::Init,
+        (settings, accounts): Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -120,10 +214,70 @@ impl Component for ChatPage {
             sender_sp.send(ChatPageMsg::SystemPromptChanged).unwrap();
         });
 
+        // Fallback chain: the configured entries, then a row to add another
+        let fallback_group = adw::PreferencesGroup::new();
+        let fallback_list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .build();
+        fallback_list.add_css_class("boxed-list");
+
+        let fallback_account = gtk::DropDown::builder().valign(gtk::Align::Center).build();
+        let sender_fa = sender.input_sender().clone();
+        fallback_account.connect_selected_notify(move |_| {
+            sender_fa
+                .send(ChatPageMsg::FallbackAccountSelected)
+                .unwrap();
+        });
+
+        let fallback_model = gtk::Entry::builder()
+            .valign(gtk::Align::Center)
+            .width_chars(16)
+            .build();
+        let sender_fm = sender.input_sender().clone();
+        fallback_model.connect_activate(move |_| {
+            sender_fm.send(ChatPageMsg::AddFallback).unwrap();
+        });
+
+        let add_button = gtk::Button::builder()
+            .icon_name("list-add-symbolic")
+            .tooltip_text("Add Fallback")
+            .valign(gtk::Align::Center)
+            .build();
+        add_button.add_css_class("flat");
+        let sender_add = sender.input_sender().clone();
+        add_button.connect_clicked(move |_| {
+            sender_add.send(ChatPageMsg::AddFallback).unwrap();
+        });
+
+        let add_row = adw::ActionRow::builder().title("Add fallback").build();
+        add_row.add_suffix(&fallback_account);
+        add_row.add_suffix(&fallback_model);
+        add_row.add_suffix(&add_button);
+        let add_list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .margin_top(12)
+            .build();
+        add_list.add_css_class("boxed-list");
+        add_list.append(&add_row);
+
+        // Titles: "Same as conversation" or one of the accounts, plus a model for it
+        let title_account_row = adw::ComboRow::new();
+        let title_model_row = adw::EntryRow::new();
+        if let Some(target) = &settings.title_model {
+            title_model_row.set_text(&target.model);
+        }
+
         let model = Self {
+            thinking_budget: settings.thinking_budget.unwrap_or(0),
             settings,
+            accounts,
             temp_scale: temp_scale.clone(),
             system_prompt_buffer: system_prompt_buffer.clone(),
+            fallback_list: fallback_list.clone(),
+            fallback_account,
+            fallback_model,
+            title_account_row: title_account_row.clone(),
+            title_model_row: title_model_row.clone(),
         };
 
         let widgets = view_output!();
@@ -134,6 +288,11 @@ impl Component for ChatPage {
         // Add text view to system prompt group
         widgets.system_prompt_group.add(&system_prompt_view);
 
+        widgets.fallback_group.add(&fallback_list);
+        widgets.fallback_group.add(&add_list);
+        model.update_account_choices();
+        model.rebuild_fallback_list(&sender);
+
         ComponentParts { model, widgets }
     }
 
@@ -147,10 +306,25 @@ impl Component for ChatPage {
                 self.settings.send_with_enter = active;
                 let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
             }
+            ChatPageMsg::SetToolsEnabled(active) => {
+                self.settings.enable_tools = active;
+                let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+            }
             ChatPageMsg::TemperatureChanged => {
                 self.settings.temperature = self.temp_scale.value() as f32;
                 let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
             }
+            ChatPageMsg::SetThinkingBudgetEnabled(enabled) => {
+                self.settings.thinking_budget = enabled.then_some(self.thinking_budget);
+                let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+            }
+            ChatPageMsg::ThinkingBudgetChanged(budget) => {
+                self.thinking_budget = budget;
+                if self.settings.thinking_budget.is_some() {
+                    self.settings.thinking_budget = Some(budget);
+                    let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+                }
+            }
             ChatPageMsg::SystemPromptChanged => {
                 let start = self.system_prompt_buffer.start_iter();
                 let end = self.system_prompt_buffer.end_iter();
@@ -165,6 +339,175 @@ impl Component for ChatPage {
                 };
                 let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
             }
+            ChatPageMsg::SetAccounts(accounts) => {
+                self.accounts = accounts;
+                self.update_account_choices();
+                self.rebuild_fallback_list(&sender);
+            }
+            ChatPageMsg::FallbackAccountSelected => {
+                let placeholder = self
+                    .selected_fallback_account()
+                    .map(|account| account.default_model.as_str());
+                self.fallback_model.set_placeholder_text(placeholder);
+            }
+            ChatPageMsg::AddFallback => {
+                let Some(account) = self.selected_fallback_account() else {
+                    return;
+                };
+                // An empty entry means the account's default model
+                let model = self.fallback_model.text().trim().to_string();
+                let model = if model.is_empty() {
+                    account.default_model.clone()
+                } else {
+                    model
+                };
+                let target = FallbackTarget {
+                    account_id: account.id.clone(),
+                    model,
+                };
+                if self.settings.fallback_chain.contains(&target) {
+                    return;
+                }
+
+                self.settings.fallback_chain.push(target);
+                self.fallback_model.set_text("");
+                self.rebuild_fallback_list(&sender);
+                let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+            }
+            ChatPageMsg::MoveFallbackUp(index) => {
+                if index == 0 || index >= self.settings.fallback_chain.len() {
+                    return;
+                }
+                self.settings.fallback_chain.swap(index - 1, index);
+                self.rebuild_fallback_list(&sender);
+                let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+            }
+            ChatPageMsg::SetAutoTitle(active) => {
+                self.settings.auto_title = active;
+                let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+            }
+            ChatPageMsg::TitleModelChanged => {
+                // Selecting the account in `update_account_choices` lands here too
+                let account = (self.title_account_row.selected() as usize)
+                    .checked_sub(1)
+                    .and_then(|i| self.accounts.get(i));
+                let title_model = account.map(|account| {
+                    let model = self.title_model_row.text().trim().to_string();
+                    FallbackTarget {
+                        account_id: account.id.clone(),
+                        model: if model.is_empty() {
+                            account.default_model.clone()
+                        } else {
+                            model
+                        },
+                    }
+                });
+                if title_model != self.settings.title_model {
+                    self.settings.title_model = title_model;
+                    let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+                }
+            }
+            ChatPageMsg::RemoveFallback(index) => {
+                if index >= self.settings.fallback_chain.len() {
+                    return;
+                }
+                self.settings.fallback_chain.remove(index);
+                self.rebuild_fallback_list(&sender);
+                let _ = sender.output(ChatPageOutput::SettingsChanged(self.settings.clone()));
+            }
+        }
+    }
+}
+
+impl ChatPage {
+    fn selected_fallback_account(&self) -> Option<&Account> {
+        self.accounts.get(self.fallback_account.selected() as usize)
+    }
+
+    fn update_account_choices(&self) {
+        let labels: Vec<&str> = self.accounts.iter().map(|a| a.label.as_str()).collect();
+
+        let mut title_labels = vec!["Same as conversation"];
+        title_labels.extend(&labels);
+        let title_index = self
+            .settings
+            .title_model
+            .as_ref()
+            .and_then(|t| self.accounts.iter().position(|a| a.id == t.account_id))
+            .map_or(0, |i| i + 1);
+        self.title_account_row
+            .set_model(Some(&gtk::StringList::new(&title_labels)));
+        self.title_account_row.set_selected(title_index as u32);
+        self.title_model_row.set_text(
+            self.settings
+                .title_model
+                .as_ref()
+                .map_or("", |t| t.model.as_str()),
+        );
+
+        self.fallback_account
+            .set_model(Some(&gtk::StringList::new(&labels)));
+        self.fallback_account
+            .set_sensitive(!self.accounts.is_empty());
+        self.fallback_model.set_placeholder_text(
+            self.selected_fallback_account()
+                .map(|a| a.default_model.as_str()),
+        );
+    }
+
+    fn rebuild_fallback_list(&self, sender: &ComponentSender<Self>) {
+        while let Some(child) = self.fallback_list.first_child() {
+            self.fallback_list.remove(&child);
+        }
+
+        for (index, target) in self.settings.fallback_chain.iter().enumerate() {
+            let account = self.accounts.iter().find(|a| a.id == target.account_id);
+            let row = adw::ActionRow::builder()
+                .title(&target.model)
+                .subtitle(match account {
+                    Some(account) => {
+                        format!("{} - {}", account.label, account.provider.display_name())
+                    }
+                    None => "Account removed; skipped".to_string(),
+                })
+                .build();
+
+            let up_btn = gtk::Button::builder()
+                .icon_name("go-up-symbolic")
+                .tooltip_text("Try Earlier")
+                .valign(gtk::Align::Center)
+                .sensitive(index > 0)
+                .build();
+            up_btn.add_css_class("flat");
+            let sender_up = sender.input_sender().clone();
+            up_btn.connect_clicked(move |_| {
+                sender_up.send(ChatPageMsg::MoveFallbackUp(index)).unwrap();
+            });
+
+            let delete_btn = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text("Remove")
+                .valign(gtk::Align::Center)
+                .build();
+            delete_btn.add_css_class("flat");
+            delete_btn.add_css_class("error");
+            let sender_del = sender.input_sender().clone();
+            delete_btn.connect_clicked(move |_| {
+                sender_del.send(ChatPageMsg::RemoveFallback(index)).unwrap();
+            });
+
+            row.add_suffix(&up_btn);
+            row.add_suffix(&delete_btn);
+            self.fallback_list.append(&row);
+        }
+
+        if self.settings.fallback_chain.is_empty() {
+            let row = adw::ActionRow::builder()
+                .title("No fallback models")
+                .subtitle("Failed requests show an error instead")
+                .build();
+            row.add_css_class("dim-label");
+            self.fallback_list.append(&row);
         }
     }
 }
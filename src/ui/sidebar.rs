@@ -3,14 +3,18 @@ use chrono::{Datelike, Utc};
 use relm4::factory::FactoryVecDeque;
 use relm4::prelude::*;
 
-use crate::models::Conversation;
+use crate::models::{
+    Conversation, Folder, MessageSearchHit, Role, Tag, SNIPPET_MATCH_END, SNIPPET_MATCH_START,
+};
+use crate::ui::window::ask_for_name;
 
 // --- SidebarItem: discriminated union for date headers vs conversation rows ---
 
 #[derive(Debug, Clone)]
 pub enum SidebarItem {
-    Header(String), // "Pinned", "Today", "Yesterday", etc.
-    Conversation(Conversation),
+    Header(String),         // "Pinned", "Today", "Yesterday", etc.
+    Folder(Option<Folder>), // folder header when grouping by folder; `None` is "No Folder"
+    Conversation(Box<Conversation>),
 }
 
 // --- ConversationRow factory component ---
@@ -24,7 +28,9 @@ pub struct ConversationRow {
 pub enum ConversationRowMsg {}
 
 #[derive(Debug)]
-pub enum ConversationRowOutput {}
+pub enum ConversationRowOutput {
+    MoveToFolder(String, Option<String>), // conversation_id, folder_id (dropped on a header)
+}
 
 #[relm4::factory(pub)]
 impl FactoryComponent for ConversationRow {
@@ -51,7 +57,7 @@ impl FactoryComponent for ConversationRow {
         _index: &DynamicIndex,
         root: Self::Root,
         returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
-        _sender: FactorySender<Self>,
+        sender: FactorySender<Self>,
     ) -> Self::Widgets {
         match &self.item {
             SidebarItem::Header(label) => {
@@ -71,6 +77,52 @@ impl FactoryComponent for ConversationRow {
                 returned_widget.set_activatable(false);
                 returned_widget.set_selectable(false);
             }
+            SidebarItem::Folder(folder) => {
+                let header_box = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Horizontal)
+                    .spacing(6)
+                    .margin_top(8)
+                    .margin_bottom(2)
+                    .margin_start(4)
+                    .build();
+                header_box.add_css_class("dim-label");
+                header_box.add_css_class("sidebar-date-header");
+                let icon = gtk::Image::from_icon_name(if folder.is_some() {
+                    "folder-symbolic"
+                } else {
+                    "folder-open-symbolic"
+                });
+                icon.set_pixel_size(12);
+                header_box.append(&icon);
+                let header_label = gtk::Label::builder()
+                    .label(folder.as_ref().map_or("No Folder", |f| f.name.as_str()))
+                    .halign(gtk::Align::Start)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .build();
+                header_label.add_css_class("caption");
+                header_box.append(&header_label);
+                root.append(&header_box);
+
+                returned_widget.set_activatable(false);
+                returned_widget.set_selectable(false);
+
+                // Conversations dragged onto the header move into the folder
+                let drop_target =
+                    gtk::DropTarget::new(glib::Type::STRING, gtk::gdk::DragAction::MOVE);
+                let folder_id = folder.as_ref().map(|f| f.id.clone());
+                let output = sender.output_sender().clone();
+                drop_target.connect_drop(move |_, value, _, _| match value.get::<String>() {
+                    Ok(conversation_id) => {
+                        let _ = output.send(ConversationRowOutput::MoveToFolder(
+                            conversation_id,
+                            folder_id.clone(),
+                        ));
+                        true
+                    }
+                    Err(_) => false,
+                });
+                returned_widget.add_controller(drop_target);
+            }
             SidebarItem::Conversation(conv) => {
                 // Title
                 let title_box = gtk::Box::builder()
@@ -106,6 +158,25 @@ impl FactoryComponent for ConversationRow {
                 model_label.add_css_class("caption");
                 root.append(&model_label);
 
+                // Tags
+                if !conv.tags.is_empty() {
+                    let tags = conv
+                        .tags
+                        .iter()
+                        .map(|t| format!("#{}", t.name))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let tags_label = gtk::Label::builder()
+                        .label(tags)
+                        .halign(gtk::Align::Start)
+                        .ellipsize(gtk::pango::EllipsizeMode::End)
+                        .max_width_chars(35)
+                        .build();
+                    tags_label.add_css_class("caption");
+                    tags_label.add_css_class("sidebar-tags");
+                    root.append(&tags_label);
+                }
+
                 // Preview excerpt
                 if let Some(preview) = &conv.last_message_preview {
                     if !preview.is_empty() {
@@ -121,6 +192,17 @@ impl FactoryComponent for ConversationRow {
                         root.append(&preview_label);
                     }
                 }
+
+                // Drag onto a folder header to file the conversation
+                let drag_source = gtk::DragSource::new();
+                drag_source.set_actions(gtk::gdk::DragAction::MOVE);
+                let conversation_id = conv.id.clone();
+                drag_source.connect_prepare(move |_, _, _| {
+                    Some(gtk::gdk::ContentProvider::for_value(
+                        &conversation_id.to_value(),
+                    ))
+                });
+                root.add_controller(drag_source);
             }
         }
 
@@ -134,6 +216,30 @@ impl FactoryComponent for ConversationRow {
 pub struct Sidebar {
     pub conversations: FactoryVecDeque<ConversationRow>,
     search_term: String,
+    // Full-text matches in message content for the current search
+    search_hits: Vec<MessageSearchHit>,
+    search_results: gtk::ListBox,
+    search_results_header: gtk::Label,
+    // Folders and tags
+    folders: Vec<Folder>,
+    tags: Vec<Tag>,
+    group_by_folder: bool,
+    // Only conversations with this tag are shown
+    tag_filter: Option<String>,
+    tag_filter_box: gtk::Box,
+    tag_dropdown: gtk::DropDown,
+}
+
+/// A change to folders or tags, applied by the owner of the sidebar.
+#[derive(Debug)]
+pub enum OrganizeAction {
+    CreateFolder(String, Option<String>), // name, conversation to move into it
+    RenameFolder(String, String),         // folder_id, name
+    DeleteFolder(String),                 // folder_id
+    MoveToFolder(String, Option<String>), // conversation_id, folder_id (None to remove)
+    CreateTag(String, Option<String>),    // name, conversation to tag
+    DeleteTag(String),                    // tag_id
+    SetTag(String, String, bool),         // conversation_id, tag_id, tagged
 }
 
 #[derive(Debug)]
@@ -141,7 +247,7 @@ pub enum SidebarMsg {
     LoadConversations(Vec<Conversation>),
     NewChat,
     ConversationSelected(String),
-    AddConversation(Conversation),
+    AddConversation(Box<Conversation>),
     RemoveConversation(String),
     UpdateConversationTitle(String, String),
     // Context menu
@@ -154,6 +260,17 @@ pub enum SidebarMsg {
     DoRename(String, String), // id, new_title
     // Search
     SearchChanged(String),
+    SetSearchResults(String, Vec<MessageSearchHit>), // query, hits
+    SearchResultActivated(usize),
+    // Folders and tags
+    SetFoldersAndTags(Vec<Folder>, Vec<Tag>),
+    SetGroupByFolder(bool),
+    TagFilterChanged(u32), // dropdown position; 0 is "All Tags"
+    DeleteFilteredTag,
+    NewFolder(Option<String>), // conversation to move into it
+    RenameFolder(String),      // folder_id
+    NewTag(Option<String>),    // conversation to tag
+    Organize(OrganizeAction),
 }
 
 #[derive(Debug)]
@@ -164,6 +281,9 @@ pub enum SidebarOutput {
     RenameConversation(String, String), // id, new_title
     ExportConversation(String),         // id
     TogglePin(String, bool),            // id, new_pinned_state
+    SearchMessages(String),             // query
+    OpenSearchResult(String, String),   // conversation_id, message_id
+    Organize(OrganizeAction),
 }
 
 #[relm4::component(pub)]
@@ -188,6 +308,14 @@ impl Component for Sidebar {
                 set_title_widget = &adw::WindowTitle {
                     set_title: "Conversations",
                 },
+
+                pack_end = &gtk::ToggleButton {
+                    set_icon_name: "folder-symbolic",
+                    set_tooltip_text: Some("Group by Folder"),
+                    connect_toggled[sender] => move |button| {
+                        sender.input(SidebarMsg::SetGroupByFolder(button.is_active()));
+                    },
+                },
             },
 
             #[wrap(Some)]
@@ -197,7 +325,7 @@ impl Component for Sidebar {
 
                 #[name = "search_entry"]
                 gtk::SearchEntry {
-                    set_placeholder_text: Some("Search conversations..."),
+                    set_placeholder_text: Some("Search conversations and messages..."),
                     set_margin_start: 8,
                     set_margin_end: 8,
                     set_margin_top: 4,
@@ -207,14 +335,66 @@ impl Component for Sidebar {
                     },
                 },
 
+                #[local_ref]
+                tag_filter_box -> gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+                    set_margin_start: 8,
+                    set_margin_end: 8,
+                    set_margin_bottom: 4,
+                    set_visible: false,
+
+                    #[local_ref]
+                    tag_dropdown -> gtk::DropDown {
+                        set_hexpand: true,
+                        set_tooltip_text: Some("Filter by Tag"),
+                        connect_selected_notify[sender] => move |dropdown| {
+                            sender.input(SidebarMsg::TagFilterChanged(dropdown.selected()));
+                        },
+                    },
+
+                    #[name = "delete_tag_button"]
+                    gtk::Button {
+                        set_icon_name: "user-trash-symbolic",
+                        set_tooltip_text: Some("Delete Tag"),
+                        add_css_class: "flat",
+                        #[watch]
+                        set_sensitive: model.tag_filter.is_some(),
+                        connect_clicked => SidebarMsg::DeleteFilteredTag,
+                    },
+                },
+
                 gtk::ScrolledWindow {
                     set_hscrollbar_policy: gtk::PolicyType::Never,
                     set_vexpand: true,
 
-                    #[local_ref]
-                    conversation_list -> gtk::ListBox {
-                        set_selection_mode: gtk::SelectionMode::Single,
-                        add_css_class: "navigation-sidebar",
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+
+                        #[local_ref]
+                        conversation_list -> gtk::ListBox {
+                            set_selection_mode: gtk::SelectionMode::Single,
+                            add_css_class: "navigation-sidebar",
+                        },
+
+                        #[local_ref]
+                        search_results_header -> gtk::Label {
+                            set_label: "Messages",
+                            set_halign: gtk::Align::Start,
+                            set_margin_top: 8,
+                            set_margin_start: 10,
+                            set_visible: false,
+                            add_css_class: "dim-label",
+                            add_css_class: "caption",
+                            add_css_class: "sidebar-date-header",
+                        },
+
+                        #[local_ref]
+                        search_results -> gtk::ListBox {
+                            set_selection_mode: gtk::SelectionMode::None,
+                            set_visible: false,
+                            add_css_class: "navigation-sidebar",
+                        },
                     },
                 },
             },
@@ -228,16 +408,39 @@ impl Component for Sidebar {
     ) -> ComponentParts<Self> {
         let conversations = FactoryVecDeque::builder()
             .launch(gtk::ListBox::default())
-            .detach();
+            .forward(sender.input_sender(), |output| match output {
+                ConversationRowOutput::MoveToFolder(conversation_id, folder_id) => {
+                    SidebarMsg::Organize(OrganizeAction::MoveToFolder(conversation_id, folder_id))
+                }
+            });
+
+        let search_results = gtk::ListBox::new();
+        let search_results_header = gtk::Label::new(None);
+        let tag_filter_box = gtk::Box::default();
+        let tag_dropdown = gtk::DropDown::from_strings(&["All Tags"]);
 
         let model = Self {
             conversations,
             search_term: String::new(),
+            search_hits: Vec::new(),
+            search_results: search_results.clone(),
+            search_results_header: search_results_header.clone(),
+            folders: Vec::new(),
+            tags: Vec::new(),
+            group_by_folder: false,
+            tag_filter: None,
+            tag_filter_box: tag_filter_box.clone(),
+            tag_dropdown: tag_dropdown.clone(),
         };
 
         let conversation_list = model.conversations.widget();
         let widgets = view_output!();
 
+        let sender_results = sender.clone();
+        search_results.connect_row_activated(move |_, row| {
+            sender_results.input(SidebarMsg::SearchResultActivated(row.index() as usize));
+        });
+
         // Connect row-activated signal properly
         let conv_list = model.conversations.widget().clone();
         let sender_clone = sender.clone();
@@ -266,34 +469,7 @@ impl Component for Sidebar {
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
         match msg {
             SidebarMsg::LoadConversations(conversations) => {
-                let mut guard = self.conversations.guard();
-                guard.clear();
-
-                // Separate pinned and unpinned
-                let (pinned, unpinned): (Vec<_>, Vec<_>) =
-                    conversations.into_iter().partition(|c| c.pinned);
-
-                // Add pinned group
-                if !pinned.is_empty() {
-                    guard.push_back(SidebarItem::Header("Pinned".to_string()));
-                    for conv in pinned {
-                        guard.push_back(SidebarItem::Conversation(conv));
-                    }
-                }
-
-                // Group unpinned by date
-                let mut current_group: Option<String> = None;
-                for conv in unpinned {
-                    let group = date_group(&conv.updated_at);
-                    if current_group.as_deref() != Some(group) {
-                        current_group = Some(group.to_string());
-                        guard.push_back(SidebarItem::Header(group.to_string()));
-                    }
-                    guard.push_back(SidebarItem::Conversation(conv));
-                }
-
-                drop(guard);
-                self.apply_search_filter();
+                self.rebuild(conversations);
             }
             SidebarMsg::NewChat => {
                 let _ = sender.output(SidebarOutput::NewChat);
@@ -311,6 +487,13 @@ impl Component for Sidebar {
                 }
             }
             SidebarMsg::AddConversation(conversation) => {
+                if self.group_by_folder {
+                    let mut conversations = self.current_conversations();
+                    conversations.insert(0, *conversation);
+                    self.rebuild(conversations);
+                    return;
+                }
+
                 // Insert after the first header ("Today" or "Pinned")
                 let mut guard = self.conversations.guard();
 
@@ -388,75 +571,103 @@ impl Component for Sidebar {
             }
             SidebarMsg::ShowContextMenu(x, y, index) => {
                 // Collect info from guard first, then drop it
-                let (is_header, is_pinned) = {
+                let item = {
                     let guard = self.conversations.guard();
-                    let is_header = guard
-                        .get(index)
-                        .map(|r| matches!(&r.item, SidebarItem::Header(_)))
-                        .unwrap_or(true);
-                    let is_pinned = guard
-                        .get(index)
-                        .map(|r| matches!(&r.item, SidebarItem::Conversation(c) if c.pinned))
-                        .unwrap_or(false);
-                    (is_header, is_pinned)
+                    guard.get(index).map(|r| r.item.clone())
                 };
 
-                if is_header {
-                    return;
-                }
-
-                let list_widget = self.conversations.widget();
-
                 let menu = gio::Menu::new();
-                if is_pinned {
-                    menu.append(Some("Unpin"), Some("sidebar.toggle-pin"));
-                } else {
-                    menu.append(Some("Pin"), Some("sidebar.toggle-pin"));
-                }
-                menu.append(Some("Rename"), Some("sidebar.rename"));
-                menu.append(Some("Export"), Some("sidebar.export"));
-                menu.append(Some("Delete"), Some("sidebar.delete"));
-
                 let action_group = gio::SimpleActionGroup::new();
 
-                let sender_pin = sender.input_sender().clone();
-                let pin_action = gio::SimpleAction::new("toggle-pin", None);
-                let idx = index;
-                pin_action.connect_activate(move |_, _| {
-                    sender_pin.send(SidebarMsg::TogglePin(idx)).unwrap();
-                });
-                action_group.add_action(&pin_action);
-
-                let sender_rename = sender.input_sender().clone();
-                let rename_action = gio::SimpleAction::new("rename", None);
-                let idx = index;
-                rename_action.connect_activate(move |_, _| {
-                    sender_rename
-                        .send(SidebarMsg::RenameConversation(idx))
-                        .unwrap();
-                });
-                action_group.add_action(&rename_action);
-
-                let sender_export = sender.input_sender().clone();
-                let export_action = gio::SimpleAction::new("export", None);
-                let idx = index;
-                export_action.connect_activate(move |_, _| {
-                    sender_export
-                        .send(SidebarMsg::ExportConversation(idx))
-                        .unwrap();
-                });
-                action_group.add_action(&export_action);
-
-                let sender_delete = sender.input_sender().clone();
-                let delete_action = gio::SimpleAction::new("delete", None);
-                let idx = index;
-                delete_action.connect_activate(move |_, _| {
-                    sender_delete
-                        .send(SidebarMsg::DeleteConversation(idx))
-                        .unwrap();
-                });
-                action_group.add_action(&delete_action);
+                match item {
+                    Some(SidebarItem::Conversation(conv)) => {
+                        if conv.pinned {
+                            menu.append(Some("Unpin"), Some("sidebar.toggle-pin"));
+                        } else {
+                            menu.append(Some("Pin"), Some("sidebar.toggle-pin"));
+                        }
+                        menu.append(Some("Rename"), Some("sidebar.rename"));
+                        menu.append_submenu(Some("Move to Folder"), &self.folder_menu(&conv));
+                        menu.append_submenu(Some("Tags"), &self.tag_menu());
+                        menu.append(Some("Export"), Some("sidebar.export"));
+                        menu.append(Some("Delete"), Some("sidebar.delete"));
+
+                        let idx = index;
+                        let sender_pin = sender.input_sender().clone();
+                        let pin_action = gio::SimpleAction::new("toggle-pin", None);
+                        pin_action.connect_activate(move |_, _| {
+                            sender_pin.send(SidebarMsg::TogglePin(idx)).unwrap();
+                        });
+                        action_group.add_action(&pin_action);
+
+                        let sender_rename = sender.input_sender().clone();
+                        let rename_action = gio::SimpleAction::new("rename", None);
+                        rename_action.connect_activate(move |_, _| {
+                            sender_rename
+                                .send(SidebarMsg::RenameConversation(idx))
+                                .unwrap();
+                        });
+                        action_group.add_action(&rename_action);
+
+                        let sender_export = sender.input_sender().clone();
+                        let export_action = gio::SimpleAction::new("export", None);
+                        export_action.connect_activate(move |_, _| {
+                            sender_export
+                                .send(SidebarMsg::ExportConversation(idx))
+                                .unwrap();
+                        });
+                        action_group.add_action(&export_action);
+
+                        let sender_delete = sender.input_sender().clone();
+                        let delete_action = gio::SimpleAction::new("delete", None);
+                        delete_action.connect_activate(move |_, _| {
+                            sender_delete
+                                .send(SidebarMsg::DeleteConversation(idx))
+                                .unwrap();
+                        });
+                        action_group.add_action(&delete_action);
+
+                        self.add_organize_actions(&action_group, &conv, &sender);
+                    }
+                    Some(SidebarItem::Folder(folder)) => {
+                        if let Some(folder) = folder {
+                            menu.append(Some("Rename Folder"), Some("sidebar.rename-folder"));
+                            menu.append(Some("Delete Folder"), Some("sidebar.delete-folder"));
+
+                            let sender_rename = sender.input_sender().clone();
+                            let folder_id = folder.id.clone();
+                            let rename_action = gio::SimpleAction::new("rename-folder", None);
+                            rename_action.connect_activate(move |_, _| {
+                                sender_rename
+                                    .send(SidebarMsg::RenameFolder(folder_id.clone()))
+                                    .unwrap();
+                            });
+                            action_group.add_action(&rename_action);
+
+                            let sender_delete = sender.input_sender().clone();
+                            let delete_action = gio::SimpleAction::new("delete-folder", None);
+                            delete_action.connect_activate(move |_, _| {
+                                sender_delete
+                                    .send(SidebarMsg::Organize(OrganizeAction::DeleteFolder(
+                                        folder.id.clone(),
+                                    )))
+                                    .unwrap();
+                            });
+                            action_group.add_action(&delete_action);
+                        }
+                        menu.append(Some("New Folder…"), Some("sidebar.new-folder"));
+
+                        let sender_new = sender.input_sender().clone();
+                        let new_action = gio::SimpleAction::new("new-folder", None);
+                        new_action.connect_activate(move |_, _| {
+                            sender_new.send(SidebarMsg::NewFolder(None)).unwrap();
+                        });
+                        action_group.add_action(&new_action);
+                    }
+                    _ => return,
+                }
 
+                let list_widget = self.conversations.widget();
                 list_widget.insert_action_group("sidebar", Some(&action_group));
 
                 let popover = gtk::PopoverMenu::from_model(Some(&menu));
@@ -489,44 +700,18 @@ impl Component for Sidebar {
                 drop(guard);
 
                 if let Some((id, current_title)) = conv_data {
-                    // Show rename dialog using adw::AlertDialog
-                    let dialog = adw::AlertDialog::builder()
-                        .heading("Rename Conversation")
-                        .body("Enter a new name:")
-                        .build();
-
-                    let entry = gtk::Entry::builder()
-                        .text(&current_title)
-                        .activates_default(true)
-                        .build();
-
-                    use adw::prelude::AlertDialogExt;
-                    dialog.set_extra_child(Some(&entry));
-                    dialog.add_response("cancel", "Cancel");
-                    dialog.add_response("rename", "Rename");
-                    dialog.set_response_appearance("rename", adw::ResponseAppearance::Suggested);
-                    dialog.set_default_response(Some("rename"));
-                    dialog.set_close_response("cancel");
-
                     let sender_dlg = sender.input_sender().clone();
-                    let conv_id = id;
-                    dialog.connect_response(None, move |_dialog, response| {
-                        if response == "rename" {
-                            let new_title = entry.text().to_string();
-                            if !new_title.trim().is_empty() {
-                                sender_dlg
-                                    .send(SidebarMsg::DoRename(conv_id.clone(), new_title))
-                                    .unwrap();
-                            }
-                        }
-                    });
-
-                    // Present dialog on the widget's window
-                    use adw::prelude::AdwDialogExt;
-                    if let Some(window) = root.root().and_then(|r| r.downcast::<gtk::Window>().ok())
-                    {
-                        dialog.present(Some(&window));
-                    }
+                    ask_for_name(
+                        root,
+                        "Rename Conversation",
+                        &current_title,
+                        "Rename",
+                        move |new_title| {
+                            sender_dlg
+                                .send(SidebarMsg::DoRename(id.clone(), new_title))
+                                .unwrap();
+                        },
+                    );
                 }
             }
             SidebarMsg::DoRename(id, new_title) => {
@@ -594,14 +779,361 @@ impl Component for Sidebar {
             SidebarMsg::SearchChanged(term) => {
                 self.search_term = term.to_lowercase();
                 self.apply_search_filter();
+
+                let query = term.trim();
+                if query.is_empty() {
+                    self.show_search_results(Vec::new());
+                } else {
+                    let _ = sender.output(SidebarOutput::SearchMessages(query.to_string()));
+                }
+            }
+            SidebarMsg::SetSearchResults(query, hits) => {
+                // Drop results for a query the user has since changed
+                if query.to_lowercase() == self.search_term.trim() {
+                    self.show_search_results(hits);
+                }
+            }
+            SidebarMsg::SearchResultActivated(index) => {
+                if let Some(hit) = self.search_hits.get(index) {
+                    let _ = sender.output(SidebarOutput::OpenSearchResult(
+                        hit.conversation_id.clone(),
+                        hit.message_id.clone(),
+                    ));
+                }
+            }
+            SidebarMsg::SetFoldersAndTags(folders, tags) => {
+                self.folders = folders;
+                self.tags = tags;
+                if self
+                    .tag_filter
+                    .as_ref()
+                    .is_some_and(|id| !self.tags.iter().any(|t| &t.id == id))
+                {
+                    self.tag_filter = None;
+                }
+
+                let mut names = vec!["All Tags"];
+                names.extend(self.tags.iter().map(|t| t.name.as_str()));
+                self.tag_dropdown
+                    .set_model(Some(&gtk::StringList::new(&names)));
+                let selected = self
+                    .tag_filter
+                    .as_ref()
+                    .and_then(|id| self.tags.iter().position(|t| &t.id == id))
+                    .map_or(0, |i| i + 1);
+                self.tag_dropdown.set_selected(selected as u32);
+                self.tag_filter_box.set_visible(!self.tags.is_empty());
+
+                if self.group_by_folder {
+                    self.rebuild(self.current_conversations());
+                }
+            }
+            SidebarMsg::SetGroupByFolder(group_by_folder) => {
+                self.group_by_folder = group_by_folder;
+                self.rebuild(self.current_conversations());
+            }
+            SidebarMsg::TagFilterChanged(position) => {
+                self.tag_filter = (position as usize)
+                    .checked_sub(1)
+                    .and_then(|i| self.tags.get(i))
+                    .map(|t| t.id.clone());
+                self.apply_search_filter();
+            }
+            SidebarMsg::DeleteFilteredTag => {
+                if let Some(tag_id) = self.tag_filter.clone() {
+                    let _ =
+                        sender.output(SidebarOutput::Organize(OrganizeAction::DeleteTag(tag_id)));
+                }
+            }
+            SidebarMsg::NewFolder(conversation_id) => {
+                let sender_dlg = sender.input_sender().clone();
+                ask_for_name(root, "New Folder", "", "Create", move |name| {
+                    sender_dlg
+                        .send(SidebarMsg::Organize(OrganizeAction::CreateFolder(
+                            name,
+                            conversation_id.clone(),
+                        )))
+                        .unwrap();
+                });
+            }
+            SidebarMsg::RenameFolder(folder_id) => {
+                let Some(folder) = self.folders.iter().find(|f| f.id == folder_id) else {
+                    return;
+                };
+                let sender_dlg = sender.input_sender().clone();
+                ask_for_name(root, "Rename Folder", &folder.name, "Rename", move |name| {
+                    sender_dlg
+                        .send(SidebarMsg::Organize(OrganizeAction::RenameFolder(
+                            folder_id.clone(),
+                            name,
+                        )))
+                        .unwrap();
+                });
+            }
+            SidebarMsg::NewTag(conversation_id) => {
+                let sender_dlg = sender.input_sender().clone();
+                ask_for_name(root, "New Tag", "", "Create", move |name| {
+                    sender_dlg
+                        .send(SidebarMsg::Organize(OrganizeAction::CreateTag(
+                            name,
+                            conversation_id.clone(),
+                        )))
+                        .unwrap();
+                });
+            }
+            SidebarMsg::Organize(action) => {
+                let _ = sender.output(SidebarOutput::Organize(action));
             }
         }
     }
 }
 
 impl Sidebar {
+    /// Rebuild the list, grouped by folder or by pinned state and date.
+    fn rebuild(&mut self, conversations: Vec<Conversation>) {
+        let mut guard = self.conversations.guard();
+        guard.clear();
+
+        if self.group_by_folder {
+            // Every folder gets a header, even when empty, so conversations can be dropped on it
+            for folder in &self.folders {
+                guard.push_back(SidebarItem::Folder(Some(folder.clone())));
+                for conv in conversations
+                    .iter()
+                    .filter(|c| c.folder_id.as_ref() == Some(&folder.id))
+                {
+                    guard.push_back(SidebarItem::Conversation(Box::new(conv.clone())));
+                }
+            }
+
+            guard.push_back(SidebarItem::Folder(None));
+            for conv in conversations.into_iter().filter(|c| {
+                !self
+                    .folders
+                    .iter()
+                    .any(|f| c.folder_id.as_ref() == Some(&f.id))
+            }) {
+                guard.push_back(SidebarItem::Conversation(Box::new(conv)));
+            }
+        } else {
+            // Separate pinned and unpinned
+            let (pinned, unpinned): (Vec<_>, Vec<_>) =
+                conversations.into_iter().partition(|c| c.pinned);
+
+            // Add pinned group
+            if !pinned.is_empty() {
+                guard.push_back(SidebarItem::Header("Pinned".to_string()));
+                for conv in pinned {
+                    guard.push_back(SidebarItem::Conversation(Box::new(conv)));
+                }
+            }
+
+            // Group unpinned by date
+            let mut current_group: Option<String> = None;
+            for conv in unpinned {
+                let group = date_group(&conv.updated_at);
+                if current_group.as_deref() != Some(group) {
+                    current_group = Some(group.to_string());
+                    guard.push_back(SidebarItem::Header(group.to_string()));
+                }
+                guard.push_back(SidebarItem::Conversation(Box::new(conv)));
+            }
+        }
+
+        drop(guard);
+        self.apply_search_filter();
+    }
+
+    /// The listed conversations, most recent first.
+    fn current_conversations(&self) -> Vec<Conversation> {
+        let mut conversations: Vec<Conversation> = self
+            .conversations
+            .iter()
+            .filter_map(|row| match &row.item {
+                SidebarItem::Conversation(conv) => Some(conv.as_ref().clone()),
+                _ => None,
+            })
+            .collect();
+        conversations.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| b.updated_at.cmp(&a.updated_at))
+        });
+        conversations
+    }
+
+    /// "Move to Folder" submenu for a conversation's context menu.
+    fn folder_menu(&self, conv: &Conversation) -> gio::Menu {
+        let menu = gio::Menu::new();
+        for (i, folder) in self.folders.iter().enumerate() {
+            if conv.folder_id.as_ref() != Some(&folder.id) {
+                menu.append(Some(&folder.name), Some(&format!("sidebar.move-to-{}", i)));
+            }
+        }
+        if conv.folder_id.is_some() {
+            menu.append(
+                Some("Remove from Folder"),
+                Some("sidebar.remove-from-folder"),
+            );
+        }
+        menu.append(Some("New Folder…"), Some("sidebar.new-folder"));
+        menu
+    }
+
+    /// "Tags" submenu for a conversation's context menu, with a check per tag.
+    fn tag_menu(&self) -> gio::Menu {
+        let menu = gio::Menu::new();
+        for (i, tag) in self.tags.iter().enumerate() {
+            menu.append(Some(&tag.name), Some(&format!("sidebar.tag-{}", i)));
+        }
+        menu.append(Some("New Tag…"), Some("sidebar.new-tag"));
+        menu
+    }
+
+    /// Actions behind `folder_menu` and `tag_menu`.
+    fn add_organize_actions(
+        &self,
+        action_group: &gio::SimpleActionGroup,
+        conv: &Conversation,
+        sender: &ComponentSender<Self>,
+    ) {
+        for (i, folder) in self.folders.iter().enumerate() {
+            let action = gio::SimpleAction::new(&format!("move-to-{}", i), None);
+            let sender = sender.input_sender().clone();
+            let conversation_id = conv.id.clone();
+            let folder_id = folder.id.clone();
+            action.connect_activate(move |_, _| {
+                sender
+                    .send(SidebarMsg::Organize(OrganizeAction::MoveToFolder(
+                        conversation_id.clone(),
+                        Some(folder_id.clone()),
+                    )))
+                    .unwrap();
+            });
+            action_group.add_action(&action);
+        }
+
+        let remove_action = gio::SimpleAction::new("remove-from-folder", None);
+        let sender_remove = sender.input_sender().clone();
+        let conversation_id = conv.id.clone();
+        remove_action.connect_activate(move |_, _| {
+            sender_remove
+                .send(SidebarMsg::Organize(OrganizeAction::MoveToFolder(
+                    conversation_id.clone(),
+                    None,
+                )))
+                .unwrap();
+        });
+        action_group.add_action(&remove_action);
+
+        let new_folder_action = gio::SimpleAction::new("new-folder", None);
+        let sender_folder = sender.input_sender().clone();
+        let conversation_id = conv.id.clone();
+        new_folder_action.connect_activate(move |_, _| {
+            sender_folder
+                .send(SidebarMsg::NewFolder(Some(conversation_id.clone())))
+                .unwrap();
+        });
+        action_group.add_action(&new_folder_action);
+
+        // Stateful actions so the menu shows which tags are set
+        for (i, tag) in self.tags.iter().enumerate() {
+            let tagged = conv.tags.iter().any(|t| t.id == tag.id);
+            let action =
+                gio::SimpleAction::new_stateful(&format!("tag-{}", i), None, &tagged.to_variant());
+            let sender = sender.input_sender().clone();
+            let conversation_id = conv.id.clone();
+            let tag_id = tag.id.clone();
+            action.connect_activate(move |_, _| {
+                sender
+                    .send(SidebarMsg::Organize(OrganizeAction::SetTag(
+                        conversation_id.clone(),
+                        tag_id.clone(),
+                        !tagged,
+                    )))
+                    .unwrap();
+            });
+            action_group.add_action(&action);
+        }
+
+        let new_tag_action = gio::SimpleAction::new("new-tag", None);
+        let sender_tag = sender.input_sender().clone();
+        let conversation_id = conv.id.clone();
+        new_tag_action.connect_activate(move |_, _| {
+            sender_tag
+                .send(SidebarMsg::NewTag(Some(conversation_id.clone())))
+                .unwrap();
+        });
+        action_group.add_action(&new_tag_action);
+    }
+
+    fn show_search_results(&mut self, hits: Vec<MessageSearchHit>) {
+        while let Some(child) = self.search_results.first_child() {
+            self.search_results.remove(&child);
+        }
+
+        for hit in &hits {
+            let row = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .spacing(2)
+                .margin_top(6)
+                .margin_bottom(6)
+                .margin_start(6)
+                .margin_end(6)
+                .build();
+
+            let title_label = gtk::Label::builder()
+                .label(&hit.conversation_title)
+                .halign(gtk::Align::Start)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .max_width_chars(30)
+                .build();
+            title_label.add_css_class("heading");
+            row.append(&title_label);
+
+            let snippet_label = gtk::Label::builder()
+                .use_markup(true)
+                .label(snippet_markup(&hit.snippet))
+                .halign(gtk::Align::Start)
+                .xalign(0.0)
+                .wrap(true)
+                .wrap_mode(gtk::pango::WrapMode::WordChar)
+                .lines(3)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .max_width_chars(35)
+                .build();
+            snippet_label.add_css_class("caption");
+            row.append(&snippet_label);
+
+            let meta_label = gtk::Label::builder()
+                .label(format!(
+                    "{} · {}",
+                    match hit.role {
+                        Role::User => "You",
+                        Role::Assistant => "Assistant",
+                    },
+                    hit.created_at
+                        .with_timezone(&chrono::Local)
+                        .format("%b %e, %Y")
+                ))
+                .halign(gtk::Align::Start)
+                .build();
+            meta_label.add_css_class("dim-label");
+            meta_label.add_css_class("caption");
+            row.append(&meta_label);
+
+            self.search_results.append(&row);
+        }
+
+        let has_hits = !hits.is_empty();
+        self.search_results.set_visible(has_hits);
+        self.search_results_header.set_visible(has_hits);
+        self.search_hits = hits;
+    }
+
     fn apply_search_filter(&mut self) {
         let is_searching = !self.search_term.is_empty();
+        let is_filtering = is_searching || self.tag_filter.is_some();
 
         // Collect filter data while holding guard
         let filter_data: Vec<(usize, bool, bool)> = {
@@ -610,13 +1142,15 @@ impl Sidebar {
                 .filter_map(|i| {
                     guard.get(i).map(|row_data| {
                         match &row_data.item {
-                            SidebarItem::Header(_) => (i, true, true), // is_header = true
+                            SidebarItem::Header(_) | SidebarItem::Folder(_) => (i, true, true), // is_header = true
                             SidebarItem::Conversation(conv) => {
-                                let visible = if is_searching {
-                                    conv.title.to_lowercase().contains(&self.search_term)
-                                } else {
-                                    true
+                                let matches_search = !is_searching
+                                    || conv.title.to_lowercase().contains(&self.search_term);
+                                let matches_tag = match &self.tag_filter {
+                                    Some(tag_id) => conv.tags.iter().any(|t| &t.id == tag_id),
+                                    None => true,
                                 };
+                                let visible = matches_search && matches_tag;
                                 (i, visible, false) // is_header = false
                             }
                         }
@@ -625,11 +1159,10 @@ impl Sidebar {
                 .collect()
         };
 
-        // When searching, hide headers; when not searching, show headers only if
-        // they have visible conversations after them
+        // When searching or filtering by tag, hide headers; otherwise show everything
         let list_widget = self.conversations.widget();
 
-        if is_searching {
+        if is_filtering {
             for (i, visible, is_header) in &filter_data {
                 if let Some(row) = list_widget.row_at_index(*i as i32) {
                     if *is_header {
@@ -640,7 +1173,7 @@ impl Sidebar {
                 }
             }
         } else {
-            // Show all when not searching
+            // Show all when not filtering
             for (i, visible, _) in &filter_data {
                 if let Some(row) = list_widget.row_at_index(*i as i32) {
                     row.set_visible(*visible);
@@ -666,3 +1199,23 @@ fn date_group(dt: &chrono::DateTime<Utc>) -> &'static str {
         "Older"
     }
 }
+
+/// Pango markup for a search snippet, with the matched terms in bold.
+pub fn snippet_markup(snippet: &str) -> String {
+    let flattened = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    // Escape between the markers; escaping would mangle the control characters themselves
+    let mut markup = String::new();
+    let mut rest = flattened.as_str();
+    while let Some(pos) = rest.find([SNIPPET_MATCH_START, SNIPPET_MATCH_END]) {
+        markup.push_str(&gtk::glib::markup_escape_text(&rest[..pos]));
+        let marker = rest[pos..].chars().next().unwrap_or_default();
+        markup.push_str(if marker == SNIPPET_MATCH_START {
+            "<b>"
+        } else {
+            "</b>"
+        });
+        rest = &rest[pos + marker.len_utf8()..];
+    }
+    markup.push_str(&gtk::glib::markup_escape_text(rest));
+    markup
+}
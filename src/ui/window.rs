@@ -40,15 +40,16 @@ pub fn create_preferences_window(
             .collect::<Vec<_>>()
     };
 
-    let accounts_page = AccountsPage::builder()
-        .launch(accounts)
-        .forward(sender, |output| match output {
-            AccountsPageOutput::AddAccount => AppMsg::OpenAccountSetup,
-            AccountsPageOutput::DeleteAccount(id) => AppMsg::DeleteAccountFromPrefs(id),
-        });
+    let accounts_page =
+        AccountsPage::builder()
+            .launch(accounts.clone())
+            .forward(sender, |output| match output {
+                AccountsPageOutput::AddAccount => AppMsg::OpenAccountSetup,
+                AccountsPageOutput::DeleteAccount(id) => AppMsg::DeleteAccountFromPrefs(id),
+            });
 
     let chat_page = ChatPage::builder()
-        .launch(settings.clone())
+        .launch((settings.clone(), accounts))
         .forward(sender, |output| match output {
             ChatPageOutput::SettingsChanged(s) => AppMsg::SettingsChanged(s),
         });
@@ -105,11 +106,11 @@ pub fn create_shortcuts_window(parent: &adw::ApplicationWindow) {
         .build();
     general_group.add_shortcut(&shortcuts_help);
 
-    let quick_switch = gtk::ShortcutsShortcut::builder()
-        .title("Quick account/model switch")
+    let command_palette = gtk::ShortcutsShortcut::builder()
+        .title("Command palette")
         .accelerator("<Control>k")
         .build();
-    general_group.add_shortcut(&quick_switch);
+    general_group.add_shortcut(&command_palette);
 
     // Chat section
     let chat_group = gtk::ShortcutsGroup::builder().title("Chat").build();
@@ -217,3 +218,77 @@ pub fn create_onboarding(
 
     onboarding
 }
+
+/// Ask for a name in an alert dialog and pass the trimmed, non-empty result to `on_name`.
+pub fn ask_for_name(
+    widget: &impl IsA<gtk::Widget>,
+    heading: &str,
+    initial: &str,
+    confirm_label: &str,
+    on_name: impl Fn(String) + 'static,
+) {
+    use adw::prelude::{AdwDialogExt, AlertDialogExt};
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(heading)
+        .body("Enter a name:")
+        .build();
+
+    let entry = gtk::Entry::builder()
+        .text(initial)
+        .activates_default(true)
+        .build();
+
+    dialog.set_extra_child(Some(&entry));
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("confirm", confirm_label);
+    dialog.set_response_appearance("confirm", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("confirm"));
+    dialog.set_close_response("cancel");
+
+    dialog.connect_response(None, move |_dialog, response| {
+        if response == "confirm" {
+            let name = entry.text().trim().to_string();
+            if !name.is_empty() {
+                on_name(name);
+            }
+        }
+    });
+
+    // Present dialog on the widget's window
+    if let Some(window) = widget.root().and_then(|r| r.downcast::<gtk::Window>().ok()) {
+        dialog.present(Some(&window));
+    }
+}
+
+/// Ask before a destructive action, calling `on_confirm` only if the user agrees.
+/// Cancel is the default, so pressing Enter doesn't confirm by accident.
+pub fn confirm_destructive(
+    widget: &impl IsA<gtk::Widget>,
+    heading: &str,
+    body: &str,
+    confirm_label: &str,
+    on_confirm: impl Fn() + 'static,
+) {
+    use adw::prelude::{AdwDialogExt, AlertDialogExt};
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(heading)
+        .body(body)
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("confirm", confirm_label);
+    dialog.set_response_appearance("confirm", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+
+    dialog.connect_response(None, move |_dialog, response| {
+        if response == "confirm" {
+            on_confirm();
+        }
+    });
+
+    if let Some(window) = widget.root().and_then(|r| r.downcast::<gtk::Window>().ok()) {
+        dialog.present(Some(&window));
+    }
+}